@@ -1,10 +1,26 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[arg(
+        long = "config",
+        value_name = "FILE",
+        global = true,
+        help = "Config file (defaults to ./mpdbtoolconfig.toml or ~/.config/mpdbtool/config.toml)"
+    )]
+    pub config: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "profile",
+        value_name = "NAME",
+        global = true,
+        help = "Use the settings of a [profiles.NAME] table in the config file"
+    )]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -19,6 +35,264 @@ pub enum Commands {
         #[command(subcommand)]
         command: XmlCommands,
     },
+    /// Search the master data for concerts
+    #[command(arg_required_else_help = true)]
+    Query {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            short = 's',
+            long = "song",
+            help = "Concerts where this song (or one of its aliases) was played"
+        )]
+        song: Option<String>,
+
+        #[arg(long = "venue", help = "Concerts at a venue whose name contains this text")]
+        venue: Option<String>,
+
+        #[arg(long = "city", help = "Concerts in a city whose name contains this text")]
+        city: Option<String>,
+
+        #[arg(long = "country", help = "Concerts in a country whose name contains this text")]
+        country: Option<String>,
+
+        #[arg(long = "from", help = "Concerts on or after this date (YYYY-MM-DD)")]
+        from: Option<chrono::NaiveDate>,
+
+        #[arg(long = "to", help = "Concerts on or before this date (YYYY-MM-DD)")]
+        to: Option<chrono::NaiveDate>,
+
+        #[arg(
+            short = 't',
+            long = "tour",
+            help = "Concerts on a tour whose name contains this text"
+        )]
+        tour: Option<String>,
+
+        #[arg(
+            long = "cover-of",
+            value_name = "ARTIST",
+            help = "Concerts where a song by this artist was covered"
+        )]
+        cover_of: Option<String>,
+
+        #[arg(long = "has-notes", help = "Concerts with notes on the concert or any of its songs")]
+        has_notes: bool,
+
+        #[arg(long = "status", help = "Concerts with this setlist status")]
+        status: Option<String>,
+
+        #[arg(
+            long = "musician",
+            value_name = "NAME",
+            help = "Concerts this musician played, as a band member or a guest"
+        )]
+        musician: Option<String>,
+
+        #[arg(short = 'f', long = "full", help = "Print full setlists instead of a compact list")]
+        full: bool,
+    },
+    /// Show the setlist of a single concert
+    #[command(arg_required_else_help = true)]
+    Show {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(help = "Concert identifier (e.g. motorpsycho-2021-05-16) or date (DD-MM-YYYY or YYYY-MM-DD)")]
+        id: String,
+
+        #[arg(short = 'c', long = "color", help = "Use colours in the output")]
+        color: bool,
+
+        #[arg(
+            id = "output_format",
+            long = "format",
+            value_enum,
+            help = "Print the setlist in an export format instead"
+        )]
+        format: Option<ExportFormat>,
+    },
+    /// Export setlists to other formats
+    #[command(arg_required_else_help = true)]
+    Export {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(id = "output_format", long = "format", value_enum, help = "Output format")]
+        format: ExportFormat,
+
+        #[arg(long = "from", help = "Only export concerts on or after this date (YYYY-MM-DD)")]
+        from: Option<chrono::NaiveDate>,
+
+        #[arg(long = "to", help = "Only export concerts on or before this date (YYYY-MM-DD)")]
+        to: Option<chrono::NaiveDate>,
+
+        #[arg(
+            short = 't',
+            long = "tour",
+            help = "Only export concerts on a tour whose name contains this text"
+        )]
+        tour: Option<String>,
+
+        #[arg(
+            short = 'o',
+            long = "output",
+            help = "Write to this file instead of standard output ('-' for standard output)"
+        )]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Import setlists from a CSV performances sheet and write them as YAML master data
+    #[command(arg_required_else_help = true)]
+    Import {
+        #[arg(short = 'i', long = "input", help = "CSV file to import ('-' for standard input)")]
+        input: std::path::PathBuf,
+
+        #[arg(
+            short = 'o',
+            long = "output",
+            help = "Write to this file instead of standard output ('-' for standard output)"
+        )]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Check the master data and the song aliases for mistakes
+    #[command(arg_required_else_help = true)]
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Watch the master data and the song aliases, and validate (and optionally sync) them on every change
+    #[command(arg_required_else_help = true)]
+    Watch {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            long = "sync",
+            help = "Sync changed concerts to the database if validation finds no errors"
+        )]
+        sync: bool,
+
+        #[arg(
+            long = "sqlite",
+            value_name = "FILE",
+            requires = "sync",
+            help = "Sync to a local SQLite database instead of the MPDB API"
+        )]
+        sqlite: Option<std::path::PathBuf>,
+    },
+    /// Static site commands
+    Site {
+        #[command(subcommand)]
+        command: SiteCommands,
+    },
+    /// Statistics over the master data
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommands,
+    },
+    /// MusicBrainz commands
+    #[command(alias = "mb")]
+    Musicbrainz {
+        #[command(subcommand)]
+        command: MusicbrainzCommands,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Markdown
+    Md,
+    /// Plain text
+    Txt,
+    /// CSV with one row per performance
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatsGroup {
+    /// Per tour
+    Tour,
+    /// Per year
+    Year,
+}
+
+#[derive(Args)]
+pub struct PerformanceFilterArgs {
+    #[arg(
+        long = "guest",
+        help = "Only performances with a guest whose name contains this text"
+    )]
+    pub guest: Option<String>,
+
+    #[arg(long = "tag", help = "Only performances with this version tag (e.g. acoustic)")]
+    pub tag: Option<String>,
+
+    #[arg(
+        long = "tease",
+        help = "Only performances that tease a song whose name contains this text"
+    )]
+    pub tease: Option<String>,
+}
+
+#[derive(Args)]
+#[command(group(clap::ArgGroup::new("format").required(true)))]
+pub struct InputArgs {
+    #[arg(
+        short = 'x',
+        long = "xml",
+        group = "format",
+        help = "Input data is in XML format",
+        conflicts_with = "yml"
+    )]
+    pub xml: bool,
+
+    #[arg(
+        short = 'y',
+        long = "yml",
+        group = "format",
+        help = "Input data is in YAML format",
+        conflicts_with = "xml"
+    )]
+    pub yml: bool,
+
+    #[arg(short = 'd', long = "directory", help = "Input data is in a directory")]
+    pub dir: bool,
+
+    #[arg(
+        long = "master",
+        value_name = "PATH",
+        help = "Master file, or directory with --directory ('-' for standard input; defaults to the config)"
+    )]
+    pub master: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "aliases",
+        value_name = "FILE",
+        help = "Song aliases file ('-' for standard input; defaults to the config)"
+    )]
+    pub aliases: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "songs",
+        value_name = "FILE",
+        help = "Song catalogue file (defaults to the config; ignored if it does not exist)"
+    )]
+    pub songs: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "discography",
+        value_name = "FILE",
+        help = "Discography file (defaults to the config; ignored if it does not exist)"
+    )]
+    pub discography: Option<std::path::PathBuf>,
+
+    #[arg(
+        long = "lineups",
+        value_name = "FILE",
+        help = "Band lineups file (defaults to the config; ignored if it does not exist)"
+    )]
+    pub lineups: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -26,26 +300,43 @@ pub enum DbCommands {
     /// Populate the database from a local XML file
     #[command(arg_required_else_help = true)]
     Populate {
+        #[command(flatten)]
+        input: Box<InputArgs>,
+
+        #[arg(
+            long = "sqlite",
+            value_name = "FILE",
+            help = "Populate a local SQLite database instead of the MPDB API"
+        )]
+        sqlite: Option<std::path::PathBuf>,
+
         #[arg(
-            short = 'x',
-            long = "xml",
-            group = "format",
-            help = "Input data is in XML format",
-            conflicts_with = "yml"
+            long = "since",
+            value_name = "REV",
+            help = "Only sync concerts added, modified or removed since this git revision of the master data"
         )]
-        xml: bool,
+        since: Option<String>,
 
         #[arg(
-            short = 'y',
-            long = "yml",
-            group = "format",
-            help = "Input data is in YAML format",
-            conflicts_with = "xml"
+            long = "state",
+            value_name = "FILE",
+            help = "Sync state cache file (defaults to sync_state_file in the config, or mpdbsync.json)"
         )]
-        yml: bool,
+        state: Option<std::path::PathBuf>,
 
-        #[arg(short = 'd', long = "directory", help = "Input data is in a directory")]
-        dir: bool,
+        #[arg(
+            long = "refresh",
+            help = "Ignore the sync state cache and fetch all IDs from the database"
+        )]
+        refresh: bool,
+
+        #[arg(
+            long = "report",
+            value_name = "FILE",
+            help = "Write a JSON report of what was created, updated, skipped and failed (- for stdout, with the summary table \
+                    on stderr)"
+        )]
+        report: Option<std::path::PathBuf>,
     },
     /// Reset the database (delete all data) (not implemented yet)
     Reset,
@@ -54,5 +345,155 @@ pub enum DbCommands {
 #[derive(Subcommand)]
 pub enum XmlCommands {
     /// Convert xml to yml
-    Convert,
+    ///
+    /// Without --input, the master file and the song aliases file are converted, each written next to its input.
+    Convert {
+        #[arg(
+            long = "master",
+            value_name = "FILE",
+            conflicts_with = "input",
+            help = "Master XML file (defaults to the config)"
+        )]
+        master: Option<std::path::PathBuf>,
+
+        #[arg(
+            long = "aliases",
+            value_name = "FILE",
+            conflicts_with = "input",
+            help = "Song aliases XML file (defaults to the config)"
+        )]
+        aliases: Option<std::path::PathBuf>,
+
+        #[arg(
+            short = 'i',
+            long = "input",
+            value_name = "FILE",
+            help = "Convert only this master or song aliases file ('-' for standard input)"
+        )]
+        input: Option<std::path::PathBuf>,
+
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            requires = "input",
+            help = "Output file for --input ('-' for standard output; defaults to the input with a .yml extension, or to \
+                    standard output for standard input)"
+        )]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SiteCommands {
+    /// Build a static HTML site from the master data
+    #[command(arg_required_else_help = true)]
+    Build {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(short = 'o', long = "out", value_name = "DIR", help = "Output directory")]
+        out: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MusicbrainzCommands {
+    /// Enrich artists and covers with data from MusicBrainz JSON dump files
+    ///
+    /// Without --output and --aliases-output, only reports what would be matched.
+    #[command(arg_required_else_help = true)]
+    Import {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            long = "artists",
+            value_name = "FILE",
+            help = "MusicBrainz artist dump (one JSON artist per line)"
+        )]
+        artists: std::path::PathBuf,
+
+        #[arg(
+            long = "works",
+            value_name = "FILE",
+            help = "MusicBrainz work dump (one JSON work per line)"
+        )]
+        works: Option<std::path::PathBuf>,
+
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "FILE",
+            help = "Write the enriched master data as YAML to this file ('-' for standard output)"
+        )]
+        output: Option<std::path::PathBuf>,
+
+        #[arg(
+            long = "aliases-output",
+            value_name = "FILE",
+            help = "Write the enriched song aliases as YAML to this file ('-' for standard output)"
+        )]
+        aliases_output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommands {
+    /// Show for each album how many of its songs were played, per tour or per year
+    #[command(arg_required_else_help = true)]
+    AlbumCoverage {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            long = "by",
+            value_enum,
+            default_value = "tour",
+            help = "Group the concerts by tour or by year"
+        )]
+        by: StatsGroup,
+
+        #[arg(long = "album", help = "Only albums whose title contains this text")]
+        album: Option<String>,
+
+        #[command(flatten)]
+        filter: PerformanceFilterArgs,
+    },
+    /// Show how often each song was played
+    #[command(arg_required_else_help = true)]
+    Songs {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        filter: PerformanceFilterArgs,
+    },
+    /// Show for each song how many concerts ago it was last played
+    #[command(arg_required_else_help = true)]
+    Gaps {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            long = "min",
+            value_name = "CONCERTS",
+            default_value_t = 0,
+            help = "Only songs not played for at least this many concerts"
+        )]
+        min: usize,
+    },
+    /// Show how many concerts have a known recording, and list the concerts without one
+    #[command(arg_required_else_help = true)]
+    Sources {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[arg(
+            long = "kind",
+            value_name = "KIND",
+            help = "List the concerts without a recording of this kind (e.g. soundboard) instead"
+        )]
+        kind: Option<String>,
+    },
 }
@@ -0,0 +1,86 @@
+//! Reading the master data as it was at an earlier revision of its git repository.
+//!
+//! The master data lives in its own git repository (the `mpdbmasterdata` submodule), so all commands are run with
+//! `git -C <directory of the file>`, and paths are given relative to that directory.
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::setlists::{FileFormat, Setlists};
+
+/// Runs a git command in a directory and returns its standard output
+fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    log::debug!("Running git {} in {}", args.join(" "), dir.display());
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Returns the directory of a path, using `.` for bare file names
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Reads the content of a file as it was at a revision
+///
+/// # Arguments
+/// * `path` - The file, inside a git working tree
+/// * `rev` - Any revision git understands (a commit, tag, branch, `HEAD~3`, ...)
+///
+/// # Returns
+/// * `Result<String, Box<dyn std::error::Error>>` - The file content on success, or an error if git fails (e.g. the
+///   file did not exist at that revision)
+pub fn read_file_at(path: &Path, rev: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let name = path.file_name().ok_or("Not a file")?.to_string_lossy();
+    git(parent(path), &["show", &format!("{}:./{}", rev, name)])
+}
+
+/// Reads and parses a master file as it was at a revision
+///
+/// # Arguments
+/// * `path` - The master file, inside a git working tree
+/// * `rev` - The revision to read
+/// * `format` - The format of the file
+///
+/// # Returns
+/// * `Result<Setlists, Box<dyn std::error::Error>>` - The parsed Setlists on success, or a git or parse error
+pub fn read_master_at(path: &Path, rev: &str, format: FileFormat) -> Result<Setlists, Box<dyn std::error::Error>> {
+    Setlists::parse(&read_file_at(path, rev)?, format)
+}
+
+/// Reads and parses all files with the extension of the given format in a directory as it was at a revision, in file
+/// name order, like `Setlists::read_dir` does for the working tree
+///
+/// # Arguments
+/// * `dir` - The directory, inside a git working tree
+/// * `rev` - The revision to read
+/// * `format` - The format of the files
+///
+/// # Returns
+/// * `Result<Setlists, Box<dyn std::error::Error>>` - The parsed Setlists on success, or a git or parse error
+pub fn read_dir_at(dir: &Path, rev: &str, format: FileFormat) -> Result<Setlists, Box<dyn std::error::Error>> {
+    let listing = git(dir, &["ls-tree", "--name-only", rev, "--", "."])?;
+    let mut names: Vec<&str> = listing
+        .lines()
+        .filter(|name| Path::new(name).extension().and_then(|s| s.to_str()) == Some(format.extension()))
+        .collect();
+    names.sort();
+
+    let mut content = String::new();
+    for name in names {
+        log::debug!("Loading file {} at {}", name, rev);
+        content.push_str(&git(dir, &["show", &format!("{}:./{}", rev, name)])?);
+        content.push('\n');
+    }
+    Setlists::parse(&content, format)
+}
@@ -0,0 +1,24 @@
+//! Tools for the Motorpsycho database (MPDB).
+//!
+//! This crate contains the setlist model with its XML, YAML and CSV parsers and serializers, the `Slug` trait used
+//! for all identifiers and URLs, validation, exporters and a static site generator, access to earlier git revisions
+//! of the master data, and the client that populates the MPDB database through a `Storage` backend (the REST API or a
+//! local SQLite database), with a report of what each populate step did.
+
+pub mod git;
+pub mod mpdb;
+pub mod musicbrainz;
+pub mod report;
+pub mod setlists;
+pub mod settings;
+pub mod site;
+pub mod slug;
+pub mod stats;
+pub mod storage;
+pub mod sync_state;
+mod tests;
+pub mod validate;
+
+pub use mpdb::{populate_changes, populate_db, Mpdb};
+pub use setlists::{Setlists, SongAliases};
+pub use slug::Slug;
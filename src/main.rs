@@ -1,164 +1,412 @@
 // Internal modules
 mod cli;
-mod mpdb;
-mod setlists;
-mod slug;
-mod tests;
 
 use cli::*;
-use mpdb::Mpdb;
-use setlists::{Setlists, SongAliases};
+use mpdbtool::git;
+use mpdbtool::mpdb::{populate_changes, populate_db, Mpdb};
+use mpdbtool::musicbrainz;
+use mpdbtool::report::RunReport;
+use mpdbtool::setlists::{
+    export_csv, export_setlist, export_setlists, render_setlist, render_summary, Discography, FileFormat, Lineups,
+    SetlistChanges, SetlistQuery, Setlists, SongAliases, SongCatalogue, TextFormat,
+};
+use mpdbtool::settings::Settings;
+use mpdbtool::site::Site;
+use mpdbtool::stats::{
+    album_coverage, render_album_coverage, render_song_counts, render_song_gaps, render_source_stats, song_counts,
+    song_gaps, source_stats, GroupBy, PerformanceFilter,
+};
+use mpdbtool::storage::{RestStorage, SqliteStorage, Storage};
+use mpdbtool::sync_state::SyncState;
+use mpdbtool::validate::{
+    has_errors, validate, validate_catalogue, validate_discography, validate_lineups, Issue, Severity,
+};
 
 // External crates
 use clap::Parser;
-use config::Config;
 use flexi_logger::{Duplicate, FileSpec, Logger, WriteMode};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use log::{debug, error, info};
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+fn file_format(input: &InputArgs) -> FileFormat {
+    match (input.xml, input.yml) {
+        (true, false) => FileFormat::Xml,
+        (false, true) => FileFormat::Yml,
+        // The format group is required and its options conflict, so clap accepts exactly one of them
+        _ => unreachable!("exactly one of --xml and --yml is accepted"),
+    }
+}
 
-const CONFIG_FILE: &str = "mpdbtoolconfig.toml";
-
-enum FileFormat {
-    Xml,
-    Yml,
+/// The path meaning standard input or standard output
+const STDIO: &str = "-";
+
+/// The master data, song aliases, song catalogue, discography and lineups files to read
+struct MasterPaths {
+    /// The master file, or the master directory with `--directory`
+    master: PathBuf,
+    aliases: PathBuf,
+    songs: PathBuf,
+    discography: PathBuf,
+    lineups: PathBuf,
 }
 
-impl FileFormat {
-    fn extension(&self) -> &'static str {
-        match self {
-            FileFormat::Xml => "xml",
-            FileFormat::Yml => "yml",
+/// Returns the master data and song aliases paths given on the command line, falling back to the config
+fn master_paths(input: &InputArgs, settings: &Settings) -> MasterPaths {
+    let extension = file_format(input).extension();
+    let master = input.master.clone().unwrap_or_else(|| {
+        if input.dir {
+            PathBuf::from(&settings.master_dir)
+        } else {
+            PathBuf::from(format!("{}.{}", settings.master_file(), extension))
         }
+    });
+    let aliases = input
+        .aliases
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", settings.aliases_file(), extension)));
+    let songs = input
+        .songs
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", settings.songs_file(), extension)));
+    let discography = input
+        .discography
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", settings.discography_file(), extension)));
+    let lineups = input
+        .lineups
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("{}.{}", settings.lineups_file(), extension)));
+    MasterPaths {
+        master,
+        aliases,
+        songs,
+        discography,
+        lineups,
     }
 }
 
-async fn populate_db(mpdb: &mut Mpdb) -> Result<(), Box<dyn std::error::Error>> {
-    // debug!("{:?}", mpdb.aliases);
-
-    // Set up progress bars
-    let multipb = MultiProgress::new();
-    let style =
-        ProgressStyle::with_template("[{elapsed_precise}] [{percent:>3}%] |{bar:80.cyan/blue}| {pos:>7}/{len:7} {msg}")
-            .unwrap()
-            .progress_chars("=>-");
-
-    let pb_countries = multipb.add(ProgressBar::new(mpdb.countries_count()));
-    pb_countries.set_style(style.clone());
-    pb_countries.set_message("Countries");
-
-    let pb_cities = multipb.add(ProgressBar::new(mpdb.cities_count()));
-    pb_cities.set_style(style.clone());
-    pb_cities.set_message("Cities");
-
-    let pb_venues = multipb.add(ProgressBar::new(mpdb.venues_count()));
-    pb_venues.set_style(style.clone());
-    pb_venues.set_message("Venues");
-
-    let pb_artists = multipb.add(ProgressBar::new(mpdb.artists_count()));
-    pb_artists.set_style(style.clone());
-    pb_artists.set_message("Artists");
-
-    let pb_songs = multipb.add(ProgressBar::new(mpdb.songs_count()));
-    pb_songs.set_style(style.clone());
-    pb_songs.set_message("Songs");
-
-    let pb_concerts = multipb.add(ProgressBar::new(mpdb.concerts_count()));
-    pb_concerts.set_style(style.clone());
-    pb_concerts.set_message("Concerts");
-
-    let pb_performances = multipb.add(ProgressBar::new(mpdb.performances_count()));
-    pb_performances.set_style(style.clone());
-    pb_performances.set_message("Performances");
-
-    multipb.println("starting!").unwrap();
-
-    info!("Populating countries");
-    let result = mpdb.populate_countries(pb_countries).await;
-    match result {
-        Ok(c) => {
-            info!("Added all countries");
-            mpdb.countries = c;
-            debug!("{:?}", mpdb.countries);
-        }
-        Err(e) => error!("Error adding countries: {e}"),
+/// Returns true if a path means standard input or output
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO
+}
+
+/// Reads a file, or standard input for `-`
+fn read_input(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    if is_stdio(path) {
+        debug!("Reading standard input");
+        Ok(std::io::read_to_string(std::io::stdin())?)
+    } else {
+        debug!("Reading file: {}", path.display());
+        std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e).into())
     }
+}
 
-    info!("Populating cities");
-    let result = mpdb.populate_cities(pb_cities).await;
-    match result {
-        Ok(c) => {
-            info!("Added all cities");
-            mpdb.cities = c;
-            debug!("{:?}", mpdb.cities);
+/// Writes to a file, or to standard output if no file or `-` is given
+fn write_output(path: Option<&Path>, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match path {
+        Some(path) if !is_stdio(path) => {
+            info!("Writing {}", path.display());
+            std::fs::write(path, content)?
         }
-        Err(e) => error!("Error adding cities: {e}"),
+        _ => print!("{}", content),
     }
+    Ok(())
+}
 
-    info!("Populating venues");
-    let result = mpdb.populate_venues(pb_venues).await;
-    match result {
-        Ok(c) => {
-            info!("Added all venues");
-            mpdb.venues = c;
-            debug!("{:?}", mpdb.venues);
-        }
-        Err(e) => error!("Error adding venues: {e}"),
+/// Loads and parses the master data and the song aliases, either from the master file or from all files in the
+/// master directory.
+fn load_master_data(
+    input: &InputArgs,
+    paths: &MasterPaths,
+) -> Result<(Setlists, SongAliases), Box<dyn std::error::Error>> {
+    let format = file_format(input);
+    if is_stdio(&paths.master) && is_stdio(&paths.aliases) {
+        return Err("Only one of the master data and the song aliases can be read from standard input".into());
     }
 
-    info!("Populating artists");
-    let result = mpdb.populate_artists(pb_artists).await;
-    match result {
-        Ok(c) => {
-            info!("Added all artists");
-            mpdb.artists = c;
-            debug!("{:?}", mpdb.artists);
-        }
-        Err(e) => error!("Error adding artists: {e}"),
+    let master = if !input.dir || is_stdio(&paths.master) {
+        Setlists::parse(&read_input(&paths.master)?, format)?
+    } else {
+        Setlists::read_dir(&paths.master, format)?
+    };
+    let aliases = SongAliases::parse(&read_input(&paths.aliases)?, format)?;
+
+    Ok((master, aliases))
+}
+
+/// The master data with the song aliases, song catalogue, discography and lineups kept next to it
+type MasterData = (Setlists, SongAliases, SongCatalogue, Discography, Lineups);
+
+/// Loads and parses the master data with the song aliases and the optional song catalogue, discography and lineups
+fn load_all_data(input: &InputArgs, paths: &MasterPaths) -> Result<MasterData, Box<dyn std::error::Error>> {
+    let (master, aliases) = load_master_data(input, paths)?;
+    let catalogue = load_song_catalogue(input, paths)?;
+    let discography = load_discography(input, paths)?;
+    let lineups = load_lineups(input, paths)?;
+    Ok((master, aliases, catalogue, discography, lineups))
+}
+
+/// Validates the master data with the song aliases, song catalogue, discography and lineups
+fn validate_all((master, aliases, catalogue, discography, lineups): &MasterData) -> Vec<Issue> {
+    let mut issues = validate(master, aliases);
+    issues.extend(validate_catalogue(catalogue, master, aliases));
+    issues.extend(validate_discography(discography, master, aliases));
+    issues.extend(validate_lineups(lineups, master));
+    issues
+}
+
+/// Reads an optional file kept next to the master data, or returns `None` if it does not exist
+fn read_optional(path: &Path, what: &str, paths: &MasterPaths) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if !is_stdio(path) && !path.exists() {
+        debug!("No {} at {}", what, path.display());
+        return Ok(None);
     }
+    let from_stdin = [
+        &paths.master,
+        &paths.aliases,
+        &paths.songs,
+        &paths.discography,
+        &paths.lineups,
+    ]
+    .iter()
+    .filter(|p| is_stdio(p))
+    .count();
+    if from_stdin > 1 {
+        return Err(format!(
+            "The {} cannot be read from standard input together with other data",
+            what
+        )
+        .into());
+    }
+    Ok(Some(read_input(path)?))
+}
 
-    info!("Populating songaliases");
-    let result = mpdb.populate_songaliases().await;
-    match result {
-        Ok(_) => info!("Added all songaliases"),
-        Err(e) => error!("Error adding songaliases: {e}"),
+/// Loads and parses the song catalogue, or returns an empty catalogue if its file does not exist
+fn load_song_catalogue(input: &InputArgs, paths: &MasterPaths) -> Result<SongCatalogue, Box<dyn std::error::Error>> {
+    match read_optional(&paths.songs, "song catalogue", paths)? {
+        Some(content) => SongCatalogue::parse(&content, file_format(input)),
+        None => Ok(SongCatalogue::new()),
     }
+}
 
-    info!("Populating songtitles");
-    let result = mpdb.populate_songtitles(pb_songs).await;
-    match result {
-        Ok(c) => {
-            info!("Added all songtitles");
-            mpdb.songtitles = c;
-            debug!("{:?}", mpdb.songtitles);
-        }
-        Err(e) => error!("Error adding songtitles: {e}"),
+/// Loads and parses the discography, or returns an empty discography if its file does not exist
+fn load_discography(input: &InputArgs, paths: &MasterPaths) -> Result<Discography, Box<dyn std::error::Error>> {
+    match read_optional(&paths.discography, "discography", paths)? {
+        Some(content) => Discography::parse(&content, file_format(input)),
+        None => Ok(Discography::new()),
     }
+}
 
-    info!("Populating concerts");
-    let result = mpdb.populate_concerts(pb_concerts).await;
-    match result {
-        Ok(c) => {
-            info!("Added all concerts");
-            mpdb.concerts = c;
-            debug!("{:?}", mpdb.concerts);
-        }
-        Err(e) => error!("Error adding concerts: {e}"),
+/// Loads and parses the band lineups, or returns no lineups if their file does not exist
+fn load_lineups(input: &InputArgs, paths: &MasterPaths) -> Result<Lineups, Box<dyn std::error::Error>> {
+    match read_optional(&paths.lineups, "lineups", paths)? {
+        Some(content) => Lineups::parse(&content, file_format(input)),
+        None => Ok(Lineups::new()),
+    }
+}
+
+/// Loads the master data as it was at a git revision, from the master file or the master directory
+fn load_master_data_at(
+    input: &InputArgs,
+    rev: &str,
+    paths: &MasterPaths,
+) -> Result<Setlists, Box<dyn std::error::Error>> {
+    let format = file_format(input);
+    if is_stdio(&paths.master) {
+        return Err("The master data must be read from a file to compare it with a git revision".into());
+    }
+    if !input.dir {
+        debug!("Loading master file {} at {}", paths.master.display(), rev);
+        git::read_master_at(&paths.master, rev, format)
+    } else {
+        git::read_dir_at(&paths.master, rev, format)
+    }
+}
+
+/// Populates a storage backend with all master data, or only with the given changes, and saves the sync state
+///
+/// Returns the report of the populate run.
+async fn populate<S: Storage>(
+    storage: S,
+    (master, aliases, catalogue, discography, lineups): MasterData,
+    changes: Option<SetlistChanges>,
+    state: SyncState,
+    state_file: &std::path::Path,
+    home_artist: &str,
+) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let mut mpdb = Mpdb::new(storage);
+    (mpdb.master, mpdb.aliases, mpdb.state) = (master, aliases, state);
+    (mpdb.catalogue, mpdb.discography, mpdb.lineups) = (catalogue, discography, lineups);
+    mpdb.home_artist = home_artist.to_string();
+    let result = match changes {
+        Some(changes) => populate_changes(&mut mpdb, &changes).await,
+        None => populate_db(&mut mpdb).await,
+    };
+    debug!("Saving sync state to {}", state_file.display());
+    mpdb.state.save(state_file)?;
+    result
+}
+
+/// Converts the performance filter options to a filter for the stats
+fn performance_filter(args: PerformanceFilterArgs) -> PerformanceFilter {
+    PerformanceFilter {
+        guest: args.guest,
+        tag: args.tag,
+        tease: args.tease,
     }
+}
+
+/// Prints validation issues followed by a one-line count
+fn print_issues(issues: &[Issue]) {
+    for issue in issues {
+        println!("  {}", issue);
+    }
+    let errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+    println!("{} error(s), {} warning(s)", errors, issues.len() - errors);
+}
 
-    info!("Populating sets and performances");
-    let result = mpdb.populate_performances(pb_performances).await;
-    match result {
-        Ok(_c) => {
-            info!("Added all sets and performances");
-            // mpdb.concerts = c;
-            // debug!("{:?}", mpdb.concerts);
+/// Returns the canonical directory of a file, to compare with the paths in file system events
+fn watched_dir(file: &Path) -> std::io::Result<PathBuf> {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.canonicalize(),
+        _ => Path::new(".").canonicalize(),
+    }
+}
+
+/// Watches the master data and the song aliases, and validates them on every change
+///
+/// Each change is summarized as the concerts added (`+`), modified (`~`) and removed (`-`) since the last version
+/// that was read, followed by the validation issues. The song catalogue, discography and lineups are read and
+/// validated again with every change. With `sync`, the changed concerts are synced to the database with them, like
+/// `db populate` does, when validation finds no errors; if syncing fails, the changes are retried on the next change.
+async fn watch_master_data<S: Storage>(
+    input: &InputArgs,
+    paths: &MasterPaths,
+    mut sync: Option<(Mpdb<S>, PathBuf)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let extension = file_format(input).extension();
+    if is_stdio(&paths.master) || is_stdio(&paths.aliases) {
+        return Err("Standard input cannot be watched".into());
+    }
+    let (master_file, aliases_file) = (&paths.master, &paths.aliases);
+
+    // Watched files as (directory, file name), where no file name means all files with the extension
+    let mut watched = vec![(
+        watched_dir(aliases_file)?,
+        aliases_file.file_name().map(|n| n.to_owned()),
+    )];
+    if input.dir {
+        watched.push((master_file.canonicalize()?, None));
+    } else {
+        watched.push((watched_dir(master_file)?, master_file.file_name().map(|n| n.to_owned())));
+    }
+    let is_watched = |path: &Path| {
+        watched.iter().any(|(dir, name)| {
+            path.parent() == Some(dir.as_path())
+                && match name {
+                    Some(name) => path.file_name() == Some(name.as_os_str()),
+                    None => path.extension().and_then(|e| e.to_str()) == Some(extension),
+                }
+        })
+    };
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+    let mut dirs: Vec<&PathBuf> = watched.iter().map(|(dir, _)| dir).collect();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let data = load_all_data(input, paths)?;
+    println!("Loaded {} concerts", data.0.data.len());
+    print_issues(&validate_all(&data));
+    let (mut master, mut aliases, ..) = data;
+    println!("Watching for changes (Ctrl-C to stop)");
+
+    while let Some(event) = rx.recv().await {
+        let event: notify::Event = event?;
+        if event.kind.is_access() || !event.paths.iter().any(|p| is_watched(p)) {
+            continue;
         }
-        Err(e) => error!("Error adding sets and/or performances: {e}"),
+        // Editors often save a file in several steps, so wait until the events settle
+        while let Ok(Some(_)) = tokio::time::timeout(std::time::Duration::from_millis(300), rx.recv()).await {}
+
+        println!("\n[{}] Change detected", chrono::Local::now().format("%H:%M:%S"));
+        let data = match load_all_data(input, paths) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("  {}", e);
+                continue;
+            }
+        };
+
+        let issues = validate_all(&data);
+        let (new_master, new_aliases, catalogue, discography, lineups) = data;
+        let changes = SetlistChanges::between(&master, &new_master);
+        let aliases_changed = new_aliases != aliases;
+        for setlist in changes.added.iter() {
+            println!("  + {}", setlist.identifier());
+        }
+        for setlist in changes.modified.iter() {
+            println!("  ~ {}", setlist.identifier());
+        }
+        for setlist in changes.removed.iter() {
+            println!("  - {}", setlist.identifier());
+        }
+        if aliases_changed {
+            println!("  song aliases changed");
+        }
+        if changes.is_empty() && !aliases_changed {
+            println!("  no concerts changed");
+        }
+        print_issues(&issues);
+
+        if let Some((mpdb, state_file)) = sync.as_mut() {
+            if has_errors(&issues) {
+                println!("Not syncing, fix the errors first");
+                continue;
+            }
+            if !changes.is_empty() || aliases_changed {
+                mpdb.aliases = new_aliases.clone();
+                (mpdb.catalogue, mpdb.discography, mpdb.lineups) = (catalogue, discography, lineups);
+                let result = sync_changes(mpdb, &changes, aliases_changed).await;
+                mpdb.state.save(state_file)?;
+                match result {
+                    Ok(()) => println!("Synced {} concert(s)", changes.added.len() + changes.modified.len()),
+                    Err(e) => {
+                        println!("Sync failed: {}", e);
+                        continue;
+                    }
+                }
+            }
+        }
+
+        (master, aliases) = (new_master, new_aliases);
     }
 
     Ok(())
 }
 
+/// Syncs changed song aliases and concerts, for watch mode
+async fn sync_changes<S: Storage>(
+    mpdb: &mut Mpdb<S>,
+    changes: &SetlistChanges,
+    aliases_changed: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if aliases_changed {
+        if mpdb.state.is_empty() {
+            mpdb.load_remote_state().await?;
+        }
+        mpdb.populate_songaliases().await?;
+    }
+    println!("{}", populate_changes(mpdb, changes).await?);
+    Ok(())
+}
+
 async fn reset_db(_mpdb_base_url: String) -> Result<(), Box<dyn std::error::Error>> {
     // let mut mpdb: Mpdb = Mpdb::new(mpdb_base_url);
     // mpdb.reset_db().await?;
@@ -166,56 +414,34 @@ async fn reset_db(_mpdb_base_url: String) -> Result<(), Box<dyn std::error::Erro
     // Ok(())
 }
 
-async fn xml_to_yml(alias_filename: String, master_filename: String) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Converting XML to YAML");
-
-    // First, the aliases file
-    info!("Reading alias file: {}", alias_filename);
-    let alias_file = std::fs::read_to_string(alias_filename.clone()).unwrap();
-    let aliases = SongAliases::from_xml(&alias_file).map_err(|e| {
-        error!("XML parsing error: {}", e);
-        e
-    })?;
-
-    info!("Converting aliases XML to YAML");
-    let yml = aliases.to_yml()?;
-    let output_filename = format!("{}.yml", alias_filename.split(".").next().unwrap());
-    info!("Writing aliases to YAML file: {}", output_filename);
-    std::fs::write(output_filename, yml)?;
-
-    // Then, the master file
-    info!("Reading master file: {}", master_filename);
-    let master_file = std::fs::read_to_string(master_filename.clone()).unwrap();
-    let master = Setlists::from_xml(&master_file).map_err(|e| {
-        error!("XML parsing error: {}", e);
-        e
-    })?;
-
-    info!("Converting master XML to YAML");
-    let yml = master.to_yml()?;
-    let output_filename = format!("{}.yml", master_filename.split(".").next().unwrap());
-    info!("Writing master to YAML file: {}", output_filename);
-    std::fs::write(output_filename, yml)?;
-
-    Ok(())
+/// Converts an XML master file or song aliases file to YAML
+///
+/// The kind of file is recognized by trying to parse it as master data first, and as song aliases second.
+fn xml_to_yml(input: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Converting {} to YAML", input.display());
+    let xml = read_input(input)?;
+    let yml = match Setlists::from_xml(&xml) {
+        Ok(master) => master.to_yml()?,
+        Err(master_error) => match SongAliases::from_xml(&xml) {
+            Ok(aliases) => aliases.to_yml()?,
+            Err(_) => {
+                error!("XML parsing error: {}", master_error);
+                return Err(master_error.into());
+            }
+        },
+    };
+    write_output(Some(output), &yml)
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Parse config
-    let settings = Config::builder()
-        .add_source(config::File::with_name(CONFIG_FILE))
-        .build()?;
-
-    let mpdb_base_url = settings.get_string("mpdb_base_url")?;
-    let master_path = settings.get_string("master_path")?;
-    let master_dir = settings.get_string("master_dir")?;
-    let master_filename = format!("{}/{}", master_path, settings.get_string("master_filename")?);
-    let aliases_filename = format!("{}/{}", master_path, settings.get_string("aliases_filename")?);
-
     // Parse CLI arguments
     let cli = Cli::parse();
 
+    // Parse config; without a config file the defaults are used, so only commands talking to the API need one
+    let settings = Settings::load(cli.config.as_deref(), cli.profile.as_deref())?;
+    let sync_state_file = settings.sync_state_file.clone();
+
     // Initialize logger
     // env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     // pretty_env_logger::init();
@@ -229,126 +455,307 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     match cli.command {
         Commands::Db { command } => match command {
-            DbCommands::Populate { xml, yml, dir } => {
-                let mut mpdb = Mpdb::new(mpdb_base_url);
-
-                // Determine file format
-                let format = match (xml, yml) {
-                    (true, false) => FileFormat::Xml,
-                    (false, true) => FileFormat::Yml,
-                    _ => unreachable!("xml and yml options are mutually exclusive."), // return Err("Exactly one format (--xml or --yml) must be specified".into()),
-                };
-
-                // Load and parse files
-                let (master_content, alias_content) = if !dir {
-                    debug!("Loading master file: {}.{}", master_filename, format.extension());
-                    let master_content =
-                        std::fs::read_to_string(format!("{}.{}", master_filename, format.extension()))?;
-                    debug!("Loading alias file: {}.{}", aliases_filename, format.extension());
-                    let alias_content =
-                        std::fs::read_to_string(format!("{}.{}", aliases_filename, format.extension()))?;
-                    (master_content, alias_content)
-                } else {
-                    let mut master_content = String::new();
-                    let mut entries: Vec<_> = std::fs::read_dir(master_dir)?.collect();
-                    entries.sort_by(|a, b| {
-                        a.as_ref()
-                            .unwrap()
-                            .path()
-                            .file_name()
-                            .unwrap()
-                            .cmp(b.as_ref().unwrap().path().file_name().unwrap())
-                    });
-                    for entry in entries {
-                        let entry = entry?;
-                        let path = entry.path();
-                        if path.extension().and_then(|s| s.to_str()) == Some(format.extension()) {
-                            debug!("Loading file: {}", path.display());
-                            let content = std::fs::read_to_string(path)?;
-                            master_content.push_str(&content);
-                            master_content.push('\n');
+            DbCommands::Populate {
+                input,
+                sqlite,
+                since,
+                state,
+                refresh,
+                report,
+            } => {
+                let data = load_all_data(&input, &master_paths(&input, &settings))?;
+
+                let changes = match since {
+                    Some(rev) => {
+                        let old = load_master_data_at(&input, &rev, &master_paths(&input, &settings))?;
+                        let changes = SetlistChanges::between(&old, &data.0);
+                        info!(
+                            "Since {}: {} added, {} modified, {} removed concert(s)",
+                            rev,
+                            changes.added.len(),
+                            changes.modified.len(),
+                            changes.removed.len()
+                        );
+                        if changes.is_empty() {
+                            println!("No concerts changed since {}", rev);
+                            return Ok(());
                         }
+                        Some(changes)
                     }
-                    let alias_content =
-                        std::fs::read_to_string(format!("{}.{}", aliases_filename, format.extension()))?;
-                    (master_content, alias_content)
+                    None => None,
                 };
 
-                mpdb.master = match format {
-                    FileFormat::Xml => {
-                        Setlists::from_xml(&master_content).map_err(|e| format!("XML parse error: {}", e))
-                    }
-                    FileFormat::Yml => {
-                        Setlists::from_yml(&master_content).map_err(|e| format!("YAML parse error: {}", e))
-                    }
-                }?;
+                let target = match &sqlite {
+                    Some(path) => format!("sqlite:{}", path.display()),
+                    None => settings.base_url()?.to_string(),
+                };
+                let state_file = state.unwrap_or_else(|| sync_state_file.clone().into());
+                let state = if refresh {
+                    SyncState::new(&target)
+                } else {
+                    SyncState::load(&state_file, &target)?
+                };
 
-                mpdb.aliases = match format {
-                    FileFormat::Xml => {
-                        SongAliases::from_xml(&alias_content).map_err(|e| format!("XML parse error: {}", e))
+                let run = match sqlite {
+                    Some(path) => {
+                        info!("Populating SQLite database {}", path.display());
+                        let storage = SqliteStorage::open(&path)?;
+                        populate(storage, data, changes, state, &state_file, &settings.home_artist).await?
                     }
-                    FileFormat::Yml => {
-                        SongAliases::from_yml(&alias_content).map_err(|e| format!("YAML parse error: {}", e))
+                    None => {
+                        let storage = settings.rest_storage()?;
+                        populate(storage, data, changes, state, &state_file, &settings.home_artist).await?
                     }
-                }?;
-
-                populate_db(&mut mpdb).await?
+                };
+                // The table goes to standard error when standard output is taken by the JSON report
+                match &report {
+                    Some(path) if is_stdio(path) => eprintln!("{}", run),
+                    _ => println!("{}", run),
+                }
+                if let Some(path) = report {
+                    write_output(Some(&path), &run.to_json()?)?;
+                }
             }
-            DbCommands::Reset => reset_db(mpdb_base_url).await?,
+            DbCommands::Reset => reset_db(settings.base_url()?.to_string()).await?,
         },
         Commands::Xml { command } => match command {
-            XmlCommands::Convert => {
-                xml_to_yml(format!("{}.xml", aliases_filename), format!("{}.xml", master_filename)).await?
-            }
+            XmlCommands::Convert {
+                master,
+                aliases,
+                input,
+                output,
+            } => match input {
+                Some(input) => {
+                    // Standard input is converted to standard output unless an output file is given
+                    let output = match output {
+                        Some(output) => output,
+                        None if is_stdio(&input) => PathBuf::from(STDIO),
+                        None => input.with_extension("yml"),
+                    };
+                    xml_to_yml(&input, &output)?
+                }
+                None => {
+                    let master = master.unwrap_or_else(|| PathBuf::from(format!("{}.xml", settings.master_file())));
+                    let aliases = aliases.unwrap_or_else(|| PathBuf::from(format!("{}.xml", settings.aliases_file())));
+                    xml_to_yml(&aliases, &aliases.with_extension("yml"))?;
+                    xml_to_yml(&master, &master.with_extension("yml"))?
+                }
+            },
         },
-    }
-
-    Ok(())
-}
-
-#[allow(dead_code)]
-fn dump_setlists(master: Setlists) {
-    for setlist in master.data {
-        println!(
-            "Artist: {} (mbid={})",
-            setlist.artist.name,
-            setlist.artist.mbid.unwrap_or("no mbid".to_string())
-        );
-        println!("Event Date: {}", setlist.event_date);
-        println!("Status: {}", setlist.status);
-        if let Some(source) = setlist.source {
-            println!("Source: {}", source);
+        Commands::Query {
+            input,
+            song,
+            venue,
+            city,
+            country,
+            from,
+            to,
+            tour,
+            cover_of,
+            has_notes,
+            status,
+            musician,
+            full,
+        } => {
+            let paths = master_paths(&input, &settings);
+            let (master, aliases) = load_master_data(&input, &paths)?;
+            let lineups = load_lineups(&input, &paths)?;
+            let query = SetlistQuery {
+                song,
+                venue,
+                city,
+                country,
+                from,
+                to,
+                tour,
+                cover_of,
+                has_notes,
+                status,
+                musician,
+                lineups,
+            };
+
+            let matches = master.query(&query, &aliases);
+            for setlist in matches.iter() {
+                if full {
+                    println!("{}", render_setlist(setlist, false));
+                } else {
+                    println!("{}", render_summary(setlist));
+                }
+            }
+            println!("{} matching concert(s)", matches.len());
+        }
+        Commands::Show {
+            input,
+            id,
+            color,
+            format,
+        } => {
+            let (master, aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+            match master.find(&id).as_slice() {
+                [] => return Err(format!("No concert found for '{}'", id).into()),
+                [setlist] => match format {
+                    Some(ExportFormat::Md) => print!("{}", export_setlist(setlist, TextFormat::Markdown)),
+                    Some(ExportFormat::Txt) => print!("{}", export_setlist(setlist, TextFormat::Text)),
+                    Some(ExportFormat::Csv) => print!("{}", export_csv(&[setlist], &aliases)?),
+                    None => print!("{}", render_setlist(setlist, color)),
+                },
+                setlists => {
+                    println!("Several concerts match '{}':", id);
+                    for setlist in setlists {
+                        println!("  {}", setlist.identifier());
+                    }
+                }
+            }
         }
-        println!("Venue: {}", setlist.venue.name);
-        println!("City: {}", setlist.venue.city.name);
-        println!("Country: {}", setlist.venue.city.country.name);
-        if let Some(tour) = setlist.tour {
-            println!("Tour: {}", tour.name);
+        Commands::Export {
+            input,
+            format,
+            from,
+            to,
+            tour,
+            output,
+        } => {
+            let (master, aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+            let query = SetlistQuery {
+                from,
+                to,
+                tour,
+                ..Default::default()
+            };
+            let setlists = master.query(&query, &aliases);
+            let exported = match format {
+                ExportFormat::Md => export_setlists(&setlists, TextFormat::Markdown),
+                ExportFormat::Txt => export_setlists(&setlists, TextFormat::Text),
+                ExportFormat::Csv => export_csv(&setlists, &aliases)?,
+            };
+            info!("Exporting {} setlists", setlists.len());
+            write_output(output.as_deref(), &exported)?
         }
-        if let Some(notes) = setlist.notes {
-            println!("Notes: {}", notes);
+        Commands::Import { input, output } => {
+            let content = read_input(&input)?;
+            let master = Setlists::from_csv(&content).map_err(|e| format!("CSV parse error: {}", e))?;
+            info!("Imported {} setlists", master.data.len());
+            write_output(output.as_deref(), &master.to_yml()?)?
         }
-
-        for set in setlist.sets.set {
-            println!("\n*Set*");
-            if let Some(name) = set.name {
-                println!("-- Set name: {}", name);
+        Commands::Validate { input } => {
+            let data = load_all_data(&input, &master_paths(&input, &settings))?;
+            let issues = validate_all(&data);
+            println!("Validated {} concerts", data.0.data.len());
+            print_issues(&issues);
+            if has_errors(&issues) {
+                return Err("Validation failed".into());
             }
-            if let Some(songs) = set.songs {
-                for song in songs {
-                    println!("{}", song.name);
-                    if song.original_artist.is_some() {
-                        println!("-- COVER!!! Original Artist: {}", song.original_artist.unwrap().name);
-                    }
-                    if song.notes.is_some() {
-                        println!("Song notes: {}", song.notes.unwrap());
-                    }
-                    if song.segue.is_some() {
-                        println!("->");
-                    }
+        }
+        Commands::Watch { input, sync, sqlite } => {
+            let state_file = PathBuf::from(&sync_state_file);
+            match (sync, sqlite) {
+                (true, Some(path)) => {
+                    let mut mpdb = Mpdb::new(SqliteStorage::open(&path)?);
+                    mpdb.state = SyncState::load(&state_file, &format!("sqlite:{}", path.display()))?;
+                    mpdb.home_artist = settings.home_artist.clone();
+                    let sync = Some((mpdb, state_file));
+                    watch_master_data(&input, &master_paths(&input, &settings), sync).await?
+                }
+                (true, None) => {
+                    let mut mpdb = Mpdb::new(settings.rest_storage()?);
+                    mpdb.state = SyncState::load(&state_file, settings.base_url()?)?;
+                    mpdb.home_artist = settings.home_artist.clone();
+                    let sync = Some((mpdb, state_file));
+                    watch_master_data(&input, &master_paths(&input, &settings), sync).await?
+                }
+                (false, _) => {
+                    let sync: Option<(Mpdb<RestStorage>, PathBuf)> = None;
+                    watch_master_data(&input, &master_paths(&input, &settings), sync).await?
                 }
             }
         }
-        println!("--------------------");
+        Commands::Site { command } => match command {
+            SiteCommands::Build { input, out } => {
+                let (master, aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+                let pages = Site::new(&master, &aliases).build(&out)?;
+                info!("Wrote {} pages to {}", pages, out.display());
+            }
+        },
+        Commands::Stats { command } => match command {
+            StatsCommands::AlbumCoverage {
+                input,
+                by,
+                album,
+                filter,
+            } => {
+                let paths = master_paths(&input, &settings);
+                let (master, aliases) = load_master_data(&input, &paths)?;
+                let mut discography = load_discography(&input, &paths)?;
+                if discography.albums.is_empty() {
+                    return Err(format!("No albums in the discography at {}", paths.discography.display()).into());
+                }
+                if let Some(album) = album {
+                    let album = album.to_lowercase();
+                    discography.albums.retain(|a| a.title.to_lowercase().contains(&album));
+                }
+                let by = match by {
+                    StatsGroup::Tour => GroupBy::Tour,
+                    StatsGroup::Year => GroupBy::Year,
+                };
+                print!(
+                    "{}",
+                    render_album_coverage(&album_coverage(
+                        &master,
+                        &aliases,
+                        &discography,
+                        by,
+                        &performance_filter(filter)
+                    ))
+                );
+            }
+            StatsCommands::Songs { input, filter } => {
+                let (master, aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+                print!(
+                    "{}",
+                    render_song_counts(&song_counts(&master, &aliases, &performance_filter(filter)))
+                );
+            }
+            StatsCommands::Gaps { input, min } => {
+                let (master, aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+                let mut gaps = song_gaps(&master, &aliases);
+                gaps.retain(|g| g.gap >= min);
+                print!("{}", render_song_gaps(&gaps));
+            }
+            StatsCommands::Sources { input, kind } => {
+                let (master, _) = load_master_data(&input, &master_paths(&input, &settings))?;
+                print!("{}", render_source_stats(&source_stats(&master, kind.as_deref())));
+            }
+        },
+        Commands::Musicbrainz { command } => match command {
+            MusicbrainzCommands::Import {
+                input,
+                artists,
+                works,
+                output,
+                aliases_output,
+            } => {
+                let (mut master, mut aliases) = load_master_data(&input, &master_paths(&input, &settings))?;
+                let artists = musicbrainz::read_artists(&artists, &master)?;
+                let works = match works {
+                    Some(works) => musicbrainz::read_works(&works, &master)?,
+                    None => vec![],
+                };
+                info!("Read {} MusicBrainz artists and {} works", artists.len(), works.len());
+                let enrichment = musicbrainz::enrich(&mut master, &mut aliases, &artists, &works);
+                if let Some(output) = &output {
+                    write_output(Some(output), &master.to_yml()?)?
+                }
+                if let Some(aliases_output) = &aliases_output {
+                    write_output(Some(aliases_output), &aliases.to_yml()?)?
+                }
+                if output.is_none() && aliases_output.is_none() {
+                    info!("Dry run: use --output and --aliases-output to write the enriched data");
+                }
+                eprint!("{}", enrichment);
+            }
+        },
     }
+
+    Ok(())
 }
@@ -1,81 +1,166 @@
-use indicatif::ProgressBar;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
+use crate::report::{RunReport, StepReport};
 use crate::setlists::*;
 use crate::slug::*;
+use crate::storage::{Entity, Storage, StorageError};
+use crate::sync_state::{setlist_hash, SongtitleIds, SyncState};
 
 #[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
-pub struct DbId(i32);
+pub struct DbId(pub i32);
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Country {
-    id: DbId,
-    name: String,
-    code: Option<String>,
+    pub id: DbId,
+    pub name: String,
+    pub code: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct City {
-    id: DbId,
-    name: String,
-    country_id: DbId,
+    pub id: DbId,
+    pub name: String,
+    #[serde(default)]
+    pub slug: String,
+    pub country_id: DbId,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Venue {
-    id: DbId,
-    name: String,
-    slug: String,
-    city_id: DbId,
+    pub id: DbId,
+    pub name: String,
+    #[serde(default)]
+    pub slug: String,
+    pub city_id: DbId,
+    pub mbid: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Artist {
-    id: DbId,
-    name: String,
+    pub id: DbId,
+    pub name: String,
+    pub mbid: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Song {
+    pub id: DbId,
+    pub artist_id: DbId,
+    pub work_mbid: Option<String>,
+    pub album: Option<String>,
+    pub composers: Option<String>,
+    pub release_year: Option<i32>,
+    pub instrumental: Option<bool>,
+    pub duration: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Album {
+    pub id: DbId,
+    pub artist_id: DbId,
+    pub title: String,
+    #[serde(default)]
+    pub slug: String,
+    pub year: Option<i32>,
+    pub mbid: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct AlbumTrack {
+    pub id: DbId,
+    pub album_id: DbId,
+    pub song_id: DbId,
+    pub position: i32,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Songtitle {
-    id: DbId,
-    title: String,
-    is_default: bool,
-    song_id: DbId,
+    pub id: DbId,
+    pub title: String,
+    pub is_default: bool,
+    pub song_id: DbId,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Concert {
-    id: DbId,
-    artist_id: DbId,
-    date: chrono::NaiveDate,
-    disambiguation: Option<String>,
-    sort_order: Option<i32>,
-    source: Option<String>,
-    slug: String,
-    venue_id: DbId,
+    pub id: DbId,
+    pub artist_id: DbId,
+    pub date: chrono::NaiveDate,
+    pub disambiguation: Option<String>,
+    pub sort_order: Option<i32>,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub slug: String,
+    pub venue_id: DbId,
+    pub mbid: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Set {
-    id: DbId,
-    concert_id: DbId,
-    name: Option<String>,
-    unique_name: String,
-    sort_order: i32,
+    pub id: DbId,
+    pub concert_id: DbId,
+    pub name: Option<String>,
+    pub unique_name: String,
+    pub sort_order: i32,
+    #[serde(default)]
+    pub uncertain_order: bool,
+    #[serde(default)]
+    pub partial: bool,
 }
 
 #[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Performance {
-    id: DbId,
-    set_id: DbId,
-    concert_id: DbId,
-    song_id: DbId,
-    songtitle_id: DbId,
-    artist_id: DbId,
-    segue: bool,
-    sort_order: i32,
+    pub id: DbId,
+    pub set_id: DbId,
+    pub concert_id: DbId,
+    pub song_id: DbId,
+    pub songtitle_id: DbId,
+    pub artist_id: DbId,
+    pub segue: bool,
+    pub sort_order: i32,
+    pub recording_mbid: Option<String>,
+    pub duration: Option<u32>,
+    pub guests: Option<String>,
+    pub teases: Option<String>,
+    pub tags: Option<String>,
+    // Parts of a medley share the set position; they are linked to the performance of the first part
+    pub medley: Option<String>,
+    pub medley_position: Option<i32>,
+    pub medley_id: Option<DbId>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Musician {
+    pub id: DbId,
+    pub name: String,
+    #[serde(default)]
+    pub slug: String,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Personnel {
+    pub id: DbId,
+    pub concert_id: DbId,
+    pub musician_id: DbId,
+    pub instrument: Option<String>,
+    pub guest: bool,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Source {
+    pub id: DbId,
+    pub concert_id: DbId,
+    pub kind: Option<String>,
+    pub lineage: Option<String>,
+    pub taper: Option<String>,
+    pub archive_id: Option<String>,
+    pub complete: Option<bool>,
+    pub notes: Option<String>,
+    pub sort_order: i32,
 }
 
 #[allow(dead_code)]
@@ -99,38 +184,78 @@ impl Concert {
     }
 }
 
-#[derive(Deserialize, Debug)]
-pub struct Mpdb {
-    // Config
-    base_url: String,
+/// The artist songs belong to when neither the song aliases nor a cover say otherwise
+pub const DEFAULT_HOME_ARTIST: &str = "Motorpsycho";
+
+#[derive(Debug)]
+pub struct Mpdb<S: Storage> {
+    // Storage backend
+    pub storage: S,
     // Raw data
     pub master: Setlists,
-    // Parsed and structured data
-    pub countries: Vec<Country>,
-    pub cities: Vec<City>,
-    pub venues: Vec<Venue>,
-    pub artists: Vec<Artist>,
-    pub songtitles: Vec<Songtitle>,
     pub aliases: SongAliases,
-    pub concerts: Vec<Concert>,
+    pub catalogue: SongCatalogue,
+    pub discography: Discography,
+    pub lineups: Lineups,
+    // Remote IDs and concert hashes, loaded from and saved to the sync state cache
+    pub state: SyncState,
+    // The artist the archive is about: always created first, and the owner of songs in the aliases without an artist
+    pub home_artist: String,
 }
 
 fn venue_slug(venue: &String, city: &String, country: &String) -> String {
     format!("{}-{}-{}", venue.slug(), city.slug(), country.slug())
 }
 
-impl Mpdb {
-    pub fn new(base_url: String) -> Mpdb {
+fn city_slug(city: &String, country: &String) -> String {
+    format!("{}-{}", city.slug(), country.slug())
+}
+
+/// Returns the slug of a listed row, or the slug of its name if the backend left it out
+///
+/// A row keyed by the fallback may not match the slug it was created with; that costs a cache miss, not the run.
+fn slug_or(slug: String, name: &str) -> String {
+    if slug.is_empty() {
+        name.to_string().slug()
+    } else {
+        slug
+    }
+}
+
+/// Returns the ID of a row returned by the storage backend
+fn row_id(row: &serde_json::Value) -> DbId {
+    DbId(row["id"].as_i64().unwrap_or_default() as i32)
+}
+
+/// Collects keys with optional MusicBrainz IDs, keeping each key once with the first MusicBrainz ID given for it
+fn with_mbids<K: Eq + Hash>(items: impl Iterator<Item = (K, Option<String>)>) -> HashMap<K, Option<String>> {
+    let mut unique: HashMap<K, Option<String>> = HashMap::new();
+    for (key, mbid) in items {
+        let known = unique.entry(key).or_default();
+        if known.is_none() {
+            *known = mbid;
+        }
+    }
+    unique
+}
+
+/// Collects the IDs of rows that have a MusicBrainz ID, by MusicBrainz ID
+fn mbid_ids<'a>(rows: impl Iterator<Item = (&'a Option<String>, DbId)>) -> HashMap<String, DbId> {
+    rows.filter_map(|(mbid, id)| mbid.clone().map(|mbid| (mbid, id)))
+        .collect()
+}
+
+impl<S: Storage> Mpdb<S> {
+    pub fn new(storage: S) -> Mpdb<S> {
         Mpdb {
-            base_url,
+            storage,
             aliases: SongAliases::new(),
             master: Setlists::new(),
-            countries: vec![],
-            cities: vec![],
-            venues: vec![],
-            artists: vec![],
-            songtitles: vec![],
-            concerts: vec![],
+            catalogue: SongCatalogue::new(),
+            discography: Discography::new(),
+            lineups: Lineups::new(),
+            state: SyncState::default(),
+            home_artist: DEFAULT_HOME_ARTIST.to_string(),
         }
     }
 
@@ -146,21 +271,46 @@ impl Mpdb {
         self.extract_all_unique_country_names().len() as u64
     }
 
-    fn extract_all_unique_artists(&self) -> HashSet<String> {
-        self.master
+    /// Returns every artist once by name, with the first MusicBrainz ID, sort name and country given for it
+    fn extract_all_unique_artists(&self) -> HashMap<String, crate::setlists::Artist> {
+        let mut unique: HashMap<String, crate::setlists::Artist> = HashMap::new();
+        let alias_artists: Vec<crate::setlists::Artist> = self
+            .aliases
+            .songs
+            .iter()
+            .filter_map(|s| s.artist.clone())
+            .map(|name| crate::setlists::Artist {
+                name,
+                sort_name: None,
+                mbid: None,
+                country: None,
+            })
+            .collect();
+        let artists = self
+            .master
             .data
             .iter()
-            .map(|s| s.artist.name.clone())
-            .chain(self.master.data.iter().flat_map(|s| {
-                s.sets.set.iter().flat_map(|set| {
-                    set.songs
-                        .as_ref()
-                        .map(|songs| songs.iter())
-                        .unwrap_or_else(|| [].iter())
-                        .filter_map(|song| song.original_artist.as_ref().map(|a| a.name.clone()))
-                })
-            }))
-            .collect()
+            .map(|s| &s.artist)
+            .chain(alias_artists.iter())
+            .chain(
+                self.master
+                    .data
+                    .iter()
+                    .flat_map(|s| s.played_songs().filter_map(|song| song.original_artist.as_ref())),
+            );
+        for artist in artists {
+            let known = unique.entry(artist.name.clone()).or_insert_with(|| artist.clone());
+            for (field, value) in [
+                (&mut known.mbid, &artist.mbid),
+                (&mut known.sort_name, &artist.sort_name),
+                (&mut known.country, &artist.country),
+            ] {
+                if field.is_none() {
+                    field.clone_from(value);
+                }
+            }
+        }
+        unique
     }
 
     pub fn artists_count(&self) -> u64 {
@@ -179,38 +329,47 @@ impl Mpdb {
         self.extract_all_unique_cities().len() as u64
     }
 
-    fn extract_all_unique_venues(&self) -> HashSet<(String, String, String)> {
-        self.master
-            .data
-            .iter()
-            .map(|s| {
-                (
-                    s.venue.name.clone(),
-                    s.venue.city.name.clone(),
-                    s.venue.city.country.name.clone(),
-                )
-            })
-            .collect()
+    /// Returns every venue once as (name, city, country), with the first MusicBrainz ID given for it
+    fn extract_all_unique_venues(&self) -> HashMap<(String, String, String), Option<String>> {
+        with_mbids(self.master.data.iter().map(|s| {
+            let venue = (
+                s.venue.name.clone(),
+                s.venue.city.name.clone(),
+                s.venue.city.country.name.clone(),
+            );
+            (venue, s.venue.mbid.clone())
+        }))
     }
 
     pub fn venues_count(&self) -> u64 {
         self.extract_all_unique_venues().len() as u64
     }
 
-    fn extract_all_unique_songs(&self) -> HashSet<(String, Option<String>)> {
-        self.master
-            .data
-            .iter()
-            .flat_map(|s| {
-                s.sets.set.iter().flat_map(|set| {
-                    set.songs
-                        .as_ref()
-                        .map(|songs| songs.iter())
-                        .unwrap_or_else(|| [].iter())
-                        .map(|song| (song.name.clone(), song.original_artist.as_ref().map(|a| a.name.clone())))
-                })
-            })
-            .collect()
+    /// Returns every song title in the master data once (by slug) with the artist the song belongs to and the first
+    /// work MusicBrainz ID given for it
+    ///
+    /// A song belongs to the original artist of a cover, and otherwise to the artist of the setlist it was first
+    /// played in. Placeholders for unknown songs are left out, so no song called "Unknown" is created.
+    fn extract_all_unique_songs(&self) -> Vec<(String, String, Option<String>)> {
+        let mut songs: Vec<(String, String, Option<String>)> = vec![];
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for setlist in self.master.data.iter() {
+            for song in setlist.known_songs() {
+                match index.get(&song.name.slug()) {
+                    Some(&i) => {
+                        if songs[i].2.is_none() {
+                            songs[i].2 = song.work_mbid.clone();
+                        }
+                    }
+                    None => {
+                        let artist = song.original_artist.as_ref().unwrap_or(&setlist.artist);
+                        index.insert(song.name.slug(), songs.len());
+                        songs.push((song.name.clone(), artist.name.clone(), song.work_mbid.clone()));
+                    }
+                }
+            }
+        }
+        songs
     }
 
     pub fn songs_count(&self) -> u64 {
@@ -222,73 +381,97 @@ impl Mpdb {
     }
 
     pub fn performances_count(&self) -> u64 {
-        // AI generated, can probably be better... but works
-        self.master
-            .data
-            .iter()
-            .flat_map(|s| {
-                s.sets.set.iter().flat_map(|set| {
-                    set.songs
-                        .as_ref()
-                        .map(|songs| songs.iter())
-                        .unwrap_or_else(|| [].iter())
-                        .map(|song| song.name.clone())
-                })
-            })
-            .count() as u64
+        self.master.data.iter().flat_map(|s| s.played_songs()).count() as u64
     }
 
     fn get_country_id(&self, country_name: &str) -> Option<DbId> {
-        self.countries.iter().find(|c| c.name == country_name).map(|c| c.id)
+        self.state.countries.get(country_name).copied()
     }
 
     fn get_city_id(&self, city_name: &str, country_name: &str) -> Option<DbId> {
-        self.cities
-            .iter()
-            .find(|c| c.name == city_name && c.country_id == self.get_country_id(country_name).unwrap())
-            .map(|c| c.id)
+        self.state
+            .cities
+            .get(&city_slug(&city_name.to_string(), &country_name.to_string()))
+            .copied()
     }
 
     fn get_venue_id(&self, slug: &str) -> Option<DbId> {
-        self.venues.iter().find(|v| v.slug == slug).map(|v| v.id)
+        self.state.venues.get(slug).copied()
     }
 
     fn get_artist_id(&self, artist_name: &str) -> Option<DbId> {
-        debug!("Getting artist ID for {}", artist_name);
-        let id = self.artists.iter().find(|c| c.name == artist_name).map(|c| c.id);
-        let x = id.unwrap().0;
-        debug!("Artist ID: {}", x);
+        let id = self.state.artists.get(artist_name).copied();
+        debug!("Artist ID for {}: {:?}", artist_name, id);
         id
     }
 
     fn get_concert_id(&self, concert_slug: String) -> Option<DbId> {
-        self.concerts.iter().find(|c| c.slug == concert_slug).map(|c| c.id)
+        self.state.concerts.get(&concert_slug).copied()
     }
 
     fn get_song_id(&self, title: String) -> Option<DbId> {
-        self.songtitles
-            .iter()
-            .find(|s| s.title.slug() == title.slug())
-            .map(|s| s.song_id)
+        self.state.songtitles.get(&title.slug()).map(|s| s.song_id)
     }
 
-    fn get_songtitle_id(&self, title: String) -> Option<DbId> {
-        self.songtitles
-            .iter()
-            .find(|s| s.title.slug() == title.slug())
-            .map(|s| s.id)
+    /// Returns the ID of a song by the MusicBrainz ID of its work if known, and otherwise by one of its titles
+    fn find_song_id(&self, title: String, work_mbid: Option<&String>) -> Option<DbId> {
+        work_mbid
+            .and_then(|mbid| self.state.song_mbids.get(mbid).copied())
+            .or_else(|| self.get_song_id(title))
     }
 
-    pub async fn populate_countries(&self, pb: ProgressBar) -> reqwest::Result<Vec<Country>> {
-        let countries = self.extract_all_unique_country_names();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/countries", self.base_url);
+    fn get_songtitle_id(&self, title: String) -> Option<DbId> {
+        self.state.songtitles.get(&title.slug()).map(|s| s.id)
+    }
 
-        let existing_countries = client.get(&url).send().await?;
-        let existing_countries: Vec<Country> = existing_countries.json().await?;
-        let existing_countries: HashSet<String> = existing_countries.iter().map(|c| c.name.clone()).collect();
+    /// Fills the sync state with the IDs of all rows in the database, fetching every table once
+    ///
+    /// Concert hashes are unknown after this, so all concerts are synced again on the next populate.
+    pub async fn load_remote_state(&mut self) -> Result<(), StorageError> {
+        let countries: Vec<Country> = self.storage.list(Entity::Countries).await?;
+        self.state.countries = countries.into_iter().map(|c| (c.name, c.id)).collect();
+        let cities: Vec<City> = self.storage.list(Entity::Cities).await?;
+        self.state.cities = cities.into_iter().map(|c| (slug_or(c.slug, &c.name), c.id)).collect();
+        let venues: Vec<Venue> = self.storage.list(Entity::Venues).await?;
+        self.state.venue_mbids = mbid_ids(venues.iter().map(|v| (&v.mbid, v.id)));
+        self.state.venues = venues.into_iter().map(|v| (slug_or(v.slug, &v.name), v.id)).collect();
+        let artists: Vec<Artist> = self.storage.list(Entity::Artists).await?;
+        self.state.artist_mbids = mbid_ids(artists.iter().map(|a| (&a.mbid, a.id)));
+        self.state.artists = artists.into_iter().map(|a| (a.name, a.id)).collect();
+        let songs: Vec<Song> = self.storage.list(Entity::Songs).await?;
+        self.state.song_mbids = mbid_ids(songs.iter().map(|s| (&s.work_mbid, s.id)));
+        let songtitles: Vec<Songtitle> = self.storage.list(Entity::Songtitles).await?;
+        self.state.songtitles = songtitles
+            .into_iter()
+            .map(|s| {
+                let ids = SongtitleIds {
+                    id: s.id,
+                    song_id: s.song_id,
+                };
+                (s.title.slug(), ids)
+            })
+            .collect();
+        let concerts: Vec<Concert> = self.storage.list(Entity::Concerts).await?;
+        self.state.concert_mbids = mbid_ids(concerts.iter().map(|c| (&c.mbid, c.id)));
+        let artist_names: HashMap<DbId, &String> = self.state.artists.iter().map(|(name, id)| (*id, name)).collect();
+        self.state.concerts = concerts
+            .into_iter()
+            .map(|c| {
+                // Concerts have no name to fall back to, so rebuild the slug they are created with
+                let slug = match artist_names.get(&c.artist_id) {
+                    Some(artist) if c.slug.is_empty() => c.identifier_with_prefix(artist.to_string()),
+                    _ => slug_or(c.slug.clone(), &c.identifier()),
+                };
+                (slug, c.id)
+            })
+            .collect();
+        self.state.concert_hashes.clear();
+        Ok(())
+    }
 
-        // println!("Existing countries: {existing_countries:?}");
+    pub async fn populate_countries(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
+        let countries = self.extract_all_unique_country_names();
+        let mut report = StepReport::new("countries");
 
         for country in countries {
             let country_name = country.0.clone();
@@ -297,8 +480,9 @@ impl Mpdb {
             pb.set_message(format!("Country: {}", country_name));
 
             // Check if country already exists
-            if existing_countries.contains(&country_name) {
+            if self.state.countries.contains_key(&country_name) {
                 info!("[SKIP] {country_name} already exists.");
+                report.skipped += 1;
                 continue;
             }
 
@@ -311,11 +495,14 @@ impl Mpdb {
 
             debug!("Sending: {data:?}");
 
-            let res = client.post(&url).json(&data).send().await?;
-            if res.status().is_success() {
+            let res = self.storage.create(Entity::Countries, &data).await?;
+            if let Some(row) = res {
                 info!("[SUCC] {country_name} added (slug {})", country_name.slug());
+                self.state.countries.insert(country_name, row_id(&row));
+                report.created += 1;
             } else {
                 error!("[FAIL] adding country {country_name} (slug {})", country_name.slug());
+                report.fail(country_name);
             }
 
             pb.inc(1);
@@ -323,22 +510,12 @@ impl Mpdb {
 
         pb.finish_with_message("Countries");
 
-        let existing_countries = client.get(&url).send().await?;
-        let existing_countries: Vec<Country> = existing_countries.json().await?;
-        Ok(existing_countries)
+        Ok(report)
     }
 
-    pub async fn populate_cities(&self, pb: ProgressBar) -> reqwest::Result<Vec<City>> {
+    pub async fn populate_cities(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
         let cities = self.extract_all_unique_cities();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/cities", self.base_url);
-
-        let existing_cities = client.get(&url).send().await?;
-        let existing_cities: Vec<City> = existing_cities.json().await?;
-        let existing_cities: HashSet<(String, DbId)> =
-            existing_cities.iter().map(|c| (c.name.clone(), c.country_id)).collect();
-
-        // println!("Existing cities: {existing_cities:?}");
+        let mut report = StepReport::new("cities");
 
         for city in cities {
             info!("[ADD?] city {} in country {}", city.0, city.1);
@@ -346,167 +523,282 @@ impl Mpdb {
 
             if let Some(country_id) = self.get_country_id(&city.1) {
                 // Check if city already exists
-                if existing_cities.contains(&(city.0.clone(), country_id)) {
+                let slug = city_slug(&city.0, &city.1);
+                if self.state.cities.contains_key(&slug) {
                     // TODO: send update request instead of skipping?
                     info!("[SKIP] city {} in country {} already exists.", city.0, city.1);
+                    report.skipped += 1;
                     continue;
                 }
 
                 // City doesn't exist, so add it
-                let slug = format!("{}-{}", city.0.slug(), city.1.slug());
                 let data = serde_json::json!({
                     "name": city.0,
                     "country_id": country_id,
                     "slug": slug
                 });
-                let res = client.post(&url).json(&data).send().await?;
-                if res.status().is_success() {
+                let res = self.storage.create(Entity::Cities, &data).await?;
+                if let Some(row) = res {
                     info!("[SUCC] city {} in country {} added.", city.0, city.1);
+                    self.state.cities.insert(slug, row_id(&row));
+                    report.created += 1;
                 } else {
                     error!("Error adding city: {} in country: {}", city.0, city.1);
+                    report.fail(format!("{}, {}", city.0, city.1));
                 }
                 pb.inc(1);
+            } else {
+                error!("[FAIL] city {}: country {} does not exist", city.0, city.1);
+                report.fail(format!("{}, {} (country does not exist)", city.0, city.1));
             }
         }
         pb.finish_with_message("Cities");
 
-        let existing_cities = client.get(&url).send().await?;
-        let existing_cities: Vec<City> = existing_cities.json().await?;
-        Ok(existing_cities)
+        Ok(report)
     }
 
-    pub async fn populate_venues(&self, pb: ProgressBar) -> reqwest::Result<Vec<Venue>> {
+    pub async fn populate_venues(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
         let venues = self.extract_all_unique_venues();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/venues", self.base_url);
-
-        let existing_venues = client.get(&url).send().await?;
-        let existing_venues: Vec<Venue> = existing_venues.json().await?;
-        let existing_venues: HashSet<(String, DbId)> =
-            existing_venues.iter().map(|c| (c.name.clone(), c.city_id)).collect();
+        let mut report = StepReport::new("venues");
 
-        // println!("Existing venues: {existing_venues:?}");
-
-        for venue in venues {
+        for (venue, mbid) in venues {
             info!("[ADD?] venue {} in city {} in country {}", venue.0, venue.1, venue.2);
             pb.set_message(format!("Venue: {}", venue.0.clone()));
 
             if let Some(city_id) = self.get_city_id(&venue.1, &venue.2) {
-                // Check if venue already exists
-                if existing_venues.contains(&(venue.0.clone(), city_id)) {
-                    info!(
-                        "[SKIP] venue {} in city {} in country {} already exists.",
-                        venue.0, venue.1, venue.2
-                    );
+                let slug = venue_slug(&venue.0, &venue.1, &venue.2);
+
+                // A venue with a known MusicBrainz ID exists, even if it is stored under another name
+                if let Some(id) = mbid.as_ref().and_then(|m| self.state.venue_mbids.get(m).copied()) {
+                    info!("[SKIP] venue {} already exists (MBID {}).", venue.0, mbid.unwrap());
+                    self.state.venues.insert(slug, id);
+                    report.skipped += 1;
                     continue;
                 }
 
-                // venue doesn't exist, so add it
                 let unique_name = format!("{}-{}", venue.0.slug(), venue.1.slug());
-                let slug = venue_slug(&venue.0, &venue.1, &venue.2);
                 let data = serde_json::json!({
                     "name": venue.0,
                     "city_id": city_id,
                     "unique_name": unique_name,
-                    "slug": slug
+                    "slug": slug,
+                    "mbid": mbid
                 });
-                let res = client.post(&url).json(&data).send().await?;
 
-                if res.status().is_success() {
+                // Check if venue already exists; if it does, add the MusicBrainz ID to it
+                if let Some(id) = self.get_venue_id(&slug) {
+                    let Some(mbid) = mbid else {
+                        info!(
+                            "[SKIP] venue {} in city {} in country {} already exists.",
+                            venue.0, venue.1, venue.2
+                        );
+                        report.skipped += 1;
+                        continue;
+                    };
+                    if self.storage.update(Entity::Venues, id, &data).await?.is_some() {
+                        info!("[UPDT] venue {} got MBID {}", venue.0, mbid);
+                        self.state.venue_mbids.insert(mbid, id);
+                        report.updated += 1;
+                    } else {
+                        error!("[FAIL] updating venue {} with MBID {}", venue.0, mbid);
+                        report.fail(format!("{}, {}, {}", venue.0, venue.1, venue.2));
+                    }
+                    pb.inc(1);
+                    continue;
+                }
+
+                // venue doesn't exist, so add it
+                let res = self.storage.create(Entity::Venues, &data).await?;
+
+                if let Some(row) = res {
                     info!(
                         "[SUCC] venue {} in city {} in country {} added (slug {})",
                         venue.0, venue.1, venue.2, slug
                     );
+                    self.state.venues.insert(slug, row_id(&row));
+                    if let Some(mbid) = mbid {
+                        self.state.venue_mbids.insert(mbid, row_id(&row));
+                    }
+                    report.created += 1;
                 } else {
                     error!(
                         "[FAIL] adding venue {} in city {} - city id {} - in country {}",
                         venue.0, venue.1, city_id.0, venue.2
                     );
+                    report.fail(format!("{}, {}, {}", venue.0, venue.1, venue.2));
                 }
 
                 pb.inc(1);
+            } else {
+                error!(
+                    "[FAIL] venue {}: city {} in {} does not exist",
+                    venue.0, venue.1, venue.2
+                );
+                report.fail(format!("{}, {}, {} (city does not exist)", venue.0, venue.1, venue.2));
             }
         }
         pb.finish_with_message("Venues");
 
-        let existing_venues = client.get(&url).send().await?;
-        let existing_venues: Vec<Venue> = existing_venues.json().await?;
-        Ok(existing_venues)
+        Ok(report)
     }
 
-    pub async fn populate_artists(&self, pb: ProgressBar) -> reqwest::Result<Vec<Artist>> {
+    pub async fn populate_artists(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
         let artists = self.extract_all_unique_artists();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/artists", self.base_url);
-
-        // Make sure Motorpsycho exists and is the first artist
-        let mp = "Motorpsycho";
-        let data = serde_json::json!({
-            "name": mp,
-            "slug": mp.to_string().slug()
-        });
-        let res = client.post(&url).json(&data).send().await?;
-        if res.status().is_success() {
-            info!("[SUCC] Motorpsycho added");
-        } else {
-            warn!("[FAIL] adding Motorpsycho");
+        let mut report = StepReport::new("artists");
+
+        // Make sure the home artist exists and is the first artist
+        let home = self.home_artist.clone();
+        let mut home_added = false;
+        if !self.state.artists.contains_key(&home) {
+            let known = artists.get(&home);
+            let mbid = known.and_then(|a| a.mbid.clone());
+            let data = serde_json::json!({
+                "name": home,
+                "slug": home.slug(),
+                "mbid": mbid,
+                "sort_name": known.and_then(|a| a.sort_name.clone()),
+                "country": known.and_then(|a| a.country.clone())
+            });
+            let res = self.storage.create(Entity::Artists, &data).await?;
+            if let Some(row) = res {
+                info!("[SUCC] home artist {} added", home);
+                self.state.artists.insert(home.clone(), row_id(&row));
+                if let Some(mbid) = mbid {
+                    self.state.artist_mbids.insert(mbid, row_id(&row));
+                }
+                report.created += 1;
+            } else {
+                warn!("[FAIL] adding home artist {}", home);
+                report.fail(home.clone());
+            }
+            home_added = true;
         }
         pb.inc(1);
 
-        let existing_artists = client.get(&url).send().await?;
-        let existing_artists: Vec<Artist> = existing_artists.json().await?;
-        let existing_artists: HashSet<String> = existing_artists.iter().map(|a| a.name.clone()).collect();
-
-        for artist in artists {
+        for (artist, known) in artists {
+            let mbid = known.mbid;
             info!("[ADD?] artist {}", artist);
             pb.set_message(format!("Artist: {}", artist));
 
-            // Check if artist already exists
-            if existing_artists.contains(&artist) {
-                info!("[SKIP] artist {} already exists.", artist);
+            if artist == home && home_added {
+                continue;
+            }
+
+            // An artist with a known MusicBrainz ID exists, even if it is stored under another name
+            if let Some(id) = mbid.as_ref().and_then(|m| self.state.artist_mbids.get(m).copied()) {
+                info!("[SKIP] artist {} already exists (MBID {}).", artist, mbid.unwrap());
+                self.state.artists.insert(artist, id);
+                report.skipped += 1;
                 continue;
             }
 
-            // artist doesn't exist, so add it
             let slug = artist.slug();
             let data = serde_json::json!({
                 "name": artist,
-                "slug": slug
+                "slug": slug,
+                "mbid": mbid,
+                "sort_name": known.sort_name,
+                "country": known.country
             });
-            let res = client.post(&url).json(&data).send().await?;
-            if res.status().is_success() {
+
+            // Check if artist already exists; if it does, add the MusicBrainz ID to it
+            if let Some(id) = self.get_artist_id(&artist) {
+                let Some(mbid) = mbid else {
+                    info!("[SKIP] artist {} already exists.", artist);
+                    report.skipped += 1;
+                    continue;
+                };
+                if self.storage.update(Entity::Artists, id, &data).await?.is_some() {
+                    info!("[UPDT] artist {} got MBID {}", artist, mbid);
+                    self.state.artist_mbids.insert(mbid, id);
+                    report.updated += 1;
+                } else {
+                    error!("[FAIL] updating artist {} with MBID {}", artist, mbid);
+                    report.fail(artist);
+                }
+                pb.inc(1);
+                continue;
+            }
+
+            // artist doesn't exist, so add it
+            let res = self.storage.create(Entity::Artists, &data).await?;
+            if let Some(row) = res {
                 info!("[SUCC] artist {} added", artist);
+                self.state.artists.insert(artist, row_id(&row));
+                if let Some(mbid) = mbid {
+                    self.state.artist_mbids.insert(mbid, row_id(&row));
+                }
+                report.created += 1;
             } else {
                 error!("[FAIL] adding artist: {}", artist);
+                report.fail(artist);
             }
             pb.inc(1);
         }
         pb.finish_with_message("Artists");
 
-        let existing_artists = client.get(&url).send().await?;
-        let existing_artists: Vec<Artist> = existing_artists.json().await?;
-        Ok(existing_artists)
+        Ok(report)
     }
 
-    pub async fn populate_songaliases(&self) -> reqwest::Result<()> {
-        // let songtitles = self.extract_all_unique_songs();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/songtitles", self.base_url);
+    /// Returns the ID of the song for a new title: the song with the title's work MusicBrainz ID if it is known,
+    /// and otherwise a new song for the artist
+    ///
+    /// # Returns
+    /// * `Result<Result<(DbId, bool), String>, StorageError>` - The song ID and whether the song was created, the
+    ///   reason why no song could be created, or a storage error
+    async fn song_for_title(
+        &mut self,
+        title: &str,
+        artist: &str,
+        work_mbid: Option<&String>,
+    ) -> Result<Result<(DbId, bool), String>, StorageError> {
+        if let Some(id) = work_mbid.and_then(|mbid| self.state.song_mbids.get(mbid)) {
+            info!("[SONG] {} is song {} (MBID {})", title, id.0, work_mbid.unwrap());
+            return Ok(Ok((*id, false)));
+        }
+
+        let Some(artist_id) = self.get_artist_id(artist) else {
+            return Ok(Err(format!("artist {} does not exist", artist)));
+        };
+        let songdata = serde_json::json!({
+            "artist_id": artist_id,
+            "work_mbid": work_mbid,
+        });
+        let song_json = self.storage.create(Entity::Songs, &songdata).await?.unwrap_or_default();
+        let song_id = row_id(&song_json);
+        info!("[SONG] Created song with ID: {}, artist_id: {}", song_id.0, artist_id.0);
+        if let Some(mbid) = work_mbid {
+            self.state.song_mbids.insert(mbid.clone(), song_id);
+        }
+        Ok(Ok((song_id, true)))
+    }
 
-        let songclient = reqwest::Client::new();
-        let songurl = format!("{}/api/songs", self.base_url);
+    pub async fn populate_songaliases(&mut self) -> Result<StepReport, StorageError> {
+        // let songtitles = self.extract_all_unique_songs();
+        let mut report = StepReport::new("song aliases");
 
         debug!("Adding songaliases");
 
         for songwithaliases in self.aliases.songs.clone() {
-            // add a song and get the id
-            let songdata = serde_json::json!({
-                "artist_id": 1,
-            });
-            let songres = songclient.post(&songurl).json(&songdata).send().await?;
-            let song_json: serde_json::Value = songres.json().await?;
-            let song_id = song_json["id"].as_i64().unwrap_or_default();
-            info!("[SONG] Created song with ID: {}", song_id);
+            if self.state.songtitles.contains_key(&songwithaliases.name.slug()) {
+                info!("[SKIP] song {} already exists.", songwithaliases.name);
+                report.skipped += 1;
+                continue;
+            }
+
+            // add a song for its artist, or find it by its MusicBrainz ID, and get the id
+            let artist = songwithaliases.artist.clone().unwrap_or(self.home_artist.clone());
+            let song_id = match self
+                .song_for_title(&songwithaliases.name, &artist, songwithaliases.work_mbid.as_ref())
+                .await?
+            {
+                Ok((song_id, _)) => song_id.0 as i64,
+                Err(reason) => {
+                    error!("[FAIL] song {}: {}", songwithaliases.name, reason);
+                    report.fail(format!("{} ({})", songwithaliases.name, reason));
+                    continue;
+                }
+            };
 
             // add the default songtitle
             let slug = songwithaliases.name.slug();
@@ -516,21 +808,28 @@ impl Mpdb {
                 "is_default": true,
                 "song_id": song_id,
             });
-            let res = client.post(&url).json(&data).send().await?;
-            if res.status().is_success() {
+            let res = self.storage.create(Entity::Songtitles, &data).await?;
+            if let Some(row) = &res {
                 info!(
                     "[SUCC] songtitle {} added, slug {}, song_id {}",
                     songwithaliases.name, slug, song_id
                 );
+                let ids = SongtitleIds {
+                    id: row_id(row),
+                    song_id: DbId(song_id as i32),
+                };
+                self.state.songtitles.insert(slug, ids);
+                report.created += 1;
             } else {
                 warn!(
                     "[FAIL] adding songtitle: {}, slug {}, song_id {}",
                     songwithaliases.name, slug, song_id
                 );
+                report.fail(songwithaliases.name.clone());
             }
 
             // Find songtitle id for the default songtitle
-            let songtitle_json: serde_json::Value = res.json().await?;
+            let songtitle_json = res.unwrap_or_default();
             let songtitle_id = songtitle_json["id"].as_i64().unwrap_or_default();
 
             // add the aliases
@@ -543,113 +842,491 @@ impl Mpdb {
                     "song_id": song_id,
                     "alias_for": Some(songtitle_id)
                 });
-                let res = client.post(&url).json(&data).send().await?;
-                if res.status().is_success() {
+                let res = self.storage.create(Entity::Songtitles, &data).await?;
+                if let Some(row) = res {
                     info!(
                         "[SUCC] alias songtitle {} added, slug {}, song_id {}",
                         alias.name, slug, song_id
                     );
+                    let ids = SongtitleIds {
+                        id: row_id(&row),
+                        song_id: DbId(song_id as i32),
+                    };
+                    self.state.songtitles.insert(slug, ids);
+                    report.created += 1;
                 } else {
                     warn!(
                         "[FAIL] adding alias songtitle: {}, slug {}, song_id {}",
                         alias.name, slug, song_id
                     );
+                    report.fail(format!("{} (alias of {})", alias.name, songwithaliases.name));
                 }
             }
         }
-        Ok(())
+        Ok(report)
     }
 
-    pub async fn populate_songtitles(&self, pb: ProgressBar) -> reqwest::Result<Vec<Songtitle>> {
+    pub async fn populate_songtitles(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
         let songtitles = self.extract_all_unique_songs();
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/songtitles", self.base_url);
-
-        let songclient = reqwest::Client::new();
-        let songurl = format!("{}/api/songs", self.base_url);
+        let mut report = StepReport::new("song titles");
 
         debug!("Songtitles: {songtitles:?}");
 
-        let existing_songtitles = client.get(&url).send().await?;
-        let existing_songtitles: Vec<Songtitle> = existing_songtitles.json().await?;
-        let existing_songtitles: HashSet<String> = existing_songtitles
-            .iter()
-            .map(|s| s.title.clone().to_lowercase())
-            .collect();
-
-        for songtitle in songtitles {
-            pb.set_message(format!("Songtitle: {}", songtitle.0.clone()));
+        for (title, artist, work_mbid) in songtitles {
+            pb.set_message(format!("Songtitle: {}", title.clone()));
             // Check if songtitle already exists
-            if existing_songtitles.contains(&songtitle.0.to_lowercase()) {
-                info!(
-                    "[SKIP] songtitle {} (slug {}) already exists.",
-                    songtitle.0,
-                    songtitle.0.slug()
-                );
+            if self.state.songtitles.contains_key(&title.slug()) {
+                info!("[SKIP] songtitle {} (slug {}) already exists.", title, title.slug());
+                report.skipped += 1;
                 continue;
             }
 
             // songtitle doesn't exist, so add it
 
-            // add a song and get the id
-            let artist_id = songtitle
-                .1
-                .as_ref()
-                .and_then(|artist_name| self.get_artist_id(artist_name))
-                .unwrap_or_else(|| {
-                    self.get_artist_id("Motorpsycho")
-                        .expect("Artist Motorpsycho should exist!")
-                });
-
-            let songdata = serde_json::json!({
-                "artist_id": artist_id,
-            });
-            let songres = songclient.post(&songurl).json(&songdata).send().await?;
-            let song_json: serde_json::Value = songres.json().await?;
-            let song_id = song_json["id"].as_i64().unwrap_or_default();
-            info!("[SONG] Created song with ID: {}, artist_id: {}", song_id, artist_id.0);
+            // add a song for its artist, or find it by its MusicBrainz ID, and get the id
+            let (song_id, is_default) = match self.song_for_title(&title, &artist, work_mbid.as_ref()).await? {
+                Ok((song_id, created)) => (song_id.0 as i64, created),
+                Err(reason) => {
+                    error!("[FAIL] songtitle {}: {}", title, reason);
+                    report.fail(format!("{} ({})", title, reason));
+                    continue;
+                }
+            };
 
-            // add the songtitle
-            let slug = songtitle.0.slug();
+            // add the songtitle; a new title for a known song is not its default title
+            let slug = title.slug();
             let data = serde_json::json!({
-                "title": songtitle.0,
+                "title": title,
                 "slug": slug,
-                "is_default": true,
+                "is_default": is_default,
                 "song_id": song_id,
             });
-            let res = client.post(&url).json(&data).send().await?;
-            if res.status().is_success() {
-                info!(
-                    "[SUCC] songtitle {} added, slug {}, song_id {}",
-                    songtitle.0, slug, song_id
-                );
+            let res = self.storage.create(Entity::Songtitles, &data).await?;
+            if let Some(row) = res {
+                info!("[SUCC] songtitle {} added, slug {}, song_id {}", title, slug, song_id);
+                let ids = SongtitleIds {
+                    id: row_id(&row),
+                    song_id: DbId(song_id as i32),
+                };
+                self.state.songtitles.insert(slug, ids);
+                report.created += 1;
             } else {
-                warn!(
-                    "[FAIL] adding songtitle: {}, slug {}, song_id {}",
-                    songtitle.0, slug, song_id
-                );
+                warn!("[FAIL] adding songtitle: {}, slug {}, song_id {}", title, slug, song_id);
+                report.fail(title);
             }
 
             pb.inc(1);
         }
         pb.finish_with_message("Songs");
 
-        let existing_songtitles = client.get(&url).send().await?;
-        let existing_songtitles: Vec<Songtitle> = existing_songtitles.json().await?;
+        Ok(report)
+    }
+
+    /// Updates the songs in the song catalogue with their metadata
+    ///
+    /// Songs are found by the default title of their song aliases entry, or by the title they are played as, and
+    /// only updated if their metadata differs. The original artist of a cover in the catalogue becomes the artist of
+    /// the song. Songs that are not in the database are skipped.
+    pub async fn populate_song_metadata(&mut self) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("song metadata");
+        if self.catalogue.songs.is_empty() {
+            return Ok(report);
+        }
+        let songs: HashMap<DbId, Song> = self
+            .storage
+            .list::<Song>(Entity::Songs)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        for info in self.catalogue.songs.clone() {
+            let title = self.aliases.canonical_name(&info.name);
+            let Some(song) = self
+                .state
+                .songtitles
+                .get(&title.slug())
+                .and_then(|ids| songs.get(&ids.song_id))
+            else {
+                warn!("[SKIP] song {} is not in the database", title);
+                report.skipped += 1;
+                continue;
+            };
+            let artist_id = match &info.original_artist {
+                Some(artist) => match self.get_artist_id(artist) {
+                    Some(id) => id,
+                    None => {
+                        error!("[FAIL] song {}: artist {} does not exist", title, artist);
+                        report.fail(format!("{} (artist {} does not exist)", title, artist));
+                        continue;
+                    }
+                },
+                None => song.artist_id,
+            };
+            let updated = Song {
+                artist_id,
+                album: info.album.clone(),
+                composers: info.composer_names(),
+                release_year: info.release_year,
+                instrumental: info.instrumental,
+                duration: info.duration_seconds(),
+                ..song.clone()
+            };
+            if &updated == song {
+                info!("[SKIP] song {} metadata is up to date", title);
+                report.skipped += 1;
+                continue;
+            }
+            if self.storage.update(Entity::Songs, song.id, &updated).await?.is_some() {
+                info!("[UPDT] song {} metadata", title);
+                report.updated += 1;
+            } else {
+                error!("[FAIL] updating song {} metadata", title);
+                report.fail(title);
+            }
+        }
 
-        Ok(existing_songtitles)
+        Ok(report)
     }
 
-    pub async fn populate_concerts(&self, pb: ProgressBar) -> reqwest::Result<Vec<Concert>> {
-        let client = reqwest::Client::new();
-        let url = format!("{}/api/concerts", self.base_url);
+    /// Adds the albums of the discography for the home artist, and links them to the songs on their tracklists
+    ///
+    /// An album that exists (by slug) is only updated, and gets its tracks relinked, if it or its tracklist changed.
+    /// Tracks that are not a known song are reported as failed; the rest of the album is still linked, keeping the
+    /// tracklist positions.
+    pub async fn populate_albums(&mut self) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("albums");
+        if self.discography.albums.is_empty() {
+            return Ok(report);
+        }
+        let Some(artist_id) = self.get_artist_id(&self.home_artist.clone()) else {
+            report.fail(format!("home artist {} does not exist", self.home_artist));
+            return Ok(report);
+        };
+        let albums: HashMap<String, Album> = self
+            .storage
+            .list::<Album>(Entity::Albums)
+            .await?
+            .into_iter()
+            .map(|a| (slug_or(a.slug.clone(), &a.title), a))
+            .collect();
+        let tracks: Vec<AlbumTrack> = self.storage.list(Entity::AlbumTracks).await?;
+
+        for album in self.discography.albums.clone() {
+            info!("[ADD?] album {}", album.title);
+            let mut wanted = vec![];
+            for (position, track) in album.tracks.iter().enumerate() {
+                let title = self.aliases.canonical_name(&track.name);
+                match self.state.songtitles.get(&title.slug()) {
+                    Some(ids) => wanted.push((ids.song_id, position as i32 + 1)),
+                    None => {
+                        error!("[FAIL] album {}: track {} is not a known song", album.title, title);
+                        report.fail(format!("{}: {} (not a known song)", album.title, title));
+                    }
+                }
+            }
+            let mut row = Album {
+                artist_id,
+                title: album.title.clone(),
+                slug: album.slug(),
+                year: album.year,
+                mbid: album.mbid.clone(),
+                ..Default::default()
+            };
+
+            match albums.get(&row.slug) {
+                Some(existing) => {
+                    row.id = existing.id;
+                    let linked: Vec<&AlbumTrack> = tracks.iter().filter(|t| t.album_id == existing.id).collect();
+                    let mut current: Vec<(DbId, i32)> = linked.iter().map(|t| (t.song_id, t.position)).collect();
+                    current.sort_by_key(|(_, position)| *position);
+                    if &row == existing && current == wanted {
+                        info!("[SKIP] album {} is up to date", album.title);
+                        report.skipped += 1;
+                        continue;
+                    }
+                    if self.storage.update(Entity::Albums, row.id, &row).await?.is_none() {
+                        error!("[FAIL] updating album {}", album.title);
+                        report.fail(album.title);
+                        continue;
+                    }
+                    for track in linked {
+                        self.storage.delete(Entity::AlbumTracks, track.id).await?;
+                    }
+                    info!("[UPDT] album {}", album.title);
+                    report.updated += 1;
+                }
+                None => match self.storage.create(Entity::Albums, &row).await? {
+                    Some(created) => {
+                        row.id = row_id(&created);
+                        info!("[SUCC] album {} added", album.title);
+                        report.created += 1;
+                    }
+                    None => {
+                        error!("[FAIL] adding album {}", album.title);
+                        report.fail(album.title);
+                        continue;
+                    }
+                },
+            }
+
+            for (song_id, position) in wanted {
+                let data = serde_json::json!({
+                    "album_id": row.id,
+                    "song_id": song_id,
+                    "position": position,
+                });
+                if self.storage.create(Entity::AlbumTracks, &data).await?.is_none() {
+                    error!("[FAIL] linking track {} of album {}", position, album.title);
+                    report.fail(format!("{}: track {}", album.title, position));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns true if any concert in the master data has band members or guests to sync
+    fn has_personnel(&self) -> bool {
+        self.master.data.iter().any(|s| !self.lineups.personnel(s).is_empty())
+    }
+
+    /// Links the musicians who played each concert to the concert, creating musicians that do not exist yet
+    ///
+    /// The band members come from the lineups file unless a setlist lists its own, and the guests from the setlist
+    /// and its songs. A concert whose personnel in the database differs is relinked; concerts that do not exist are
+    /// reported as failed. Nothing is listed or sent if no concert has any personnel.
+    pub async fn populate_personnel(&mut self) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("personnel");
+        if !self.has_personnel() {
+            return Ok(report);
+        }
+        let mut musicians: HashMap<String, DbId> = self
+            .storage
+            .list::<Musician>(Entity::Musicians)
+            .await?
+            .into_iter()
+            .map(|m| (slug_or(m.slug, &m.name), m.id))
+            .collect();
+        let personnel: Vec<Personnel> = self.storage.list(Entity::Personnel).await?;
+
+        for setlist in self.master.data.iter() {
+            // A concert with an invalid date already failed in the concerts step
+            let Some(concert_slug) = Self::concert_slug(setlist) else {
+                continue;
+            };
+            let players = self.lineups.personnel(setlist);
+            let Some(concert_id) = self.get_concert_id(concert_slug.clone()) else {
+                if !players.is_empty() {
+                    warn!("[FAIL] concert {} does not exist, skipping its personnel", concert_slug);
+                    report.fail(format!("{} (concert does not exist)", concert_slug));
+                }
+                continue;
+            };
+
+            let mut wanted = vec![];
+            for player in players {
+                let slug = player.name.slug();
+                let musician_id = match musicians.get(&slug) {
+                    Some(id) => *id,
+                    None => {
+                        let data = serde_json::json!({ "name": player.name, "slug": slug });
+                        match self.storage.create(Entity::Musicians, &data).await? {
+                            Some(row) => {
+                                info!("[SUCC] musician {} added", player.name);
+                                musicians.insert(slug, row_id(&row));
+                                row_id(&row)
+                            }
+                            None => {
+                                error!("[FAIL] adding musician {}", player.name);
+                                report.fail(format!("{}: {}", concert_slug, player.name));
+                                continue;
+                            }
+                        }
+                    }
+                };
+                wanted.push(Personnel {
+                    concert_id,
+                    musician_id,
+                    instrument: player.instrument,
+                    guest: player.guest,
+                    ..Default::default()
+                });
+            }
+
+            let linked: Vec<&Personnel> = personnel.iter().filter(|p| p.concert_id == concert_id).collect();
+            let mut current: Vec<Personnel> = linked
+                .iter()
+                .map(|p| Personnel {
+                    id: DbId::default(),
+                    ..(*p).clone()
+                })
+                .collect();
+            current.sort_by_key(|p| (p.musician_id.0, p.instrument.clone(), p.guest));
+            wanted.sort_by_key(|p| (p.musician_id.0, p.instrument.clone(), p.guest));
+            if current == wanted {
+                if !wanted.is_empty() {
+                    info!("[SKIP] personnel of {} is up to date", concert_slug);
+                    report.skipped += 1;
+                }
+                continue;
+            }
+
+            for row in linked {
+                self.storage.delete(Entity::Personnel, row.id).await?;
+            }
+            for row in wanted.iter() {
+                if self.storage.create(Entity::Personnel, row).await?.is_none() {
+                    error!("[FAIL] linking musician {} to {}", row.musician_id.0, concert_slug);
+                    report.fail(format!("{}: musician {}", concert_slug, row.musician_id.0));
+                }
+            }
+            info!("[SUCC] personnel of {} linked", concert_slug);
+            if current.is_empty() {
+                report.created += 1;
+            } else {
+                report.updated += 1;
+            }
+        }
 
-        let existing_concerts = client.get(&url).send().await?;
-        let existing_concerts: Vec<Concert> = existing_concerts.json().await?;
-        debug!("Existing concerts: {:?}", existing_concerts);
+        Ok(report)
+    }
+
+    /// Returns true if any concert in the master data has a known recording to sync
+    fn has_sources(&self) -> bool {
+        self.master.data.iter().any(|s| !s.recordings().is_empty())
+    }
+
+    /// Creates the known recordings of each concert, with legacy source strings parsed into structured sources
+    ///
+    /// A concert whose sources in the database differ gets them recreated; concerts that do not exist are reported
+    /// as failed. Nothing is listed or sent if no concert has any source.
+    pub async fn populate_sources(&mut self) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("sources");
+        if !self.has_sources() {
+            return Ok(report);
+        }
+        let sources: Vec<Source> = self.storage.list(Entity::Sources).await?;
+
+        for setlist in self.master.data.iter() {
+            // A concert with an invalid date already failed in the concerts step
+            let Some(concert_slug) = Self::concert_slug(setlist) else {
+                continue;
+            };
+            let recordings = setlist.recordings();
+            let Some(concert_id) = self.get_concert_id(concert_slug.clone()) else {
+                if !recordings.is_empty() {
+                    warn!("[FAIL] concert {} does not exist, skipping its sources", concert_slug);
+                    report.fail(format!("{} (concert does not exist)", concert_slug));
+                }
+                continue;
+            };
+
+            let wanted: Vec<Source> = recordings
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| Source {
+                    concert_id,
+                    kind: r.kind,
+                    lineage: r.lineage,
+                    taper: r.taper,
+                    archive_id: r.archive_id,
+                    complete: r.complete,
+                    notes: r.notes,
+                    sort_order: i as i32,
+                    ..Default::default()
+                })
+                .collect();
+            let mut linked: Vec<&Source> = sources.iter().filter(|s| s.concert_id == concert_id).collect();
+            linked.sort_by_key(|s| s.sort_order);
+            let current: Vec<Source> = linked
+                .iter()
+                .map(|s| Source {
+                    id: DbId::default(),
+                    ..(*s).clone()
+                })
+                .collect();
+            if current == wanted {
+                if !wanted.is_empty() {
+                    info!("[SKIP] sources of {} are up to date", concert_slug);
+                    report.skipped += 1;
+                }
+                continue;
+            }
+
+            for row in linked {
+                self.storage.delete(Entity::Sources, row.id).await?;
+            }
+            for row in wanted.iter() {
+                if self.storage.create(Entity::Sources, row).await?.is_none() {
+                    error!("[FAIL] adding source {} of {}", row.sort_order + 1, concert_slug);
+                    report.fail(format!("{}: source {}", concert_slug, row.sort_order + 1));
+                }
+            }
+            info!("[SUCC] sources of {} added", concert_slug);
+            if current.is_empty() {
+                report.created += 1;
+            } else {
+                report.updated += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns the date of the concert of a setlist, or `None` if it is not a `dd-mm-yyyy` date
+    fn concert_date(setlist: &Setlist) -> Option<chrono::NaiveDate> {
+        chrono::NaiveDate::parse_from_str(&setlist.event_date, "%d-%m-%Y").ok()
+    }
+
+    /// Returns the slug of the concert of a setlist, as used in the database, or `None` if its date is invalid
+    fn concert_slug(setlist: &Setlist) -> Option<String> {
+        let concert = Concert {
+            date: Self::concert_date(setlist)?,
+            disambiguation: setlist.disambiguation.clone(),
+            ..Default::default()
+        };
+        Some(concert.identifier_with_prefix(setlist.artist.name.clone()))
+    }
+
+    /// Returns true if a concert was fully synced with exactly this setlist before
+    fn is_unchanged(&self, setlist: &Setlist) -> bool {
+        let Some(slug) = Self::concert_slug(setlist) else {
+            return false;
+        };
+        self.state.concerts.contains_key(&slug) && self.state.concert_hashes.get(&slug) == Some(&setlist_hash(setlist))
+    }
+
+    pub async fn populate_concerts(&mut self, pb: ProgressBar) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("concerts");
+        let mut synced = vec![];
+        let mut renamed = vec![];
+        let mut stale = HashSet::new();
 
         for setlist in self.master.data.iter() {
             pb.set_message(format!("Concert: {}", setlist.event_date));
+            // Populating does not validate the master data first, so an invalid date must not abort the run
+            let Some(date) = Self::concert_date(setlist) else {
+                error!(
+                    "[FAIL] {} has an invalid date '{}'",
+                    setlist.identifier(),
+                    setlist.event_date
+                );
+                report.fail(format!(
+                    "{} (invalid date '{}')",
+                    setlist.identifier(),
+                    setlist.event_date
+                ));
+                pb.inc(1);
+                continue;
+            };
+            if self.is_unchanged(setlist) {
+                info!("[SKIP] {} is unchanged", setlist.identifier());
+                report.skipped += 1;
+                pb.inc(1);
+                continue;
+            }
+
             // Create a concert object
             let artist_id = self.get_artist_id(&setlist.artist.name);
             let venue_slug = venue_slug(
@@ -660,43 +1337,63 @@ impl Mpdb {
             let venue_id = self.get_venue_id(&venue_slug);
             let mut concert = Concert {
                 artist_id: artist_id.unwrap_or_default(),
-                date: chrono::NaiveDate::parse_from_str(&setlist.event_date, "%d-%m-%Y").unwrap(),
+                date,
                 venue_id: venue_id.unwrap_or_default(),
                 disambiguation: setlist.disambiguation.clone(),
                 sort_order: setlist.sort_order,
                 source: setlist.source.clone(),
+                mbid: setlist.mbid.clone(),
                 ..Default::default()
             };
 
             // Now we can create the slug for the concert
             concert.slug = concert.identifier_with_prefix(setlist.artist.name.clone());
 
-            // Loop through the existing concerts and check if the slug already exists
-            // If it does, update the concert
-            // If it doesn't, add the concert
-            if existing_concerts.iter().any(|c| c.slug == concert.slug) {
+            // If the concert already exists, update it, and remember to recreate its sets and performances.
+            // If it doesn't, add it. A concert found by its MusicBrainz ID may have had another slug.
+            let by_mbid = setlist
+                .mbid
+                .as_ref()
+                .and_then(|m| self.state.concert_mbids.get(m).copied());
+            if let Some(id) = by_mbid.or_else(|| self.get_concert_id(concert.slug.clone())) {
                 info!("[UPDT] {} already exists - updating", concert.slug);
+                concert.id = id;
 
-                concert.id = existing_concerts
-                    .iter()
-                    .find(|c| c.slug == concert.slug)
-                    .map(|c| c.id)
-                    .unwrap_or_default();
-
-                let url = format!("{}/api/concerts/{}", self.base_url, concert.id.0);
-                let res = client.put(&url).json(&concert).send().await?;
-                if res.status().is_success() {
+                let res = self.storage.update(Entity::Concerts, concert.id, &concert).await?;
+                if res.is_some() {
                     info!("[SUCC] {} updated", concert.slug);
+                    let old_slug = self
+                        .state
+                        .concerts
+                        .iter()
+                        .find(|(_, c)| **c == id)
+                        .map(|(slug, _)| slug);
+                    if let Some(old_slug) = old_slug.filter(|slug| **slug != concert.slug) {
+                        info!("[UPDT] {} was {}", concert.slug, old_slug);
+                        renamed.push((old_slug.clone(), concert.slug.clone(), id));
+                    }
+                    if let Some(mbid) = concert.mbid.clone() {
+                        self.state.concert_mbids.insert(mbid, id);
+                    }
+                    stale.insert(concert.slug.clone());
+                    report.updated += 1;
                 } else {
                     error!("[FAIL] updating concert {}", concert.slug);
+                    report.fail(concert.slug);
                 }
             } else {
                 info!("[ADD!] {}", concert.slug);
-                let res = client.post(&url).json(&concert).send().await?;
-                if res.status().is_success() {
+                let res = self.storage.create(Entity::Concerts, &concert).await?;
+                if let Some(row) = res {
                     info!("[SUCC] {} added", concert.slug);
+                    if let Some(mbid) = concert.mbid {
+                        self.state.concert_mbids.insert(mbid, row_id(&row));
+                    }
+                    synced.push((concert.slug, row_id(&row)));
+                    report.created += 1;
                 } else {
                     error!("[FAIL] adding concert {}", concert.slug);
+                    report.fail(concert.slug);
                 }
             }
 
@@ -704,35 +1401,54 @@ impl Mpdb {
         }
         pb.finish_with_message("Concerts");
 
-        let existing_concerts = client.get(&url).send().await?;
-        let existing_concerts: Vec<Concert> = existing_concerts.json().await?;
-        Ok(existing_concerts)
+        self.state.concerts.extend(synced);
+        for (old_slug, slug, id) in renamed {
+            self.state.concerts.remove(&old_slug);
+            self.state.concert_hashes.remove(&old_slug);
+            self.state.concerts.insert(slug, id);
+        }
+        if !stale.is_empty() {
+            // Only failures matter here: the sets and performances are recreated by populate_performances
+            let cleared = self.clear_concerts(&stale, false).await?;
+            report.failed += cleared.failed;
+            report.failures.extend(cleared.failures);
+        }
+
+        Ok(report)
     }
 
-    pub async fn populate_performances(&self, pb: ProgressBar) -> reqwest::Result<()> {
-        let client = reqwest::Client::new();
-        let set_url = format!("{}/api/sets", self.base_url);
-        let performance_url = format!("{}/api/performances", self.base_url);
+    /// Creates the sets and performances of all concerts that changed since they were last synced
+    ///
+    /// # Returns
+    /// * `Result<(StepReport, StepReport), StorageError>` - The reports for the sets and for the performances, or a
+    ///   storage error
+    pub async fn populate_performances(&mut self, pb: ProgressBar) -> Result<(StepReport, StepReport), StorageError> {
+        let mut sets_report = StepReport::new("sets");
+        let mut report = StepReport::new("performances");
+        let mut synced = vec![];
 
         for setlist in self.master.data.iter() {
-            let concert = Concert {
-                date: chrono::NaiveDate::parse_from_str(&setlist.event_date, "%d-%m-%Y").unwrap(),
-                disambiguation: setlist.disambiguation.clone(),
-                ..Default::default()
+            // A concert with an invalid date already failed in the concerts step
+            let Some(concert_slug) = Self::concert_slug(setlist) else {
+                pb.inc(setlist.played_songs().count() as u64);
+                continue;
+            };
+            if self.is_unchanged(setlist) {
+                sets_report.skipped += setlist.sets.set.len();
+                report.skipped += setlist.played_songs().count();
+                pb.inc(setlist.played_songs().count() as u64);
+                continue;
+            }
+            let Some(concert_id) = self.get_concert_id(concert_slug.clone()) else {
+                warn!("[FAIL] concert {} does not exist, skipping its sets", concert_slug);
+                sets_report.fail(format!("{} (concert does not exist)", concert_slug));
+                pb.inc(setlist.played_songs().count() as u64);
+                continue;
             };
-            let concert_slug = concert.identifier_with_prefix(setlist.artist.name.clone());
-            let concert_id = self.get_concert_id(concert_slug.clone()).unwrap_or_default();
+            let mut complete = true;
 
             for (i, set) in setlist.sets.set.iter().enumerate() {
-                let set_name = if set.encore.is_some() {
-                    Some(format!("Encore {}", set.encore.as_ref().unwrap()))
-                } else if set.name.is_some() {
-                    // Set is named, and is not an encore
-                    set.name.clone()
-                } else {
-                    // Set is not named, and not an encore
-                    None
-                };
+                let set_name = set.title();
                 let setdata = Set {
                     concert_id,
                     name: set_name.clone(),
@@ -742,71 +1458,384 @@ impl Mpdb {
                         set_name.clone().unwrap_or("main set".to_string()).slug()
                     ),
                     sort_order: i as i32,
+                    uncertain_order: set.uncertain_order.unwrap_or(false),
+                    partial: set.partial.unwrap_or(false),
                     ..Default::default()
                 };
 
-                // For now we assume the db is empty and we don't have to deal with existing entities
-
                 info!(
                     "[ADD!] set {} for concert {}",
                     setdata.unique_name.clone(),
                     concert_slug.clone()
                 );
-                let res = client.post(&set_url).json(&setdata).send().await?;
+                let res = self.storage.create(Entity::Sets, &setdata).await?;
 
-                if res.status().is_success() {
+                if res.is_some() {
                     info!(
                         "[SUCC] set {} for concert {} added",
                         setdata.unique_name.clone(),
                         concert_slug
                     );
+                    sets_report.created += 1;
                 } else {
                     warn!(
                         "[FAIL] set {} for concert {}",
                         setdata.unique_name.clone(),
                         concert_slug
                     );
+                    sets_report.fail(setdata.unique_name.clone());
+                    complete = false;
                 }
 
-                let set_json: serde_json::Value = res.json().await?;
-                let set_id = set_json["id"].as_i64().unwrap_or_default() as i32;
+                let set_id = row_id(&res.unwrap_or_default());
                 let artist_id = self.get_artist_id(&setlist.artist.name);
 
-                if set.songs.is_some() {
-                    for (i, performance) in set.songs.clone().unwrap().iter().enumerate() {
-                        pb.set_message(format!("Performance of: {}", performance.name.clone()));
-                        let song_id = self.get_song_id(performance.name.clone());
-                        let songtitle_id = self.get_songtitle_id(performance.name.clone());
-                        info!("[ADD!] performance of song '{}'", performance.name);
-
-                        let perfdata = Performance {
-                            segue: performance.segue.unwrap_or(false),
-                            set_id: DbId(set_id),
-                            concert_id,
-                            artist_id: artist_id.unwrap(),
-                            song_id: song_id.unwrap(),
-                            songtitle_id: songtitle_id.unwrap(),
-                            sort_order: i as i32,
-                            ..Default::default()
-                        };
-
-                        let res = client.post(&performance_url).json(&perfdata).send().await?;
-                        if res.status().is_success() {
-                            info!("[SUCC] performance of song '{}' added", performance.name);
-                        } else {
-                            warn!("[FAIL] performance of song '{}'", performance.name);
-                            warn!("payload: {}", serde_json::json!(&perfdata));
+                if let Some(songs) = &set.songs {
+                    for (i, slot) in songs.iter().enumerate() {
+                        // A medley is one slot: each part gets a performance at the same position, linked to the first
+                        let mut medley_id = None;
+                        for (j, part) in slot.components().iter().enumerate() {
+                            // Guests, teases and tags of a whole medley apply to each of its parts
+                            let performance = &slot.medley_part(part);
+                            // Unknown songs are left out, keeping the positions of the songs after them
+                            if performance.is_unknown() {
+                                info!("[SKIP] unknown song {} of set {}", i + 1, setdata.unique_name);
+                                report.skipped += 1;
+                                pb.inc(1);
+                                continue;
+                            }
+                            pb.set_message(format!("Performance of: {}", performance.name.clone()));
+                            let song_id = self.find_song_id(performance.name.clone(), performance.work_mbid.as_ref());
+                            let songtitle_id = self.get_songtitle_id(performance.name.clone());
+                            info!("[ADD!] performance of song '{}'", performance.name);
+
+                            let perfdata = Performance {
+                                segue: performance.segue.or(slot.segue).unwrap_or(false),
+                                set_id,
+                                concert_id,
+                                artist_id: artist_id.unwrap_or_default(),
+                                song_id: song_id.unwrap_or_default(),
+                                songtitle_id: songtitle_id.unwrap_or_default(),
+                                sort_order: i as i32,
+                                recording_mbid: performance.recording_mbid.clone(),
+                                duration: performance.duration_seconds(),
+                                guests: performance.guest_names(),
+                                teases: performance.tease_names(),
+                                tags: performance.tag_names(),
+                                medley: slot.is_medley().then(|| slot.name.clone()),
+                                medley_position: slot.is_medley().then_some(j as i32),
+                                medley_id,
+                                ..Default::default()
+                            };
+
+                            let res = self.storage.create(Entity::Performances, &perfdata).await?;
+                            if let Some(row) = &res {
+                                info!("[SUCC] performance of song '{}' added", performance.name);
+                                report.created += 1;
+                                if slot.is_medley() && medley_id.is_none() {
+                                    medley_id = Some(row_id(row));
+                                }
+                            } else {
+                                warn!("[FAIL] performance of song '{}'", performance.name);
+                                warn!("payload: {}", serde_json::json!(&perfdata));
+                                report.fail(format!("{}: {} ({})", setdata.unique_name, performance.name, i + 1));
+                                complete = false;
+                            }
+
+                            pb.inc(1);
                         }
-
-                        pb.inc(1);
                     }
                 } else {
                     info!("[NULL] no performances found in this set");
                 }
             }
+
+            // Only remember the hash if everything was synced, so failed concerts are retried next time
+            if complete {
+                synced.push((concert_slug, setlist_hash(setlist)));
+            }
         }
         pb.finish_with_message("Performances");
 
-        Ok(())
+        self.state.concert_hashes.extend(synced);
+
+        Ok((sets_report, report))
+    }
+
+    /// Deletes the sets and performances of concerts, and optionally the concerts themselves
+    ///
+    /// Used before re-syncing modified concerts (whose sets and performances are recreated from scratch) and for
+    /// concerts that were removed from the master data. The personnel and sources of deleted concerts are deleted
+    /// first, if the master data has any.
+    ///
+    /// # Arguments
+    /// * `slugs` - The slugs of the concerts
+    /// * `delete_concerts` - Whether to delete the concerts too
+    ///
+    /// # Returns
+    /// * `Result<StepReport, StorageError>` - The number of deleted concerts and the rows the backend refused to
+    ///   delete, or a storage error
+    pub async fn clear_concerts(
+        &mut self,
+        slugs: &HashSet<String>,
+        delete_concerts: bool,
+    ) -> Result<StepReport, StorageError> {
+        let mut report = StepReport::new("removed concerts");
+        let concert_ids: HashSet<DbId> = slugs
+            .iter()
+            .filter_map(|slug| self.get_concert_id(slug.clone()))
+            .collect();
+        if concert_ids.is_empty() {
+            return Ok(report);
+        }
+
+        // Newest first, so the later parts of a medley go before the performance they link to
+        let performances: Vec<Performance> = self.storage.list(Entity::Performances).await?;
+        for performance in performances
+            .iter()
+            .rev()
+            .filter(|p| concert_ids.contains(&p.concert_id))
+        {
+            if !self.storage.delete(Entity::Performances, performance.id).await? {
+                error!("[FAIL] deleting performance {}", performance.id.0);
+                report.fail(format!("performance {}", performance.id.0));
+            }
+        }
+
+        // Listed even without lineups or guests, since the removed concerts may have had personnel
+        if delete_concerts {
+            let personnel: Vec<Personnel> = self.storage.list(Entity::Personnel).await?;
+            for row in personnel.iter().filter(|p| concert_ids.contains(&p.concert_id)) {
+                if !self.storage.delete(Entity::Personnel, row.id).await? {
+                    error!("[FAIL] deleting personnel {}", row.id.0);
+                    report.fail(format!("personnel {}", row.id.0));
+                }
+            }
+        }
+
+        // Listed even if the master data has no sources, since the removed concerts may have had them
+        if delete_concerts {
+            let sources: Vec<Source> = self.storage.list(Entity::Sources).await?;
+            for row in sources.iter().filter(|s| concert_ids.contains(&s.concert_id)) {
+                if !self.storage.delete(Entity::Sources, row.id).await? {
+                    error!("[FAIL] deleting source {}", row.id.0);
+                    report.fail(format!("source {}", row.id.0));
+                }
+            }
+        }
+
+        let sets: Vec<Set> = self.storage.list(Entity::Sets).await?;
+        for set in sets.iter().filter(|s| concert_ids.contains(&s.concert_id)) {
+            info!("[DEL!] set {}", set.unique_name);
+            if !self.storage.delete(Entity::Sets, set.id).await? {
+                error!("[FAIL] deleting set {}", set.unique_name);
+                report.fail(format!("set {}", set.unique_name));
+            }
+        }
+
+        for slug in slugs {
+            self.state.concert_hashes.remove(slug);
+            let Some(id) = self.get_concert_id(slug.clone()) else {
+                continue;
+            };
+            if delete_concerts {
+                info!("[DEL!] {}", slug);
+                if self.storage.delete(Entity::Concerts, id).await? {
+                    info!("[SUCC] {} deleted", slug);
+                    self.state.forget_concert(slug);
+                    report.deleted += 1;
+                } else {
+                    error!("[FAIL] deleting concert {}", slug);
+                    report.fail(slug.clone());
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Populates the database with the master data and song aliases loaded into `mpdb`, showing progress bars
+///
+/// Each step (countries, cities, venues, artists, song aliases, song titles, concerts, sets, performances,
+/// personnel and sources) is run in order, and the IDs of the rows it creates are stored in `mpdb.state` for the next
+/// steps. Errors in a step are logged, and the next step is run anyway.
+///
+/// If `mpdb.state` is empty, it is first filled from the database. Concerts whose setlist hash matches the state are
+/// skipped; concerts that exist with another hash are updated, and their sets and performances recreated.
+///
+/// # Returns
+/// * `Result<RunReport, Box<dyn std::error::Error>>` - The outcome of every step, or an error if the state cannot
+///   be loaded from the database
+pub async fn populate_db<S: Storage>(mpdb: &mut Mpdb<S>) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let mut report = RunReport::default();
+    run_steps(mpdb, &mut report).await?;
+    Ok(report)
+}
+
+/// Runs the populate steps, adding their outcomes to `report`
+async fn run_steps<S: Storage>(mpdb: &mut Mpdb<S>, report: &mut RunReport) -> Result<(), Box<dyn std::error::Error>> {
+    // debug!("{:?}", mpdb.aliases);
+
+    // Set up progress bars
+    let multipb = MultiProgress::new();
+    let style =
+        ProgressStyle::with_template("[{elapsed_precise}] [{percent:>3}%] |{bar:80.cyan/blue}| {pos:>7}/{len:7} {msg}")
+            .unwrap()
+            .progress_chars("=>-");
+
+    let pb_countries = multipb.add(ProgressBar::new(mpdb.countries_count()));
+    pb_countries.set_style(style.clone());
+    pb_countries.set_message("Countries");
+
+    let pb_cities = multipb.add(ProgressBar::new(mpdb.cities_count()));
+    pb_cities.set_style(style.clone());
+    pb_cities.set_message("Cities");
+
+    let pb_venues = multipb.add(ProgressBar::new(mpdb.venues_count()));
+    pb_venues.set_style(style.clone());
+    pb_venues.set_message("Venues");
+
+    let pb_artists = multipb.add(ProgressBar::new(mpdb.artists_count()));
+    pb_artists.set_style(style.clone());
+    pb_artists.set_message("Artists");
+
+    let pb_songs = multipb.add(ProgressBar::new(mpdb.songs_count()));
+    pb_songs.set_style(style.clone());
+    pb_songs.set_message("Songs");
+
+    let pb_concerts = multipb.add(ProgressBar::new(mpdb.concerts_count()));
+    pb_concerts.set_style(style.clone());
+    pb_concerts.set_message("Concerts");
+
+    let pb_performances = multipb.add(ProgressBar::new(mpdb.performances_count()));
+    pb_performances.set_style(style.clone());
+    pb_performances.set_message("Performances");
+
+    multipb.println("starting!").unwrap();
+
+    if mpdb.state.is_empty() {
+        info!("Sync state is empty - fetching IDs from the database");
+        mpdb.load_remote_state().await?;
+    }
+
+    info!("Populating countries");
+    let result = mpdb.populate_countries(pb_countries).await;
+    log_step("countries", &result);
+    report.add("countries", result);
+
+    info!("Populating cities");
+    let result = mpdb.populate_cities(pb_cities).await;
+    log_step("cities", &result);
+    report.add("cities", result);
+
+    info!("Populating venues");
+    let result = mpdb.populate_venues(pb_venues).await;
+    log_step("venues", &result);
+    report.add("venues", result);
+
+    info!("Populating artists");
+    let result = mpdb.populate_artists(pb_artists).await;
+    log_step("artists", &result);
+    report.add("artists", result);
+
+    info!("Populating songaliases");
+    let result = mpdb.populate_songaliases().await;
+    log_step("song aliases", &result);
+    report.add("song aliases", result);
+
+    info!("Populating songtitles");
+    let result = mpdb.populate_songtitles(pb_songs).await;
+    log_step("song titles", &result);
+    report.add("song titles", result);
+
+    info!("Populating song metadata");
+    let result = mpdb.populate_song_metadata().await;
+    log_step("song metadata", &result);
+    report.add("song metadata", result);
+
+    info!("Populating albums");
+    let result = mpdb.populate_albums().await;
+    log_step("albums", &result);
+    report.add("albums", result);
+
+    info!("Populating concerts");
+    let result = mpdb.populate_concerts(pb_concerts).await;
+    log_step("concerts", &result);
+    report.add("concerts", result);
+
+    info!("Populating sets and performances");
+    match mpdb.populate_performances(pb_performances).await {
+        Ok((sets, performances)) => {
+            log_step("sets", &Ok(sets.clone()));
+            log_step("performances", &Ok(performances.clone()));
+            report.steps.push(sets);
+            report.steps.push(performances);
+        }
+        Err(e) => {
+            error!("Error adding sets and/or performances: {e}");
+            report.add("sets", Err::<StepReport, _>(&e));
+            report.add("performances", Err::<StepReport, _>(&e));
+        }
+    }
+
+    info!("Populating personnel");
+    let result = mpdb.populate_personnel().await;
+    log_step("personnel", &result);
+    report.add("personnel", result);
+
+    info!("Populating sources");
+    let result = mpdb.populate_sources().await;
+    log_step("sources", &result);
+    report.add("sources", result);
+
+    Ok(())
+}
+
+/// Logs the outcome of a populate step
+fn log_step(step: &str, result: &Result<StepReport, StorageError>) {
+    match result {
+        Ok(report) if report.is_ok() => info!("Added all {step}"),
+        Ok(report) => warn!("Adding {step}: {} of them failed", report.failed),
+        Err(e) => error!("Error adding {step}: {e}"),
+    }
+}
+
+/// Syncs only the concerts that changed between two versions of the master data
+///
+/// Removed concerts are deleted with their sets and performances. Modified concerts are updated and get their sets
+/// and performances recreated, and added concerts are created, so `mpdb.master` is replaced by the changed
+/// setlists. Countries, cities, venues, artists and songs are only created for what the changed setlists need; they
+/// are never deleted, since other concerts may still refer to them.
+///
+/// # Arguments
+/// * `mpdb` - The database to sync, with the song aliases loaded
+/// * `changes` - The changes to sync
+///
+/// # Returns
+/// * `Result<RunReport, Box<dyn std::error::Error>>` - The outcome of the deletions and of every populate step, or
+///   an error if deleting fails
+pub async fn populate_changes<S: Storage>(
+    mpdb: &mut Mpdb<S>,
+    changes: &SetlistChanges,
+) -> Result<RunReport, Box<dyn std::error::Error>> {
+    info!(
+        "Syncing changes: {} added, {} modified, {} removed concert(s)",
+        changes.added.len(),
+        changes.modified.len(),
+        changes.removed.len()
+    );
+
+    if mpdb.state.is_empty() {
+        mpdb.load_remote_state().await?;
+    }
+    let mut report = RunReport::default();
+    let removed: HashSet<String> = changes.removed.iter().map(|s| s.identifier()).collect();
+    report.steps.push(mpdb.clear_concerts(&removed, true).await?);
+
+    mpdb.master = changes.changed();
+    if !mpdb.master.data.is_empty() {
+        run_steps(mpdb, &mut report).await?;
     }
+    Ok(report)
 }
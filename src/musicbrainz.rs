@@ -0,0 +1,399 @@
+//! Enrichment of the master data from MusicBrainz JSON data dumps.
+//!
+//! The MusicBrainz JSON dumps have one entity per line. Only the `artist` and `work` dumps are used, and they can be
+//! cut down to a subset (e.g. with `grep`) before importing; while reading, only the entities that can match the
+//! master data are kept.
+//!
+//! Artists in the master data (setlist artists and the original artists of covers) are matched by MBID, or else by
+//! name and aliases, and get their MBID, sort name and country filled in. Covers by a matched artist are matched to
+//! the works that artist wrote by title and aliases, and get the MBID of the work; the canonical work title is added
+//! to the song aliases. Names that match more than one artist or work are reported for manual resolution instead of
+//! guessing, and values that are already set are never changed.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::setlists::{Alias, Artist, Setlists, SongAliases, SongWithAliases};
+use crate::slug::Slug;
+
+/// An alias of a MusicBrainz artist or work
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MbAlias {
+    pub name: String,
+}
+
+/// An artist from the MusicBrainz artist dump
+///
+/// # Fields
+///
+/// * `id`: The MBID of the artist
+/// * `name`, `sort_name`: The name of the artist, and the name used for sorting (e.g. `Beatles, The`)
+/// * `country`: The ISO 3166-1 code of the artist's country, if known
+/// * `disambiguation`: A comment that tells artists with the same name apart
+/// * `aliases`: Other names of the artist
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MbArtist {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "sort-name")]
+    pub sort_name: Option<String>,
+    pub country: Option<String>,
+    pub disambiguation: Option<String>,
+    pub aliases: Vec<MbAlias>,
+}
+
+/// The artist a MusicBrainz relationship points to
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MbArtistRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// A relationship of a MusicBrainz work, e.g. to its composer
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MbRelation {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub artist: Option<MbArtistRef>,
+}
+
+/// A work (a song, as written) from the MusicBrainz work dump
+///
+/// # Fields
+///
+/// * `id`: The MBID of the work
+/// * `title`: The canonical title of the work
+/// * `disambiguation`: A comment that tells works with the same title apart
+/// * `aliases`: Other titles of the work
+/// * `relations`: Relationships to the artists who wrote the work, among others
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MbWork {
+    pub id: String,
+    pub title: String,
+    pub disambiguation: Option<String>,
+    pub aliases: Vec<MbAlias>,
+    pub relations: Vec<MbRelation>,
+}
+
+/// The relationship types that make an artist the writer of a work
+const WRITER_RELATIONS: &[&str] = &["composer", "lyricist", "writer"];
+
+impl MbArtist {
+    /// Returns the slugs of the name and the aliases of the artist
+    fn slugs(&self) -> HashSet<String> {
+        std::iter::once(&self.name)
+            .chain(self.aliases.iter().map(|a| &a.name))
+            .map(|name| name.slug())
+            .collect()
+    }
+}
+
+impl fmt::Display for MbArtist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}", self.name, self.id)?;
+        for extra in [&self.country, &self.disambiguation].into_iter().flatten() {
+            write!(f, ", {}", extra)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl MbWork {
+    /// Returns the slugs of the title and the aliases of the work
+    fn slugs(&self) -> HashSet<String> {
+        std::iter::once(&self.title)
+            .chain(self.aliases.iter().map(|a| &a.name))
+            .map(|name| name.slug())
+            .collect()
+    }
+
+    /// Returns true if the artist with the given MBID wrote the work
+    fn written_by(&self, artist_id: &str) -> bool {
+        self.relations.iter().any(|r| {
+            WRITER_RELATIONS.contains(&r.kind.as_str()) && r.artist.as_ref().is_some_and(|a| a.id == artist_id)
+        })
+    }
+}
+
+impl fmt::Display for MbWork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}", self.title, self.id)?;
+        if let Some(disambiguation) = &self.disambiguation {
+            write!(f, ", {}", disambiguation)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Reads the entities of a MusicBrainz JSON dump file, keeping only those `keep` returns true for
+///
+/// # Arguments
+/// * `path` - The dump file, with one JSON entity per line
+/// * `keep` - Decides which entities to keep
+///
+/// # Returns
+/// * `Result<Vec<T>, Box<dyn std::error::Error>>` - The kept entities, or an I/O error or the first parse error with
+///   its line number
+pub fn read_dump<T: DeserializeOwned>(
+    path: &Path,
+    keep: impl Fn(&T) -> bool,
+) -> Result<Vec<T>, Box<dyn std::error::Error>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut entities = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entity: T = serde_json::from_str(&line).map_err(|e| format!("{} line {}: {}", path.display(), i + 1, e))?;
+        if keep(&entity) {
+            entities.push(entity);
+        }
+    }
+    Ok(entities)
+}
+
+/// Returns every artist in the master data: setlist artists and the original artists of covers
+fn master_artists(master: &Setlists) -> impl Iterator<Item = &Artist> {
+    master.data.iter().flat_map(|s| {
+        std::iter::once(&s.artist).chain(s.played_songs().filter_map(|song| song.original_artist.as_ref()))
+    })
+}
+
+/// Reads the artists of a MusicBrainz artist dump that can match an artist in the master data
+pub fn read_artists(path: &Path, master: &Setlists) -> Result<Vec<MbArtist>, Box<dyn std::error::Error>> {
+    let names: HashSet<String> = master_artists(master).map(|a| a.name.slug()).collect();
+    let ids: HashSet<&String> = master_artists(master).filter_map(|a| a.mbid.as_ref()).collect();
+    read_dump(path, |artist: &MbArtist| {
+        ids.contains(&artist.id) || !artist.slugs().is_disjoint(&names)
+    })
+}
+
+/// Reads the works of a MusicBrainz work dump that can match a cover in the master data
+pub fn read_works(path: &Path, master: &Setlists) -> Result<Vec<MbWork>, Box<dyn std::error::Error>> {
+    let titles: HashSet<String> = master
+        .data
+        .iter()
+        .flat_map(|s| s.played_songs())
+        .filter(|song| song.original_artist.is_some())
+        .map(|song| song.name.slug())
+        .collect();
+    read_dump(path, |work: &MbWork| !work.slugs().is_disjoint(&titles))
+}
+
+/// A name in the master data that matches more than one MusicBrainz entity
+///
+/// # Fields
+///
+/// * `kind`: What was matched: `artist` or `work`
+/// * `name`: The name in the master data, e.g. `Watersign by Hawkwind` for a work
+/// * `candidates`: The matching entities, with their MBIDs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ambiguity {
+    pub kind: &'static str,
+    pub name: String,
+    pub candidates: Vec<String>,
+}
+
+impl fmt::Display for Ambiguity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ambiguous {} '{}':", self.kind, self.name)?;
+        for candidate in self.candidates.iter() {
+            write!(f, "\n  - {}", candidate)?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of enriching the master data
+///
+/// # Fields
+///
+/// * `artists`: The number of artists (by name) that were matched
+/// * `covers`: The number of covers (by title and artist) that were matched to a work
+/// * `ambiguous`: The names that matched more than one entity and were left alone
+/// * `unmatched`: The names that matched nothing, e.g. `artist Hawkwind`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Enrichment {
+    pub artists: usize,
+    pub covers: usize,
+    pub ambiguous: Vec<Ambiguity>,
+    pub unmatched: Vec<String>,
+}
+
+/// Formats the counts, followed by the ambiguous and the unmatched names
+impl fmt::Display for Enrichment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Matched {} artists and {} covers", self.artists, self.covers)?;
+        for ambiguity in self.ambiguous.iter() {
+            writeln!(f, "{}", ambiguity)?;
+        }
+        for name in self.unmatched.iter() {
+            writeln!(f, "unmatched {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Matches a name against candidates, recording ambiguous and unmatched names
+fn single<'a, T: fmt::Display>(
+    enrichment: &mut Enrichment,
+    kind: &'static str,
+    name: String,
+    candidates: Vec<&'a T>,
+) -> Option<&'a T> {
+    match candidates.len() {
+        0 => {
+            enrichment.unmatched.push(format!("{} {}", kind, name));
+            None
+        }
+        1 => Some(candidates[0]),
+        _ => {
+            let candidates = candidates.iter().map(|c| c.to_string()).collect();
+            enrichment.ambiguous.push(Ambiguity { kind, name, candidates });
+            None
+        }
+    }
+}
+
+/// Enriches the artists and covers of the master data, and the song aliases, with MusicBrainz data
+///
+/// # Arguments
+/// * `master` - The master data to enrich
+/// * `aliases` - The song aliases, which get the canonical titles of matched works
+/// * `artists` - Artists from the MusicBrainz artist dump
+/// * `works` - Works from the MusicBrainz work dump
+///
+/// # Returns
+/// * `Enrichment` - What was matched, and the names that were ambiguous or matched nothing
+pub fn enrich(master: &mut Setlists, aliases: &mut SongAliases, artists: &[MbArtist], works: &[MbWork]) -> Enrichment {
+    let mut enrichment = Enrichment::default();
+
+    // Match every artist name once, by an MBID given in the master data, or else by name and aliases
+    let by_id: HashMap<&str, &MbArtist> = artists.iter().map(|a| (a.id.as_str(), a)).collect();
+    let mut names: Vec<(String, Option<String>)> = vec![];
+    for artist in master_artists(master) {
+        match names.iter_mut().find(|(name, _)| *name == artist.name) {
+            Some((_, mbid)) => {
+                if mbid.is_none() {
+                    mbid.clone_from(&artist.mbid);
+                }
+            }
+            None => names.push((artist.name.clone(), artist.mbid.clone())),
+        }
+    }
+    let mut matched: HashMap<String, &MbArtist> = HashMap::new();
+    for (name, mbid) in names {
+        let candidates = match &mbid {
+            Some(mbid) => by_id.get(mbid.as_str()).copied().into_iter().collect(),
+            None => artists.iter().filter(|a| a.slugs().contains(&name.slug())).collect(),
+        };
+        if let Some(artist) = single(&mut enrichment, "artist", name.clone(), candidates) {
+            matched.insert(name, artist);
+        }
+    }
+    enrichment.artists = matched.len();
+
+    let fill = |artist: &mut Artist| {
+        if let Some(mb) = matched.get(&artist.name) {
+            artist.mbid.get_or_insert_with(|| mb.id.clone());
+            if artist.sort_name.is_none() {
+                artist.sort_name.clone_from(&mb.sort_name);
+            }
+            if artist.country.is_none() {
+                artist.country.clone_from(&mb.country);
+            }
+        }
+    };
+
+    // Match every cover once by its title and artist, to a work that artist wrote
+    let mut covers: HashMap<(String, String), Option<&MbWork>> = HashMap::new();
+    for setlist in master.data.iter_mut() {
+        fill(&mut setlist.artist);
+        for song in setlist
+            .sets
+            .set
+            .iter_mut()
+            .flat_map(|set| set.songs.iter_mut().flatten())
+            .flat_map(|song| song.components_mut())
+        {
+            let Some(original_artist) = song.original_artist.as_mut() else {
+                continue;
+            };
+            fill(original_artist);
+            let Some(mb_artist) = matched.get(&original_artist.name) else {
+                continue;
+            };
+            if song.work_mbid.is_some() {
+                continue;
+            }
+            let key = (song.name.slug(), original_artist.name.clone());
+            let work = *covers.entry(key).or_insert_with(|| {
+                let candidates = works
+                    .iter()
+                    .filter(|w| w.written_by(&mb_artist.id) && w.slugs().contains(&song.name.slug()))
+                    .collect();
+                let name = format!("{} by {}", song.name, original_artist.name);
+                let work = single(&mut enrichment, "work", name, candidates);
+                if let Some(work) = work {
+                    add_work_title(aliases, &song.name, &original_artist.name, work);
+                }
+                work
+            });
+            if let Some(work) = work {
+                song.work_mbid = Some(work.id.clone());
+            }
+        }
+    }
+    enrichment.covers = covers.values().filter(|w| w.is_some()).count();
+
+    enrichment
+}
+
+/// Adds the title of a work to the song aliases for a cover, and fills in the artist and the work MBID of the song if
+/// they are missing
+///
+/// If no entry matches the title the cover was played as or the work title, a new entry is added with the work title
+/// as its canonical title. An existing entry keeps its canonical title. Either way, the played-as title and the work
+/// title are added as aliases if the entry does not have them yet.
+fn add_work_title(aliases: &mut SongAliases, played_as: &str, artist: &str, work: &MbWork) {
+    let slugs = [played_as.to_string().slug(), work.title.slug()];
+    let song = aliases.songs.iter_mut().find(|s| {
+        std::iter::once(&s.name)
+            .chain(s.aliases.iter().map(|a| &a.name))
+            .any(|name| slugs.contains(&name.slug()))
+    });
+    let song = match song {
+        Some(song) => song,
+        None => {
+            aliases.songs.push(SongWithAliases {
+                name: work.title.clone(),
+                artist: None,
+                work_mbid: None,
+                aliases: vec![],
+            });
+            aliases.songs.last_mut().unwrap()
+        }
+    };
+    song.artist.get_or_insert_with(|| artist.to_string());
+    song.work_mbid.get_or_insert_with(|| work.id.clone());
+    for title in [played_as, work.title.as_str()] {
+        let known = std::iter::once(&song.name)
+            .chain(song.aliases.iter().map(|a| &a.name))
+            .any(|name| name.slug() == title.to_string().slug());
+        if !known {
+            song.aliases.push(Alias {
+                name: title.to_string(),
+            });
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Outcome counts of a populate run.
+//!
+//! Every `populate_*` step returns a `StepReport` with the number of rows it created, updated, skipped, deleted and
+//! failed to sync, and what failed. `populate_db` collects them in a `RunReport`, which the command line prints as a
+//! table at the end of the run and can write as JSON.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The outcome of one populate step
+///
+/// # Fields
+///
+/// * `step`: The name of the step, e.g. `countries`
+/// * `created`, `updated`, `skipped`, `deleted`: The number of rows created, updated, skipped because they were
+///   already in sync, and deleted
+/// * `failed`: The number of rows that could not be synced
+/// * `failures`: A description of each row that could not be synced
+/// * `error`: The error that aborted the step, if any; the counts are then unknown
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StepReport {
+    pub step: String,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub deleted: usize,
+    pub failed: usize,
+    pub failures: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl StepReport {
+    /// Creates an empty report for a step
+    pub fn new(step: &str) -> Self {
+        StepReport {
+            step: step.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Counts a row that could not be synced
+    pub fn fail(&mut self, failure: String) {
+        self.failed += 1;
+        self.failures.push(failure);
+    }
+
+    /// Adds the counts and failures of another report to this one
+    pub fn merge(&mut self, other: StepReport) {
+        self.created += other.created;
+        self.updated += other.updated;
+        self.skipped += other.skipped;
+        self.deleted += other.deleted;
+        self.failed += other.failed;
+        self.failures.extend(other.failures);
+    }
+
+    /// Returns true if nothing failed in the step
+    pub fn is_ok(&self) -> bool {
+        self.failed == 0 && self.error.is_none()
+    }
+}
+
+/// The outcome of a populate run
+///
+/// # Fields
+///
+/// * `steps`: The reports of the steps, in the order they were run
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RunReport {
+    pub steps: Vec<StepReport>,
+}
+
+impl RunReport {
+    /// Adds the outcome of a step; a step that failed with an error is recorded with the error and no counts
+    pub fn add<E: fmt::Display>(&mut self, step: &str, result: Result<StepReport, E>) {
+        match result {
+            Ok(report) => self.steps.push(report),
+            Err(e) => self.steps.push(StepReport {
+                error: Some(e.to_string()),
+                ..StepReport::new(step)
+            }),
+        }
+    }
+
+    /// Returns the report of a step, if it was run
+    pub fn step(&self, step: &str) -> Option<&StepReport> {
+        self.steps.iter().find(|s| s.step == step)
+    }
+
+    /// Returns the sums of all steps
+    pub fn totals(&self) -> StepReport {
+        let mut totals = StepReport::new("total");
+        for step in self.steps.iter() {
+            totals.merge(step.clone());
+        }
+        totals
+    }
+
+    /// Returns true if nothing failed in any step
+    pub fn is_ok(&self) -> bool {
+        self.steps.iter().all(|s| s.is_ok())
+    }
+
+    /// Returns the report as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Formats the report as a table with a row per step and a total row, followed by the failures and errors
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.steps.iter().map(|s| s.step.len()).max().unwrap_or_default().max(5);
+        writeln!(
+            f,
+            "{:<width$} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            "step", "created", "updated", "skipped", "deleted", "failed"
+        )?;
+        let totals = self.totals();
+        for step in self.steps.iter().chain(std::iter::once(&totals)) {
+            if step.error.is_some() {
+                writeln!(f, "{:<width$} {:>44}", step.step, "error")?;
+                continue;
+            }
+            writeln!(
+                f,
+                "{:<width$} {:>8} {:>8} {:>8} {:>8} {:>8}",
+                step.step, step.created, step.updated, step.skipped, step.deleted, step.failed
+            )?;
+        }
+        for step in self.steps.iter() {
+            if let Some(error) = &step.error {
+                writeln!(f, "{}: error: {}", step.step, error)?;
+            }
+            for failure in step.failures.iter() {
+                writeln!(f, "{}: failed: {}", step.step, failure)?;
+            }
+        }
+        Ok(())
+    }
+}
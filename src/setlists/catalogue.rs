@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use super::FileFormat;
+use crate::slug::Slug;
+
+/// The song catalogue: metadata of the songs, kept in its own file next to the song aliases
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct SongCatalogue {
+    #[serde(rename = "song", default)]
+    pub songs: Vec<SongInfo>,
+}
+
+/// The metadata of a song
+///
+/// # Fields
+///
+/// * `name`: The title of the song; its default title in the song aliases, or the title it is played as
+/// * `album`: The album the song was first released on
+/// * `composers`: The people credited with writing the song
+/// * `release_year`: The year the song was first released
+/// * `original_artist`: The artist who wrote and first recorded a cover
+/// * `instrumental`: Whether the song has no vocals
+/// * `duration`: The typical duration of a performance, as `M:SS` or `H:MM:SS`
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct SongInfo {
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(rename = "composer", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub composers: Vec<Composer>,
+    #[serde(rename = "releaseYear")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_year: Option<i32>,
+    #[serde(rename = "originalArtist")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instrumental: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Composer {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+impl SongCatalogue {
+    /// Creates a new empty SongCatalogue struct
+    pub fn new() -> Self {
+        SongCatalogue { songs: Vec::new() }
+    }
+
+    /// Parses a SongCatalogue struct from an XML string
+    ///
+    /// # Arguments
+    /// * `xml` - A string containing XML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_xml_rust::Error>` - The parsed SongCatalogue on success, or a deserialization error
+    pub fn from_xml(xml: &str) -> Result<Self, serde_xml_rust::Error> {
+        serde_xml_rust::from_str(xml)
+    }
+
+    /// Parses a SongCatalogue struct from a YAML string
+    ///
+    /// # Arguments
+    /// * `yml` - A string containing YAML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_yml::Error>` - The parsed SongCatalogue on success, or a deserialization error
+    pub fn from_yml(yml: &str) -> Result<Self, serde_yml::Error> {
+        serde_yml::from_str(yml)
+    }
+
+    /// Parses a SongCatalogue struct from a string in the given format
+    ///
+    /// # Arguments
+    /// * `content` - A string containing XML or YAML data
+    /// * `format` - The format of the data
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed SongCatalogue on success, or a parse error
+    pub fn parse(content: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match format {
+            FileFormat::Xml => Self::from_xml(content).map_err(|e| format!("XML parse error: {}", e))?,
+            FileFormat::Yml => Self::from_yml(content).map_err(|e| format!("YAML parse error: {}", e))?,
+        })
+    }
+
+    /// Converts the SongCatalogue struct to a YAML string
+    ///
+    /// # Returns
+    /// * `Result<String, serde_yml::Error>` - The YAML string on success, or a serialization error
+    pub fn to_yml(&self) -> Result<String, serde_yml::Error> {
+        serde_yml::to_string(self)
+    }
+
+    /// Returns the metadata of a song, comparing titles by slug
+    ///
+    /// # Arguments
+    /// * `name` - The title of the song
+    ///
+    /// # Returns
+    /// * `Option<&SongInfo>` - The metadata of the song, if the catalogue has it
+    pub fn get(&self, name: &str) -> Option<&SongInfo> {
+        let slug = name.to_string().slug();
+        self.songs.iter().find(|s| s.name.slug() == slug)
+    }
+}
+
+impl SongInfo {
+    /// Returns the names of the composers, separated by commas, or `None` if there are none
+    pub fn composer_names(&self) -> Option<String> {
+        let names: Vec<&str> = self.composers.iter().map(|c| c.name.as_str()).collect();
+        (!names.is_empty()).then(|| names.join(", "))
+    }
+
+    /// Returns the typical duration in seconds, or `None` if it is not given or cannot be parsed
+    pub fn duration_seconds(&self) -> Option<u32> {
+        self.duration.as_deref().and_then(parse_duration)
+    }
+}
+
+/// Parses a duration given as `M:SS` or `H:MM:SS` into seconds
+///
+/// # Arguments
+/// * `duration` - The duration, e.g. `7:45`
+///
+/// # Returns
+/// * `Option<u32>` - The duration in seconds, or `None` if it is malformed or seconds or minutes exceed 59
+pub fn parse_duration(duration: &str) -> Option<u32> {
+    let parts = duration
+        .trim()
+        .split(':')
+        .map(|p| p.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    match parts[..] {
+        [m, s] if s < 60 => Some(m * 60 + s),
+        [h, m, s] if m < 60 && s < 60 => Some(h * 3600 + m * 60 + s),
+        _ => None,
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use super::{Setlist, Setlists};
+
+/// The setlists that were added, modified or removed between two versions of the master data.
+///
+/// Setlists are matched by their identifier (artist, date and disambiguation), so a setlist whose date or
+/// disambiguation changed shows up as one removed and one added setlist.
+///
+/// # Fields
+///
+/// * `added`: Setlists that are only in the new version
+/// * `modified`: Setlists that are in both versions, with different content (as in the new version)
+/// * `removed`: Setlists that are only in the old version
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SetlistChanges {
+    pub added: Vec<Setlist>,
+    pub modified: Vec<Setlist>,
+    pub removed: Vec<Setlist>,
+}
+
+impl SetlistChanges {
+    /// Compares two versions of the master data
+    ///
+    /// # Arguments
+    /// * `old` - The old version
+    /// * `new` - The new version
+    ///
+    /// # Returns
+    /// * `SetlistChanges` - The changes, each list in the order of the version it comes from
+    pub fn between(old: &Setlists, new: &Setlists) -> Self {
+        let old_by_id: HashMap<String, &Setlist> = old.data.iter().rev().map(|s| (s.identifier(), s)).collect();
+        let new_by_id: HashMap<String, &Setlist> = new.data.iter().rev().map(|s| (s.identifier(), s)).collect();
+
+        let mut changes = SetlistChanges::default();
+        for setlist in new.data.iter() {
+            match old_by_id.get(&setlist.identifier()) {
+                None => changes.added.push(setlist.clone()),
+                Some(old) if *old != setlist => changes.modified.push(setlist.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = old
+            .data
+            .iter()
+            .filter(|s| !new_by_id.contains_key(&s.identifier()))
+            .cloned()
+            .collect();
+
+        changes
+    }
+
+    /// Returns true if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns the added and modified setlists, i.e. the ones that have to be synced
+    pub fn changed(&self) -> Setlists {
+        Setlists {
+            data: self.added.iter().chain(self.modified.iter()).cloned().collect(),
+        }
+    }
+}
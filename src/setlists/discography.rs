@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use super::FileFormat;
+use crate::slug::Slug;
+
+/// The discography: the albums of the home artist with their tracklists
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Discography {
+    #[serde(rename = "album", default)]
+    pub albums: Vec<Album>,
+}
+
+/// An album and its tracklist
+///
+/// # Fields
+///
+/// * `title`: The title of the album
+/// * `year`: The year the album was released
+/// * `mbid`: Optional MusicBrainz ID of the album's release group
+/// * `tracks`: The songs on the album in tracklist order, by their default title in the song aliases
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Album {
+    #[serde(rename = "title")]
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mbid: Option<String>,
+    #[serde(rename = "track", default)]
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Track {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+impl Discography {
+    /// Creates a new empty Discography struct
+    pub fn new() -> Self {
+        Discography { albums: Vec::new() }
+    }
+
+    /// Parses a Discography struct from an XML string
+    ///
+    /// # Arguments
+    /// * `xml` - A string containing XML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_xml_rust::Error>` - The parsed Discography on success, or a deserialization error
+    pub fn from_xml(xml: &str) -> Result<Self, serde_xml_rust::Error> {
+        serde_xml_rust::from_str(xml)
+    }
+
+    /// Parses a Discography struct from a YAML string
+    ///
+    /// # Arguments
+    /// * `yml` - A string containing YAML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_yml::Error>` - The parsed Discography on success, or a deserialization error
+    pub fn from_yml(yml: &str) -> Result<Self, serde_yml::Error> {
+        serde_yml::from_str(yml)
+    }
+
+    /// Parses a Discography struct from a string in the given format
+    ///
+    /// # Arguments
+    /// * `content` - A string containing XML or YAML data
+    /// * `format` - The format of the data
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed Discography on success, or a parse error
+    pub fn parse(content: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match format {
+            FileFormat::Xml => Self::from_xml(content).map_err(|e| format!("XML parse error: {}", e))?,
+            FileFormat::Yml => Self::from_yml(content).map_err(|e| format!("YAML parse error: {}", e))?,
+        })
+    }
+
+    /// Converts the Discography struct to a YAML string
+    ///
+    /// # Returns
+    /// * `Result<String, serde_yml::Error>` - The YAML string on success, or a serialization error
+    pub fn to_yml(&self) -> Result<String, serde_yml::Error> {
+        serde_yml::to_string(self)
+    }
+}
+
+impl Album {
+    /// Returns the slug of the album, e.g. `the-tower-2017`
+    pub fn slug(&self) -> String {
+        match self.year {
+            Some(year) => format!("{}-{}", self.title, year).slug(),
+            None => self.title.slug(),
+        }
+    }
+}
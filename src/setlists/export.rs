@@ -0,0 +1,157 @@
+use super::Setlist;
+
+/// Text formats that setlists can be exported to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    Markdown,
+    Text,
+}
+
+/// Song notes collected while rendering, printed as footnotes after the setlist
+struct Footnotes {
+    next: usize,
+    notes: Vec<(usize, String)>,
+}
+
+impl Footnotes {
+    fn add(&mut self, note: &str) -> usize {
+        let n = self.next;
+        self.next += 1;
+        self.notes.push((n, note.to_string()));
+        n
+    }
+}
+
+fn render(setlist: &Setlist, format: TextFormat, footnotes: &mut Footnotes) -> String {
+    let mut out = String::new();
+    let mut title = format!("{}, {}", setlist.artist.name, setlist.event_date);
+    if let Some(disambiguation) = &setlist.disambiguation {
+        title.push_str(&format!(" ({})", disambiguation));
+    }
+    let location = format!("{}, {}", setlist.venue.city.name, setlist.venue.city.country.name);
+
+    match format {
+        TextFormat::Markdown => {
+            out.push_str(&format!("## {}\n\n", title));
+            out.push_str(&format!("**{}**, {}  \n", setlist.venue.name, location));
+            if let Some(tour) = &setlist.tour {
+                out.push_str(&format!("*{}*  \n", tour.name));
+            }
+        }
+        TextFormat::Text => {
+            out.push_str(&format!("{}\n", title));
+            out.push_str(&format!("{}, {}\n", setlist.venue.name, location));
+            if let Some(tour) = &setlist.tour {
+                out.push_str(&format!("Tour: {}\n", tour.name));
+            }
+        }
+    }
+    out.push('\n');
+
+    let mut set_number = 0;
+    for set in setlist.sets.set.iter() {
+        let heading = match (&set.encore, set.title()) {
+            (Some(_), Some(title)) => title,
+            (None, Some(name)) => name,
+            (_, None) => {
+                set_number += 1;
+                format!("Set {}", set_number)
+            }
+        };
+
+        let mut line = String::new();
+        let songs = set.songs.as_deref().unwrap_or_default();
+        for (i, song) in songs.iter().enumerate() {
+            line.push_str(&song.name);
+            if let Some(parts) = song.medley_names() {
+                line.push_str(&format!(" ({})", parts));
+            }
+            if let Some(artist) = &song.original_artist {
+                line.push_str(&format!(" ({})", artist.name));
+            }
+            if let Some(notes) = &song.notes {
+                let n = footnotes.add(notes);
+                match format {
+                    TextFormat::Markdown => line.push_str(&format!("[^{}]", n)),
+                    TextFormat::Text => line.push_str(&format!(" [{}]", n)),
+                }
+            }
+            if i + 1 < songs.len() {
+                if song.segue.unwrap_or(false) {
+                    line.push_str(" > ");
+                } else {
+                    line.push_str(", ");
+                }
+            }
+        }
+
+        match format {
+            TextFormat::Markdown => out.push_str(&format!("**{}:** {}  \n", heading, line)),
+            TextFormat::Text => out.push_str(&format!("{}: {}\n", heading, line)),
+        }
+    }
+
+    if let Some(notes) = &setlist.notes {
+        out.push('\n');
+        match format {
+            TextFormat::Markdown => out.push_str(&format!("*{}*\n", notes)),
+            TextFormat::Text => out.push_str(&format!("Notes: {}\n", notes)),
+        }
+    }
+
+    out
+}
+
+/// Renders the footnotes collected so far, and clears them
+fn render_footnotes(format: TextFormat, footnotes: &mut Footnotes) -> String {
+    let mut out = String::new();
+    if footnotes.notes.is_empty() {
+        return out;
+    }
+    out.push('\n');
+    for (n, note) in footnotes.notes.drain(..) {
+        match format {
+            TextFormat::Markdown => out.push_str(&format!("[^{}]: {}\n", n, note)),
+            TextFormat::Text => out.push_str(&format!("[{}] {}\n", n, note)),
+        }
+    }
+    out
+}
+
+/// Exports a single setlist as Markdown or plain text
+///
+/// Sets and encores are written one per line, with segues shown as `>` between songs, covers followed by the
+/// original artist in parentheses, and song notes as numbered footnotes after the setlist.
+///
+/// # Arguments
+/// * `setlist` - The setlist to export
+/// * `format` - The output format
+///
+/// # Returns
+/// * `String` - The exported setlist
+pub fn export_setlist(setlist: &Setlist, format: TextFormat) -> String {
+    export_setlists(&[setlist], format)
+}
+
+/// Exports several setlists as Markdown or plain text, separated by blank lines
+///
+/// Footnote numbers are unique across the whole document, so the output can be pasted as a single post.
+///
+/// # Arguments
+/// * `setlists` - The setlists to export
+/// * `format` - The output format
+///
+/// # Returns
+/// * `String` - The exported setlists
+pub fn export_setlists(setlists: &[&Setlist], format: TextFormat) -> String {
+    let mut footnotes = Footnotes { next: 1, notes: vec![] };
+    setlists
+        .iter()
+        .map(|setlist| {
+            let mut out = render(setlist, format, &mut footnotes);
+            out.push_str(&render_footnotes(format, &mut footnotes));
+            out
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use super::{parse_date, FileFormat, Setlist};
+use crate::slug::Slug;
+
+/// The lineups: who played in a band during which period, kept in its own file next to the master data
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Lineups {
+    #[serde(rename = "lineup", default)]
+    pub lineups: Vec<Lineup>,
+}
+
+/// The members of a band during a period
+///
+/// # Fields
+///
+/// * `artist`: The name of the band
+/// * `from`: The first day of the period, as `dd-mm-yyyy` or `yyyy-mm-dd`
+/// * `to`: Optional last day of the period; the lineup is current if not given
+/// * `members`: The members of the band and the instruments they played
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Lineup {
+    #[serde(rename = "artist")]
+    pub artist: String,
+    #[serde(rename = "from")]
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(rename = "member", default)]
+    pub members: Vec<Member>,
+}
+
+/// A musician playing in a band
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Member {
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instrument: Option<String>,
+}
+
+/// A musician who played at a concert
+///
+/// # Fields
+///
+/// * `name`: The name of the musician
+/// * `instrument`: Optional instrument the musician played
+/// * `guest`: Whether the musician played as a guest rather than as a band member
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Player {
+    pub name: String,
+    pub instrument: Option<String>,
+    pub guest: bool,
+}
+
+impl Lineups {
+    /// Creates a new empty Lineups struct
+    pub fn new() -> Self {
+        Lineups { lineups: Vec::new() }
+    }
+
+    /// Parses a Lineups struct from an XML string
+    ///
+    /// # Arguments
+    /// * `xml` - A string containing XML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_xml_rust::Error>` - The parsed Lineups on success, or a deserialization error
+    pub fn from_xml(xml: &str) -> Result<Self, serde_xml_rust::Error> {
+        serde_xml_rust::from_str(xml)
+    }
+
+    /// Parses a Lineups struct from a YAML string
+    ///
+    /// # Arguments
+    /// * `yml` - A string containing YAML data
+    ///
+    /// # Returns
+    /// * `Result<Self, serde_yml::Error>` - The parsed Lineups on success, or a deserialization error
+    pub fn from_yml(yml: &str) -> Result<Self, serde_yml::Error> {
+        serde_yml::from_str(yml)
+    }
+
+    /// Parses a Lineups struct from a string in the given format
+    ///
+    /// # Arguments
+    /// * `content` - A string containing XML or YAML data
+    /// * `format` - The format of the data
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed Lineups on success, or a parse error
+    pub fn parse(content: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match format {
+            FileFormat::Xml => Self::from_xml(content).map_err(|e| format!("XML parse error: {}", e))?,
+            FileFormat::Yml => Self::from_yml(content).map_err(|e| format!("YAML parse error: {}", e))?,
+        })
+    }
+
+    /// Converts the Lineups struct to a YAML string
+    ///
+    /// # Returns
+    /// * `Result<String, serde_yml::Error>` - The YAML string on success, or a serialization error
+    pub fn to_yml(&self) -> Result<String, serde_yml::Error> {
+        serde_yml::to_string(self)
+    }
+
+    /// Returns the lineup of the band on the date of a concert
+    ///
+    /// # Arguments
+    /// * `setlist` - The concert
+    ///
+    /// # Returns
+    /// * `Option<&Lineup>` - The first lineup of the concert's artist covering its date, if any
+    pub fn lineup_for(&self, setlist: &Setlist) -> Option<&Lineup> {
+        let date = setlist.date()?;
+        let artist = setlist.artist.name.slug();
+        self.lineups
+            .iter()
+            .find(|l| l.artist.slug() == artist && l.covers(date))
+    }
+
+    /// Returns the musicians who played at a concert
+    ///
+    /// The band members are the concert's own members if it has any, and otherwise the lineup for its date. Guests are
+    /// those of the concert followed by those of its songs and medley parts; a musician is listed once per instrument.
+    ///
+    /// # Arguments
+    /// * `setlist` - The concert
+    ///
+    /// # Returns
+    /// * `Vec<Player>` - The band members followed by the guests
+    pub fn personnel(&self, setlist: &Setlist) -> Vec<Player> {
+        let members = if setlist.members.is_empty() {
+            self.lineup_for(setlist).map(|l| &l.members[..]).unwrap_or_default()
+        } else {
+            &setlist.members[..]
+        };
+        let members = members.iter().map(|m| Player {
+            name: m.name.clone(),
+            instrument: m.instrument.clone(),
+            guest: false,
+        });
+        let guests = setlist
+            .guests
+            .iter()
+            .chain(
+                setlist
+                    .songs()
+                    .flat_map(|song| std::iter::once(song).chain(song.medley.iter()))
+                    .flat_map(|song| song.guests.iter()),
+            )
+            .map(|g| Player {
+                name: g.name.clone(),
+                instrument: g.instrument.clone(),
+                guest: true,
+            });
+
+        let mut players: Vec<Player> = vec![];
+        for player in members.chain(guests) {
+            let duplicate = players
+                .iter()
+                .any(|p| p.name.slug() == player.name.slug() && p.instrument == player.instrument);
+            if !duplicate {
+                players.push(player);
+            }
+        }
+        players
+    }
+}
+
+impl Lineup {
+    /// Returns the first day of the lineup, or `None` if it cannot be parsed
+    pub fn from_date(&self) -> Option<chrono::NaiveDate> {
+        parse_date(&self.from)
+    }
+
+    /// Returns the last day of the lineup, or `None` if it is current or cannot be parsed
+    pub fn to_date(&self) -> Option<chrono::NaiveDate> {
+        self.to.as_deref().and_then(parse_date)
+    }
+
+    /// Returns true if the lineup played on a date
+    pub fn covers(&self, date: chrono::NaiveDate) -> bool {
+        self.from_date().is_some_and(|from| from <= date) && self.to_date().is_none_or(|to| date <= to)
+    }
+}
@@ -1,19 +1,87 @@
 use serde::{Deserialize, Serialize};
-#[derive(Clone, Debug, Deserialize, Serialize)]
+
+use crate::slug::Slug;
+
+mod catalogue;
+mod diff;
+mod discography;
+mod export;
+mod lineups;
+mod query;
+mod render;
+mod sheet;
+mod sources;
+
+pub use catalogue::{parse_duration, Composer, SongCatalogue, SongInfo};
+pub use diff::SetlistChanges;
+pub use discography::{Album, Discography, Track};
+pub use export::{export_setlist, export_setlists, TextFormat};
+pub use lineups::{Lineup, Lineups, Member, Player};
+pub use query::SetlistQuery;
+pub use render::{render_setlist, render_summary};
+pub use sheet::{export_csv, performance_rows, PerformanceRow};
+pub use sources::{Source, Sources, SOURCE_KINDS};
+
+/// File formats the master data and the song aliases can be stored in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    Xml,
+    Yml,
+}
+
+/// Parses a date as written in the data files
+///
+/// The master data uses `dd-mm-yyyy`, but ISO dates (`yyyy-mm-dd`) are accepted as well.
+///
+/// # Arguments
+/// * `date` - The date to parse
+///
+/// # Returns
+/// * `Option<chrono::NaiveDate>` - The date, or `None` if it cannot be parsed
+pub fn parse_date(date: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(date, "%d-%m-%Y")
+        .or_else(|_| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()
+}
+
+impl FileFormat {
+    /// Returns the file extension used for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Xml => "xml",
+            FileFormat::Yml => "yml",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct SongAliases {
     #[serde(rename = "song")]
     pub songs: Vec<SongWithAliases>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// A song with its default title and the other titles it is known by
+///
+/// # Fields
+///
+/// * `name`: The default title of the song
+/// * `artist`: Optional name of the artist the song belongs to; the home artist if not given
+/// * `work_mbid`: Optional MusicBrainz ID of the song's work
+/// * `aliases`: The other titles of the song
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct SongWithAliases {
     #[serde(rename = "name")]
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(rename = "workMbid")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_mbid: Option<String>,
     #[serde(rename = "alias")]
     pub aliases: Vec<Alias>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Alias {
     #[serde(rename = "name")]
     pub name: String,
@@ -25,7 +93,6 @@ impl Default for SongAliases {
     }
 }
 
-#[allow(dead_code)]
 impl SongAliases {
     /// Creates a new empty SongAliases struct
     ///
@@ -57,6 +124,21 @@ impl SongAliases {
         serde_yml::from_str(yml)
     }
 
+    /// Parses a SongAliases struct from a string in the given format
+    ///
+    /// # Arguments
+    /// * `content` - A string containing XML or YAML data
+    /// * `format` - The format of the data
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed SongAliases on success, or a parse error
+    pub fn parse(content: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match format {
+            FileFormat::Xml => Self::from_xml(content).map_err(|e| format!("XML parse error: {}", e))?,
+            FileFormat::Yml => Self::from_yml(content).map_err(|e| format!("YAML parse error: {}", e))?,
+        })
+    }
+
     /// Converts the SongAliases struct to a YAML string
     ///
     /// # Returns
@@ -64,13 +146,31 @@ impl SongAliases {
     pub fn to_yml(&self) -> Result<String, serde_yml::Error> {
         serde_yml::to_string(self)
     }
+
+    /// Returns the canonical name of a song, resolving aliases
+    ///
+    /// Names are compared by slug, so differences in case and punctuation are ignored.
+    ///
+    /// # Arguments
+    /// * `name` - The song name as it appears in a setlist
+    ///
+    /// # Returns
+    /// * `String` - The canonical song name, or `name` itself if it is not a known song or alias
+    pub fn canonical_name(&self, name: &str) -> String {
+        let slug = name.to_string().slug();
+        self.songs
+            .iter()
+            .find(|s| s.name.slug() == slug || s.aliases.iter().any(|a| a.name.slug() == slug))
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| name.to_string())
+    }
 }
 
 // TODO:
 // setlist.status should be Enum
 // setlist.event_date should maybe be some date type
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Setlists {
     #[serde(rename = "setlist")]
     pub data: Vec<Setlist>,
@@ -82,7 +182,6 @@ impl Default for Setlists {
     }
 }
 
-#[allow(dead_code)]
 impl Setlists {
     /// Creates a new empty Setlists struct
     ///
@@ -114,6 +213,45 @@ impl Setlists {
         serde_yml::from_str(yml)
     }
 
+    /// Parses a Setlists struct from a string in the given format
+    ///
+    /// # Arguments
+    /// * `content` - A string containing XML or YAML data
+    /// * `format` - The format of the data
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed Setlists on success, or a parse error
+    pub fn parse(content: &str, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match format {
+            FileFormat::Xml => Self::from_xml(content).map_err(|e| format!("XML parse error: {}", e))?,
+            FileFormat::Yml => Self::from_yml(content).map_err(|e| format!("YAML parse error: {}", e))?,
+        })
+    }
+
+    /// Reads and parses all files with the extension of the given format in a directory, in file name order
+    ///
+    /// # Arguments
+    /// * `dir` - The directory to read
+    /// * `format` - The format of the files
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The parsed Setlists on success, or an I/O or parse error
+    pub fn read_dir(dir: &std::path::Path, format: FileFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut content = String::new();
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        for path in paths {
+            if path.extension().and_then(|s| s.to_str()) == Some(format.extension()) {
+                log::debug!("Loading file: {}", path.display());
+                content.push_str(&std::fs::read_to_string(path)?);
+                content.push('\n');
+            }
+        }
+        Self::parse(&content, format)
+    }
+
     /// Converts the Setlists struct to a YAML string
     ///
     /// # Returns
@@ -121,6 +259,41 @@ impl Setlists {
     pub fn to_yml(&self) -> Result<String, serde_yml::Error> {
         serde_yml::to_string(self)
     }
+
+    /// Returns all setlists matching a query
+    ///
+    /// # Arguments
+    /// * `query` - The filters to apply
+    /// * `aliases` - Song aliases used to resolve song names
+    ///
+    /// # Returns
+    /// * `Vec<&Setlist>` - The matching setlists, in master data order
+    pub fn query(&self, query: &SetlistQuery, aliases: &SongAliases) -> Vec<&Setlist> {
+        self.data.iter().filter(|s| query.matches(s, aliases)).collect()
+    }
+
+    /// Finds setlists by concert identifier or by date
+    ///
+    /// # Arguments
+    /// * `id` - A concert identifier as produced by `Setlist::identifier`, or a date (`dd-mm-yyyy` or `yyyy-mm-dd`)
+    ///
+    /// # Returns
+    /// * `Vec<&Setlist>` - All matching setlists; more than one if several concerts were played on that date
+    pub fn find(&self, id: &str) -> Vec<&Setlist> {
+        let by_slug: Vec<&Setlist> = self
+            .data
+            .iter()
+            .filter(|s| s.identifier() == id.to_string().slug())
+            .collect();
+        if !by_slug.is_empty() {
+            return by_slug;
+        }
+
+        match parse_date(id) {
+            Some(date) => self.data.iter().filter(|s| s.date() == Some(date)).collect(),
+            None => vec![],
+        }
+    }
 }
 
 /// Represents a setlist, which is a collection of songs played by an artist at a specific event.
@@ -133,13 +306,17 @@ impl Setlists {
 /// * `event_date`: The date of the event
 /// * `disambiguation`: Optional disambiguation information for the event
 /// * `sort_order`: Optional sort order for the setlist
-/// * `source`: Optional source information for the setlist
+/// * `source`: Optional source information for the setlist, as a single legacy string
+/// * `sources`: The known recordings of the concert; replace the legacy `source` when given
+/// * `mbid`: Optional MusicBrainz ID of the event
 /// * `artist`: The artist who played the setlist
 /// * `venue`: The venue where the setlist was played
 /// * `tour`: Optional tour information for the setlist
 /// * `notes`: Optional notes about the setlist
+/// * `members`: The band members who played the concert; overrides the lineup for its date when given
+/// * `guests`: Guest musicians who played (part of) the concert
 /// * `sets`: The sets played during the event
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Setlist {
     #[serde(rename = "status")]
     pub status: String,
@@ -153,17 +330,83 @@ pub struct Setlist {
     pub sort_order: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Sources::is_empty")]
+    pub sources: Sources,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mbid: Option<String>,
     pub artist: Artist,
     pub venue: Venue,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tour: Option<Tour>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
+    #[serde(rename = "member", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<Member>,
+    #[serde(rename = "guest", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub guests: Vec<Guest>,
     #[serde(rename = "sets")]
     pub sets: Sets,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl Setlist {
+    /// Parses the event date of the setlist
+    ///
+    /// The master data uses `dd-mm-yyyy`, but ISO dates (`yyyy-mm-dd`) are accepted as well.
+    ///
+    /// # Returns
+    /// * `Option<chrono::NaiveDate>` - The event date, or `None` if it could not be parsed
+    pub fn date(&self) -> Option<chrono::NaiveDate> {
+        parse_date(&self.event_date)
+    }
+
+    /// Returns the known recordings of the concert: its sources, or else its legacy source string parsed as a source
+    pub fn recordings(&self) -> Vec<Source> {
+        if !self.sources.is_empty() {
+            return self.sources.source.clone();
+        }
+        self.source.as_deref().map(Source::parse_legacy).into_iter().collect()
+    }
+
+    /// Returns the identifier of the concert, e.g. `motorpsycho-2021-05-16`
+    ///
+    /// This is the same slug that `Concert::identifier_with_prefix` produces with the artist name as prefix, and is
+    /// used as the concert slug in the database.
+    pub fn identifier(&self) -> String {
+        let date = self
+            .date()
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| self.event_date.clone());
+        match &self.disambiguation {
+            Some(d) => format!("{}-{}-{}", self.artist.name, date, d).slug(),
+            None => format!("{}-{}", self.artist.name, date).slug(),
+        }
+    }
+
+    /// Returns an iterator over all songs in all sets of the setlist
+    pub fn songs(&self) -> impl Iterator<Item = &Song> {
+        self.sets.set.iter().flat_map(|set| set.songs.iter().flatten())
+    }
+
+    /// Returns an iterator over all songs played in the setlist, with medleys split into their parts
+    pub fn played_songs(&self) -> impl Iterator<Item = &Song> {
+        self.songs().flat_map(|song| song.components())
+    }
+
+    /// Returns an iterator over the songs played in the setlist that are known, with medleys split into their parts
+    /// and placeholders for unknown songs left out
+    pub fn known_songs(&self) -> impl Iterator<Item = &Song> {
+        self.played_songs().filter(|song| !song.is_unknown())
+    }
+
+    /// Returns true if the setlist is known to be incomplete: a set is marked partial or a song is unknown
+    pub fn is_partial(&self) -> bool {
+        self.sets.set.iter().any(|set| set.partial == Some(true)) || self.played_songs().any(|song| song.is_unknown())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Artist {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -171,48 +414,93 @@ pub struct Artist {
     pub sort_name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mbid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Venue {
     pub name: String,
     pub city: City,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mbid: Option<String>,
+}
+
+impl Slug for Venue {
+    /// Returns a slug for the venue, made unique by including the city and country, e.g.
+    /// `verkstedhallen-trondheim-norway`
+    fn slug(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.name.slug(),
+            self.city.name.slug(),
+            self.city.country.name.slug()
+        )
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct City {
     pub name: String,
     pub country: Country,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Country {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Tour {
     pub name: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Sets {
     pub set: Vec<Set>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Set {
     #[serde(rename = "name")]
     pub name: Option<String>,
     #[serde(rename = "encore")]
     pub encore: Option<String>,
+    #[serde(rename = "uncertainOrder")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uncertain_order: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial: Option<bool>,
     #[serde(rename = "song")]
     pub songs: Option<Vec<Song>>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl Set {
+    /// Returns what is uncertain about the set, e.g. `incomplete, order uncertain`, or `None` if nothing is
+    pub fn qualifiers(&self) -> Option<String> {
+        let mut qualifiers = vec![];
+        if self.partial == Some(true) {
+            qualifiers.push("incomplete");
+        }
+        if self.uncertain_order == Some(true) {
+            qualifiers.push("order uncertain");
+        }
+        (!qualifiers.is_empty()).then(|| qualifiers.join(", "))
+    }
+
+    /// Returns the display name of the set: "Encore N" for encores, otherwise the set name if it has one
+    pub fn title(&self) -> Option<String> {
+        if let Some(encore) = &self.encore {
+            Some(format!("Encore {}", encore))
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Song {
     #[serde(rename = "name")]
     pub name: String,
@@ -223,6 +511,27 @@ pub struct Song {
     pub original_artist: Option<Artist>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
+    #[serde(rename = "workMbid")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_mbid: Option<String>,
+    #[serde(rename = "recordingMbid")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_mbid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(rename = "guest", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub guests: Vec<Guest>,
+    #[serde(rename = "tease", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub teases: Vec<Tease>,
+    #[serde(rename = "tag", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+    // The songs of a medley, played as one performance slot; the name is then the title of the medley
+    #[serde(rename = "part", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub medley: Vec<Song>,
     // #[serde(rename = "aliasFor")]
     // pub alias_for: Option<String>,
 }
@@ -234,7 +543,162 @@ impl Default for Song {
             segue: Some(false),
             original_artist: None,
             notes: None,
+            work_mbid: None,
+            recording_mbid: None,
+            duration: None,
+            guests: vec![],
+            teases: vec![],
+            tags: vec![],
+            medley: vec![],
             // alias_for: None,
         }
     }
 }
+
+impl Song {
+    /// Returns true if the song is a medley of several songs
+    pub fn is_medley(&self) -> bool {
+        !self.medley.is_empty()
+    }
+
+    /// Returns the songs played in this performance slot: the parts of a medley, or else the song itself
+    pub fn components(&self) -> &[Song] {
+        if self.is_medley() {
+            &self.medley
+        } else {
+            std::slice::from_ref(self)
+        }
+    }
+
+    /// Returns the songs played in this performance slot for editing: the parts of a medley, or else the song itself
+    pub fn components_mut(&mut self) -> &mut [Song] {
+        if self.is_medley() {
+            &mut self.medley
+        } else {
+            std::slice::from_mut(self)
+        }
+    }
+
+    /// Returns a part of this medley as it was performed: the part with the guests, teases and tags of the whole
+    /// medley added, each listed once
+    ///
+    /// # Arguments
+    /// * `part` - A part of the medley
+    ///
+    /// # Returns
+    /// * `Song` - The part, with the medley's guests, teases and tags
+    pub fn medley_part(&self, part: &Song) -> Song {
+        let mut performed = part.clone();
+        for guest in self.guests.iter() {
+            if !performed.guests.iter().any(|g| g.name.slug() == guest.name.slug()) {
+                performed.guests.push(guest.clone());
+            }
+        }
+        for tease in self.teases.iter() {
+            if !performed.teases.iter().any(|t| t.name.slug() == tease.name.slug()) {
+                performed.teases.push(tease.clone());
+            }
+        }
+        for tag in self.tags.iter() {
+            if !performed.has_tag(&tag.name) {
+                performed.tags.push(tag.clone());
+            }
+        }
+        performed
+    }
+
+    /// Returns the parts of a medley separated by slashes, e.g. `Hell, Part 1-3 / Un Chien d'Espace`, or `None` if
+    /// the song is not a medley
+    pub fn medley_names(&self) -> Option<String> {
+        let names: Vec<&str> = self.medley.iter().map(|s| s.name.as_str()).collect();
+        (!names.is_empty()).then(|| names.join(" / "))
+    }
+
+    /// Returns true if the song is a placeholder for a song that was played but is not known: its name is
+    /// `unknown` (in any case) or only question marks
+    pub fn is_unknown(&self) -> bool {
+        let name = self.name.trim();
+        name.eq_ignore_ascii_case(UNKNOWN_SONG) || (!name.is_empty() && name.chars().all(|c| c == '?'))
+    }
+
+    /// Returns the duration of the performance in seconds, or `None` if it is not given or cannot be parsed
+    pub fn duration_seconds(&self) -> Option<u32> {
+        self.duration.as_deref().and_then(parse_duration)
+    }
+
+    /// Returns the guests as text, e.g. `Ståle Storløkken (keyboards), Jenny Hval`, or `None` if there are none
+    pub fn guest_names(&self) -> Option<String> {
+        let guests: Vec<String> = self.guests.iter().map(|g| g.to_string()).collect();
+        (!guests.is_empty()).then(|| guests.join(", "))
+    }
+
+    /// Returns the teased songs separated by commas, or `None` if there are none
+    pub fn tease_names(&self) -> Option<String> {
+        let teases: Vec<&str> = self.teases.iter().map(|t| t.name.as_str()).collect();
+        (!teases.is_empty()).then(|| teases.join(", "))
+    }
+
+    /// Returns the version tags separated by commas, or `None` if there are none
+    pub fn tag_names(&self) -> Option<String> {
+        let tags: Vec<&str> = self.tags.iter().map(|t| t.name.as_str()).collect();
+        (!tags.is_empty()).then(|| tags.join(", "))
+    }
+
+    /// Returns true if the performance has a version tag, comparing case-insensitively
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.name.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// A guest musician on a performance
+///
+/// # Fields
+///
+/// * `name`: The name of the guest
+/// * `instrument`: What the guest played or sang, e.g. `saxophone`
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Guest {
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instrument: Option<String>,
+}
+
+impl std::fmt::Display for Guest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.instrument {
+            Some(instrument) => write!(f, "{} ({})", self.name, instrument),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// A song teased (briefly quoted) during a performance
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Tease {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+/// A version tag of a performance, e.g. `acoustic` or `partial`
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Tag {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+/// The name of the placeholder for a song that was played but is not known
+pub const UNKNOWN_SONG: &str = "unknown";
+
+/// The version tags validation knows; other tags are reported as possible typos
+pub const VERSION_TAGS: &[&str] = &[
+    "acoustic",
+    "electric",
+    "extended",
+    "instrumental",
+    "jam",
+    "partial",
+    "reprise",
+    "solo",
+    "unplugged",
+];
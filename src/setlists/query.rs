@@ -0,0 +1,128 @@
+use chrono::NaiveDate;
+
+use super::{Lineups, Setlist, SongAliases};
+use crate::slug::Slug;
+
+/// A set of filters that can be evaluated against setlists.
+///
+/// Every filter is optional, and a setlist matches the query only if it matches all filters that are set.
+/// Text filters (venue, city, country, tour) match case-insensitively on a substring of the name.
+///
+/// # Fields
+///
+/// * `song`: A song that must have been played, matched by canonical name (aliases are resolved)
+/// * `venue`: Part of the venue name
+/// * `city`: Part of the city name
+/// * `country`: Part of the country name
+/// * `from`: Earliest event date (inclusive)
+/// * `to`: Latest event date (inclusive)
+/// * `tour`: Part of the tour name
+/// * `cover_of`: The original artist of a cover that must have been played
+/// * `has_notes`: Only match setlists with notes on the concert or on any of its songs
+/// * `status`: The setlist status (e.g. "confirmed")
+/// * `musician`: A musician who must have played the concert, as a band member or a guest
+/// * `lineups`: The lineups used to find the band members of concerts without their own
+#[derive(Clone, Debug, Default)]
+pub struct SetlistQuery {
+    pub song: Option<String>,
+    pub venue: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub tour: Option<String>,
+    pub cover_of: Option<String>,
+    pub has_notes: bool,
+    pub status: Option<String>,
+    pub musician: Option<String>,
+    pub lineups: Lineups,
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl SetlistQuery {
+    /// Checks whether a setlist matches all filters of the query
+    ///
+    /// # Arguments
+    /// * `setlist` - The setlist to check
+    /// * `aliases` - Song aliases used to resolve song names
+    ///
+    /// # Returns
+    /// * `bool` - `true` if the setlist matches
+    pub fn matches(&self, setlist: &Setlist, aliases: &SongAliases) -> bool {
+        if let Some(status) = &self.status {
+            if !setlist.status.eq_ignore_ascii_case(status) {
+                return false;
+            }
+        }
+
+        if let Some(venue) = &self.venue {
+            if !contains_ignore_case(&setlist.venue.name, venue) {
+                return false;
+            }
+        }
+
+        if let Some(city) = &self.city {
+            if !contains_ignore_case(&setlist.venue.city.name, city) {
+                return false;
+            }
+        }
+
+        if let Some(country) = &self.country {
+            if !contains_ignore_case(&setlist.venue.city.country.name, country) {
+                return false;
+            }
+        }
+
+        if let Some(tour) = &self.tour {
+            match &setlist.tour {
+                Some(t) if contains_ignore_case(&t.name, tour) => {}
+                _ => return false,
+            }
+        }
+
+        if self.from.is_some() || self.to.is_some() {
+            let Some(date) = setlist.date() else {
+                return false;
+            };
+            if self.from.is_some_and(|from| date < from) || self.to.is_some_and(|to| date > to) {
+                return false;
+            }
+        }
+
+        if let Some(song) = &self.song {
+            let wanted = aliases.canonical_name(song).slug();
+            if !setlist
+                .played_songs()
+                .any(|s| aliases.canonical_name(&s.name).slug() == wanted)
+            {
+                return false;
+            }
+        }
+
+        if let Some(artist) = &self.cover_of {
+            let wanted = artist.slug();
+            if !setlist
+                .played_songs()
+                .any(|s| s.original_artist.as_ref().is_some_and(|a| a.name.slug() == wanted))
+            {
+                return false;
+            }
+        }
+
+        if let Some(musician) = &self.musician {
+            let wanted = musician.slug();
+            if !self.lineups.personnel(setlist).iter().any(|p| p.name.slug() == wanted) {
+                return false;
+            }
+        }
+
+        if self.has_notes && setlist.notes.is_none() && !setlist.songs().any(|s| s.notes.is_some()) {
+            return false;
+        }
+
+        true
+    }
+}
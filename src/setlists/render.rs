@@ -0,0 +1,146 @@
+use super::Setlist;
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// Wraps text in an ANSI escape sequence if colour is enabled
+fn paint(text: &str, code: &str, colour: bool) -> String {
+    if colour {
+        format!("{}{}{}", code, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Renders a setlist for display in a terminal
+///
+/// The output starts with a header (artist, date, venue, city, country, tour, status, source and notes), followed by
+/// the sets and encores with numbered songs. Segues are shown as `->` after the song, covers with their original
+/// artist, version tags and durations after the song, and guests, teases and song notes on their own lines below the
+/// song.
+///
+/// # Arguments
+/// * `setlist` - The setlist to render
+/// * `colour` - Whether to use ANSI colours
+///
+/// # Returns
+/// * `String` - The rendered setlist
+pub fn render_setlist(setlist: &Setlist, colour: bool) -> String {
+    let mut out = String::new();
+
+    let date = setlist
+        .date()
+        .map(|d| d.format("%A %-d %B %Y").to_string())
+        .unwrap_or_else(|| setlist.event_date.clone());
+    let mut title = format!("{} - {}", setlist.artist.name, date);
+    if let Some(disambiguation) = &setlist.disambiguation {
+        title.push_str(&format!(" ({})", disambiguation));
+    }
+    out.push_str(&paint(&title, BOLD, colour));
+    out.push('\n');
+    out.push_str(&format!(
+        "{}, {}, {}\n",
+        setlist.venue.name, setlist.venue.city.name, setlist.venue.city.country.name
+    ));
+    if let Some(tour) = &setlist.tour {
+        out.push_str(&format!("Tour: {}\n", tour.name));
+    }
+    out.push_str(&paint(&format!("Status: {}", setlist.status), DIM, colour));
+    out.push('\n');
+    if setlist.sources.is_empty() {
+        if let Some(source) = &setlist.source {
+            out.push_str(&paint(&format!("Source: {}", source), DIM, colour));
+            out.push('\n');
+        }
+    }
+    for source in setlist.sources.source.iter() {
+        out.push_str(&paint(&format!("Source: {}", source), DIM, colour));
+        out.push('\n');
+    }
+    if let Some(notes) = &setlist.notes {
+        out.push_str(&format!("Notes: {}\n", notes));
+    }
+
+    let mut set_number = 0;
+    for set in setlist.sets.set.iter() {
+        out.push('\n');
+        let heading = match (&set.encore, set.title()) {
+            (Some(_), Some(title)) => title,
+            (None, Some(name)) => {
+                set_number += 1;
+                format!("Set {}: {}", set_number, name)
+            }
+            (_, None) => {
+                set_number += 1;
+                format!("Set {}", set_number)
+            }
+        };
+        let heading = match set.qualifiers() {
+            Some(qualifiers) => format!("{} ({})", heading, qualifiers),
+            None => heading,
+        };
+        out.push_str(&paint(&heading, CYAN, colour));
+        out.push('\n');
+
+        let Some(songs) = &set.songs else {
+            out.push_str(&paint("  (no songs)", DIM, colour));
+            out.push('\n');
+            continue;
+        };
+
+        for (i, song) in songs.iter().enumerate() {
+            let mut line = format!("{:>4}. {}", i + 1, song.name);
+            if let Some(parts) = song.medley_names() {
+                line.push_str(&format!(" ({})", parts));
+            }
+            if let Some(artist) = &song.original_artist {
+                line.push(' ');
+                line.push_str(&paint(&format!("({} cover)", artist.name), YELLOW, colour));
+            }
+            if let Some(tags) = song.tag_names() {
+                line.push(' ');
+                line.push_str(&paint(&format!("[{}]", tags), DIM, colour));
+            }
+            if let Some(duration) = &song.duration {
+                line.push_str(&format!(" ({})", duration));
+            }
+            if song.segue.unwrap_or(false) {
+                line.push_str(" ->");
+            }
+            out.push_str(&line);
+            out.push('\n');
+            if let Some(guests) = song.guest_names() {
+                out.push_str(&paint(&format!("        with {}", guests), DIM, colour));
+                out.push('\n');
+            }
+            if let Some(teases) = song.tease_names() {
+                out.push_str(&paint(&format!("        teases {}", teases), DIM, colour));
+                out.push('\n');
+            }
+            if let Some(notes) = &song.notes {
+                out.push_str(&paint(&format!("        {}", notes), DIM, colour));
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+/// Renders a one-line summary of a setlist: date, venue and tour
+pub fn render_summary(setlist: &Setlist) -> String {
+    let mut line = format!(
+        "{}  {}, {}, {}",
+        setlist.event_date, setlist.venue.name, setlist.venue.city.name, setlist.venue.city.country.name
+    );
+    if let Some(disambiguation) = &setlist.disambiguation {
+        line.push_str(&format!(" ({})", disambiguation));
+    }
+    if let Some(tour) = &setlist.tour {
+        line.push_str(&format!("  [{}]", tour.name));
+    }
+    line
+}
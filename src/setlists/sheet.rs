@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// One row of the performances sheet: a single song played at a concert.
+///
+/// The concert and set details are repeated on every row, so the sheet can be sorted and filtered freely in a
+/// spreadsheet. Concert-level notes and sources are not part of the sheet.
+///
+/// A set without songs is kept as a row with position 0 and no song, and a concert without sets as a row with set
+/// number 0, so that importing the sheet gives back every concert and set. A medley has a row for each of its parts,
+/// all at the position of the medley; the notes and cover artist of the medley itself are not part of the sheet.
+///
+/// # Fields
+///
+/// * `date`: The event date of the concert
+/// * `disambiguation`: Disambiguation of the concert, if several were played on the same date
+/// * `artist`: The artist who played the concert
+/// * `status`: The setlist status
+/// * `venue`, `city`, `country`: Where the concert was played
+/// * `tour`: The tour the concert was part of
+/// * `set_number`: The position of the set in the concert, starting at 1, or 0 if the concert has no sets
+/// * `set_name`: The name of the set, if it is named
+/// * `encore`: The encore number, if the set is an encore
+/// * `position`: The position of the song in the set, starting at 1, or 0 if the set has no songs
+/// * `song`: The canonical song title, resolved through the song aliases
+/// * `played_as`: The song title as written in the setlist
+/// * `segue`: Whether the song segued into the next one
+/// * `cover_artist`: The original artist, if the song is a cover
+/// * `notes`: Notes on the performance
+/// * `medley`: The title of the medley, if the song was played as part of one
+/// * `medley_position`: The position of the song in the medley, starting at 1
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct PerformanceRow {
+    pub date: String,
+    pub disambiguation: Option<String>,
+    pub artist: String,
+    pub status: String,
+    pub venue: String,
+    pub city: String,
+    pub country: String,
+    pub tour: Option<String>,
+    pub set_number: usize,
+    pub set_name: Option<String>,
+    pub encore: Option<String>,
+    pub position: usize,
+    pub song: String,
+    pub played_as: String,
+    pub segue: bool,
+    pub cover_artist: Option<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub medley: Option<String>,
+    #[serde(default)]
+    pub medley_position: Option<usize>,
+}
+
+/// Flattens setlists into performance rows
+///
+/// # Arguments
+/// * `setlists` - The setlists to flatten
+/// * `aliases` - Song aliases used to resolve canonical song titles
+///
+/// # Returns
+/// * `Vec<PerformanceRow>` - One row per performance, in setlist order
+pub fn performance_rows(setlists: &[&Setlist], aliases: &SongAliases) -> Vec<PerformanceRow> {
+    let mut rows = vec![];
+    for setlist in setlists {
+        let concert = PerformanceRow {
+            date: setlist.event_date.clone(),
+            disambiguation: setlist.disambiguation.clone(),
+            artist: setlist.artist.name.clone(),
+            status: setlist.status.clone(),
+            venue: setlist.venue.name.clone(),
+            city: setlist.venue.city.name.clone(),
+            country: setlist.venue.city.country.name.clone(),
+            tour: setlist.tour.as_ref().map(|t| t.name.clone()),
+            ..Default::default()
+        };
+        if setlist.sets.set.is_empty() {
+            rows.push(concert.clone());
+        }
+        for (set_index, set) in setlist.sets.set.iter().enumerate() {
+            let set_row = PerformanceRow {
+                set_number: set_index + 1,
+                set_name: set.name.clone(),
+                encore: set.encore.clone(),
+                ..concert.clone()
+            };
+            if set.songs.as_ref().is_none_or(|songs| songs.is_empty()) {
+                rows.push(set_row.clone());
+            }
+            for (song_index, slot) in set.songs.iter().flatten().enumerate() {
+                let parts = slot.components();
+                for (part_index, song) in parts.iter().enumerate() {
+                    // The last part of a medley segues into the next song if the medley does
+                    let segue = if slot.is_medley() && part_index + 1 == parts.len() {
+                        slot.segue
+                    } else {
+                        song.segue
+                    };
+                    rows.push(PerformanceRow {
+                        position: song_index + 1,
+                        song: aliases.canonical_name(&song.name),
+                        played_as: song.name.clone(),
+                        segue: segue.unwrap_or(false),
+                        cover_artist: song.original_artist.as_ref().map(|a| a.name.clone()),
+                        notes: song.notes.clone(),
+                        medley: slot.is_medley().then(|| slot.name.clone()),
+                        medley_position: slot.is_medley().then_some(part_index + 1),
+                        ..set_row.clone()
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Exports setlists as CSV, with one row per performance
+///
+/// # Arguments
+/// * `setlists` - The setlists to export
+/// * `aliases` - Song aliases used to resolve canonical song titles
+///
+/// # Returns
+/// * `Result<String, Box<dyn std::error::Error>>` - The CSV data on success, or a serialization error
+pub fn export_csv(setlists: &[&Setlist], aliases: &SongAliases) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = ::csv::Writer::from_writer(vec![]);
+    for row in performance_rows(setlists, aliases) {
+        writer.serialize(row)?;
+    }
+    let data = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8(data)?)
+}
+
+impl Setlists {
+    /// Rebuilds setlists from CSV data as written by `export_csv`
+    ///
+    /// Consecutive rows with the same date, disambiguation and artist form one concert, and rows with the same set
+    /// number within a concert form one set. Songs are named by their played-as title, falling back to the canonical
+    /// title if it is empty. Rows with set number 0 or position 0 only add their concert or set, and consecutive rows
+    /// of the same medley form one medley.
+    ///
+    /// # Arguments
+    /// * `data` - A string containing CSV data
+    ///
+    /// # Returns
+    /// * `Result<Self, csv::Error>` - The parsed Setlists on success, or a deserialization error
+    pub fn from_csv(data: &str) -> Result<Self, ::csv::Error> {
+        let mut reader = ::csv::Reader::from_reader(data.as_bytes());
+        let mut setlists = Setlists::new();
+        let mut current_set = 0;
+
+        for row in reader.deserialize() {
+            let row: PerformanceRow = row?;
+            let same_concert = setlists.data.last().is_some_and(|s: &Setlist| {
+                s.event_date == row.date && s.disambiguation == row.disambiguation && s.artist.name == row.artist
+            });
+            if !same_concert {
+                setlists.data.push(Setlist {
+                    status: row.status.clone(),
+                    event_date: row.date.clone(),
+                    disambiguation: row.disambiguation.clone(),
+                    sort_order: None,
+                    source: None,
+                    sources: Sources::default(),
+                    mbid: None,
+                    artist: Artist {
+                        name: row.artist.clone(),
+                        sort_name: None,
+                        mbid: None,
+                        country: None,
+                    },
+                    venue: Venue {
+                        name: row.venue.clone(),
+                        city: City {
+                            name: row.city.clone(),
+                            country: Country {
+                                name: row.country.clone(),
+                                code: None,
+                            },
+                        },
+                        mbid: None,
+                    },
+                    tour: row.tour.clone().map(|name| Tour { name }),
+                    notes: None,
+                    members: vec![],
+                    guests: vec![],
+                    sets: Sets { set: vec![] },
+                });
+                current_set = 0;
+            }
+
+            let setlist = setlists.data.last_mut().unwrap();
+            if row.set_number == 0 {
+                continue;
+            }
+            if current_set != row.set_number || setlist.sets.set.is_empty() {
+                setlist.sets.set.push(Set {
+                    name: row.set_name.clone(),
+                    encore: row.encore.clone(),
+                    uncertain_order: None,
+                    partial: None,
+                    songs: None,
+                });
+                current_set = row.set_number;
+            }
+            if row.position == 0 {
+                continue;
+            }
+
+            let name = if row.played_as.is_empty() {
+                row.song
+            } else {
+                row.played_as
+            };
+            let song = Song {
+                name,
+                segue: row.segue.then_some(true),
+                original_artist: row.cover_artist.map(|name| Artist {
+                    name,
+                    sort_name: None,
+                    mbid: None,
+                    country: None,
+                }),
+                notes: row.notes,
+                ..Default::default()
+            };
+            let songs = setlist.sets.set.last_mut().unwrap().songs.get_or_insert_with(Vec::new);
+            let Some(title) = row.medley else {
+                songs.push(song);
+                continue;
+            };
+            let slot = match songs.last_mut() {
+                Some(slot) if slot.is_medley() && slot.name == title && row.medley_position != Some(1) => slot,
+                _ => {
+                    songs.push(Song {
+                        name: title,
+                        ..Default::default()
+                    });
+                    songs.last_mut().unwrap()
+                }
+            };
+            // The segue of a part is on the medley until the next part follows
+            if let Some(previous) = slot.medley.last_mut() {
+                previous.segue = slot.segue.take();
+            }
+            slot.segue = song.segue;
+            slot.medley.push(Song { segue: None, ..song });
+        }
+
+        Ok(setlists)
+    }
+}
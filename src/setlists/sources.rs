@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+/// The kinds of recordings a source can be; other kinds are reported by validation
+pub const SOURCE_KINDS: &[&str] = &["audience", "soundboard", "matrix", "fm", "video"];
+
+/// The known recordings of a concert
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Sources {
+    #[serde(rename = "source", default)]
+    pub source: Vec<Source>,
+}
+
+impl Sources {
+    /// Returns true if no recordings are known
+    pub fn is_empty(&self) -> bool {
+        self.source.is_empty()
+    }
+}
+
+/// A recording of a concert, as circulated among tape traders
+///
+/// # Fields
+///
+/// * `kind`: The kind of recording, one of `SOURCE_KINDS`
+/// * `lineage`: The transfer chain, e.g. `DAT > CD-R > EAC > FLAC`
+/// * `taper`: The person who made the recording
+/// * `archive_id`: The identifier of the recording in an archive, e.g. an archive.org item or an etree SHN ID
+/// * `complete`: Whether the whole concert was recorded
+/// * `notes`: Other information about the recording
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Source {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taper: Option<String>,
+    #[serde(rename = "archiveId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub complete: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Words in legacy source strings that give away the kind of recording, checked in this order
+const KIND_WORDS: &[(&str, &[&str])] = &[
+    ("matrix", &["matrix"]),
+    ("video", &["video", "vhs", "dvd", "pro-shot", "proshot"]),
+    ("fm", &["fm", "radio", "broadcast"]),
+    ("soundboard", &["soundboard", "sbd", "board"]),
+    ("audience", &["audience", "aud"]),
+];
+
+/// Phrases in legacy source strings that precede the name of the taper
+const TAPER_PREFIXES: &[&str] = &["taped by ", "recorded by ", "taper: "];
+
+impl Source {
+    /// Parses a legacy single-string source, e.g. `SBD > DAT > CD, taped by John Doe`
+    ///
+    /// The string is split at commas and semicolons. The kind is recognised by keywords such as `AUD`, `SBD` or `FM`,
+    /// a part containing `>` is taken as the lineage, and a part starting with `taped by` or `recorded by` as the
+    /// taper. Other parts that are not just a kind keyword are kept as the notes.
+    ///
+    /// # Arguments
+    /// * `legacy` - The legacy source string
+    ///
+    /// # Returns
+    /// * `Source` - The structured source
+    pub fn parse_legacy(legacy: &str) -> Source {
+        let lowercase = legacy.to_lowercase();
+        let all_words = words(&lowercase);
+        let kind = KIND_WORDS
+            .iter()
+            .find(|(_, keywords)| keywords.iter().any(|k| all_words.contains(k)))
+            .map(|(kind, _)| kind.to_string());
+
+        let mut source = Source {
+            kind,
+            ..Default::default()
+        };
+        let mut notes = vec![];
+        for part in legacy.split([',', ';']).map(str::trim).filter(|p| !p.is_empty()) {
+            let lower = part.to_lowercase();
+            if let Some(prefix) = TAPER_PREFIXES.iter().find(|p| lower.starts_with(*p)) {
+                source.taper = Some(part[prefix.len()..].trim().to_string());
+            } else if part.contains('>') {
+                source.lineage = Some(part.to_string());
+            } else if !words(&lower)
+                .iter()
+                .all(|w| KIND_WORDS.iter().any(|(_, k)| k.contains(w)))
+            {
+                notes.push(part);
+            }
+        }
+        if !notes.is_empty() {
+            source.notes = Some(notes.join(", "));
+        }
+        source
+    }
+}
+
+/// Splits lowercase text into words, keeping hyphenated words such as `pro-shot` together
+fn words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+impl std::fmt::Display for Source {
+    /// Formats the source as a single line, e.g. `soundboard, taped by John Doe, DAT > CD (incomplete)`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = vec![self.kind.clone().unwrap_or_else(|| "unknown".to_string())];
+        parts.extend(self.taper.as_ref().map(|t| format!("taped by {}", t)));
+        parts.extend(self.lineage.clone());
+        parts.extend(self.archive_id.as_ref().map(|a| format!("archive {}", a)));
+        parts.extend(self.notes.clone());
+        write!(f, "{}", parts.join(", "))?;
+        if self.complete == Some(false) {
+            write!(f, " (incomplete)")?;
+        }
+        Ok(())
+    }
+}
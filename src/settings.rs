@@ -0,0 +1,218 @@
+//! Settings from the config file, the selected profile and `MPDBTOOL_*` environment variables.
+//!
+//! The config file is the one given with `--config`, or else `mpdbtoolconfig.toml` in the working directory, or else
+//! `$XDG_CONFIG_HOME/mpdbtool/config.toml` (`~/.config/mpdbtool/config.toml`). Without any config file, the defaults
+//! are used. A profile (`[profiles.<name>]`) overrides the top-level values of the file, and environment variables
+//! override both: `MPDBTOOL_MPDB_BASE_URL`, `MPDBTOOL_TOKEN`, `MPDBTOOL_PAGINATION__MODE`, ...
+
+use config::{Config, ConfigError, Environment};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::mpdb::DEFAULT_HOME_ARTIST;
+use crate::storage::{Entity, Pagination, RestStorage};
+
+/// The config file looked for in the working directory
+pub const CONFIG_FILE: &str = "mpdbtoolconfig.toml";
+
+/// Pagination of the REST API listings
+///
+/// # Fields
+///
+/// * `mode`: The default mode for all endpoints: `none`, `page` or `cursor`
+/// * `limit`: The number of rows per page
+/// * `endpoints`: Modes for single endpoints, by endpoint name (e.g. `performances = "cursor"`)
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PaginationSettings {
+    pub mode: String,
+    pub limit: usize,
+    pub endpoints: HashMap<String, String>,
+}
+
+impl Default for PaginationSettings {
+    fn default() -> Self {
+        PaginationSettings {
+            mode: "none".to_string(),
+            limit: Pagination::DEFAULT_LIMIT,
+            endpoints: HashMap::new(),
+        }
+    }
+}
+
+/// The settings of mpdbtool
+///
+/// # Fields
+///
+/// * `mpdb_base_url`: The base URL of the MPDB API; only needed by commands that talk to it
+/// * `master_path`: The directory of the master file and the song aliases file
+/// * `master_filename`, `aliases_filename`: The names of those files, without extension
+/// * `songs_filename`: The name of the song catalogue file in the same directory, without extension
+/// * `discography_filename`: The name of the discography file in the same directory, without extension
+/// * `lineups_filename`: The name of the band lineups file in the same directory, without extension
+/// * `master_dir`: The directory with the master data split into several files
+/// * `sync_state_file`: The sync state cache file
+/// * `username`, `password`: Credentials for HTTP basic authentication against the API
+/// * `token`: A bearer token for the API, used instead of the username and password
+/// * `pagination`: Pagination of the API listings
+/// * `home_artist`: The artist the archive is about, who owns the songs in the song aliases without an artist
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub mpdb_base_url: Option<String>,
+    pub master_path: String,
+    pub master_filename: String,
+    pub aliases_filename: String,
+    pub songs_filename: String,
+    pub discography_filename: String,
+    pub lineups_filename: String,
+    pub master_dir: String,
+    pub sync_state_file: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub pagination: PaginationSettings,
+    pub home_artist: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mpdb_base_url: None,
+            master_path: "mpdbmasterdata".to_string(),
+            master_filename: "master".to_string(),
+            aliases_filename: "master_aliases".to_string(),
+            songs_filename: "master_songs".to_string(),
+            discography_filename: "master_discography".to_string(),
+            lineups_filename: "master_lineups".to_string(),
+            master_dir: "mpdbmasterdata/sorted".to_string(),
+            sync_state_file: "mpdbsync.json".to_string(),
+            username: None,
+            password: None,
+            token: None,
+            pagination: PaginationSettings::default(),
+            home_artist: DEFAULT_HOME_ARTIST.to_string(),
+        }
+    }
+}
+
+/// Returns the config file to use: the given one, or the first one that exists of `mpdbtoolconfig.toml` in the
+/// working directory and `mpdbtool/config.toml` in the XDG config directory
+pub fn find_config_file(path: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = path {
+        return Some(path.to_path_buf());
+    }
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.exists() {
+        return Some(local);
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    let xdg = config_home.join("mpdbtool").join("config.toml");
+    xdg.exists().then_some(xdg)
+}
+
+impl Settings {
+    /// Loads the settings
+    ///
+    /// # Arguments
+    /// * `path` - The config file given on the command line, if any; it must exist
+    /// * `profile` - The profile to use, if any; it must exist in the config file
+    ///
+    /// # Returns
+    /// * `Result<Settings, ConfigError>` - The settings on success, or an error if the config file cannot be read or
+    ///   parsed, the profile does not exist, or the pagination limit is 0
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Settings, ConfigError> {
+        Self::load_with_env(path, profile, None)
+    }
+
+    /// Loads the settings like `load`, taking the environment variables from `env` instead of the process
+    /// environment if given
+    pub fn load_with_env(
+        path: Option<&Path>,
+        profile: Option<&str>,
+        env: Option<HashMap<String, String>>,
+    ) -> Result<Settings, ConfigError> {
+        let mut file = Config::builder();
+        if let Some(path) = find_config_file(path) {
+            log::debug!("Reading config file {}", path.display());
+            file = file.add_source(config::File::from(path));
+        }
+        let file = file.build()?;
+
+        let mut builder = Config::builder().add_source(file.clone());
+        if let Some(profile) = profile {
+            let values: config::Map<String, config::Value> = file
+                .get(&format!("profiles.{}", profile))
+                .map_err(|_| ConfigError::Message(format!("Unknown profile '{}'", profile)))?;
+            let mut overrides = Config::builder();
+            for (key, value) in values {
+                overrides = overrides.set_override(key, value)?;
+            }
+            builder = builder.add_source(overrides.build()?);
+        }
+        let environment = Environment::with_prefix("MPDBTOOL")
+            .prefix_separator("_")
+            .separator("__")
+            .try_parsing(true)
+            .source(env);
+
+        let settings: Settings = builder.add_source(environment).build()?.try_deserialize()?;
+        if settings.pagination.limit == 0 {
+            return Err(ConfigError::Message("pagination.limit must be at least 1".to_string()));
+        }
+        Ok(settings)
+    }
+
+    /// Returns the master file path without extension
+    pub fn master_file(&self) -> String {
+        format!("{}/{}", self.master_path, self.master_filename)
+    }
+
+    /// Returns the song aliases file path without extension
+    pub fn aliases_file(&self) -> String {
+        format!("{}/{}", self.master_path, self.aliases_filename)
+    }
+
+    /// Returns the song catalogue file path without extension
+    pub fn songs_file(&self) -> String {
+        format!("{}/{}", self.master_path, self.songs_filename)
+    }
+
+    /// Returns the discography file path without extension
+    pub fn discography_file(&self) -> String {
+        format!("{}/{}", self.master_path, self.discography_filename)
+    }
+
+    /// Returns the band lineups file path without extension
+    pub fn lineups_file(&self) -> String {
+        format!("{}/{}", self.master_path, self.lineups_filename)
+    }
+
+    /// Returns the base URL of the MPDB API, or an error if it is not configured
+    pub fn base_url(&self) -> Result<&str, String> {
+        self.mpdb_base_url.as_deref().ok_or_else(|| {
+            "mpdb_base_url is not set: add it to the config file, or set MPDBTOOL_MPDB_BASE_URL".to_string()
+        })
+    }
+
+    /// Creates the REST storage for the MPDB API, with the configured credentials and pagination
+    pub fn rest_storage(&self) -> Result<RestStorage, Box<dyn std::error::Error>> {
+        let limit = self.pagination.limit;
+        let mut storage = RestStorage::new(self.base_url()?.to_string())
+            .with_pagination(Pagination::from_mode(&self.pagination.mode, limit)?);
+        for (name, mode) in self.pagination.endpoints.iter() {
+            let entity = Entity::from_name(name).ok_or(format!("Unknown endpoint '{}' in pagination config", name))?;
+            storage = storage.with_endpoint_pagination(entity, Pagination::from_mode(mode, limit)?);
+        }
+        if let Some(token) = &self.token {
+            storage = storage.with_token(token.clone());
+        } else if let Some(username) = &self.username {
+            storage = storage.with_basic_auth(username.clone(), self.password.clone());
+        }
+        Ok(storage)
+    }
+}
@@ -0,0 +1,343 @@
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::setlists::*;
+use crate::slug::*;
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:50em;margin:auto;padding:1em;line-height:1.4}\
+nav a{margin-right:1em}ol{padding-left:2em}.meta,.notes{color:#666}.cover{color:#a60}";
+
+/// A group of concerts sharing a song, venue, tour or year
+struct Group<'a> {
+    name: String,
+    concerts: Vec<&'a Setlist>,
+}
+
+/// A static HTML site generated from the master data.
+///
+/// The site has one page per concert, song, venue, tour and year, each in its own directory with an index page,
+/// plus a front page linking to all indexes. Page file names are slugs, so URLs are the same as in the database.
+pub struct Site<'a> {
+    master: &'a Setlists,
+    aliases: &'a SongAliases,
+}
+
+/// Escapes text for inclusion in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Wraps page content in a complete HTML document
+///
+/// `root` is the relative path from the page to the site root, e.g. `../` for pages in a section directory.
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n\
+         </head>\n<body>\n<nav><a href=\"{root}index.html\">Home</a><a href=\"{root}concerts/index.html\">Concerts</a>\
+         <a href=\"{root}songs/index.html\">Songs</a><a href=\"{root}venues/index.html\">Venues</a>\
+         <a href=\"{root}tours/index.html\">Tours</a><a href=\"{root}years/index.html\">Years</a></nav>\n\
+         <h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape(title),
+    )
+}
+
+fn link(href: &str, text: &str) -> String {
+    format!("<a href=\"{}\">{}</a>", href, escape(text))
+}
+
+fn year_of(setlist: &Setlist) -> Option<String> {
+    setlist.date().map(|d| d.format("%Y").to_string())
+}
+
+impl<'a> Site<'a> {
+    pub fn new(master: &'a Setlists, aliases: &'a SongAliases) -> Self {
+        Site { master, aliases }
+    }
+
+    /// Returns all concerts sorted by date and sort order
+    fn concerts(&self) -> Vec<&'a Setlist> {
+        let mut concerts: Vec<&Setlist> = self.master.data.iter().collect();
+        concerts.sort_by_key(|s| (s.date(), s.sort_order));
+        concerts
+    }
+
+    /// Groups the concerts by a key, keyed on slug
+    fn group_by<F>(&self, key: F) -> BTreeMap<String, Group<'a>>
+    where
+        F: Fn(&'a Setlist) -> Vec<String>,
+    {
+        let mut groups: BTreeMap<String, Group<'a>> = BTreeMap::new();
+        for setlist in self.concerts() {
+            for name in key(setlist) {
+                let group = groups.entry(name.slug()).or_insert_with(|| Group {
+                    name: name.clone(),
+                    concerts: vec![],
+                });
+                if !group.concerts.iter().any(|s| std::ptr::eq(*s, setlist)) {
+                    group.concerts.push(setlist);
+                }
+            }
+        }
+        groups
+    }
+
+    fn songs(&self) -> BTreeMap<String, Group<'a>> {
+        let aliases = self.aliases;
+        self.group_by(|s| s.known_songs().map(|song| aliases.canonical_name(&song.name)).collect())
+    }
+
+    fn venues(&self) -> BTreeMap<String, Group<'a>> {
+        let mut groups: BTreeMap<String, Group<'a>> = BTreeMap::new();
+        for setlist in self.concerts() {
+            groups
+                .entry(setlist.venue.slug())
+                .or_insert_with(|| Group {
+                    name: format!(
+                        "{}, {}, {}",
+                        setlist.venue.name, setlist.venue.city.name, setlist.venue.city.country.name
+                    ),
+                    concerts: vec![],
+                })
+                .concerts
+                .push(setlist);
+        }
+        groups
+    }
+
+    fn tours(&self) -> BTreeMap<String, Group<'a>> {
+        self.group_by(|s| s.tour.iter().map(|t| t.name.clone()).collect())
+    }
+
+    fn years(&self) -> BTreeMap<String, Group<'a>> {
+        self.group_by(|s| year_of(s).into_iter().collect())
+    }
+
+    /// Renders a list of concerts with links to their pages, from a page in a section directory
+    fn concert_list(&self, concerts: &[&Setlist]) -> String {
+        let mut html = String::from("<ul>\n");
+        for setlist in concerts {
+            html.push_str(&format!(
+                "<li>{} {}</li>\n",
+                link(
+                    &format!("../concerts/{}.html", setlist.identifier()),
+                    &setlist.event_date
+                ),
+                escape(&format!(
+                    "{}, {}, {}",
+                    setlist.venue.name, setlist.venue.city.name, setlist.venue.city.country.name
+                ))
+            ));
+        }
+        html.push_str("</ul>\n");
+        html
+    }
+
+    fn concert_page(&self, setlist: &Setlist) -> String {
+        let mut body = String::from("<p class=\"meta\">");
+        body.push_str(&link(
+            &format!("../venues/{}.html", setlist.venue.slug()),
+            &setlist.venue.name,
+        ));
+        body.push_str(&escape(&format!(
+            ", {}, {}",
+            setlist.venue.city.name, setlist.venue.city.country.name
+        )));
+        if let Some(year) = year_of(setlist) {
+            body.push_str(" &middot; ");
+            body.push_str(&link(&format!("../years/{}.html", year), &year));
+        }
+        if let Some(tour) = &setlist.tour {
+            body.push_str(" &middot; ");
+            body.push_str(&link(&format!("../tours/{}.html", tour.name.slug()), &tour.name));
+        }
+        body.push_str(&format!(" &middot; {}</p>\n", escape(&setlist.status)));
+        if let Some(notes) = &setlist.notes {
+            body.push_str(&format!("<p class=\"notes\">{}</p>\n", escape(notes)));
+        }
+
+        let mut set_number = 0;
+        for set in setlist.sets.set.iter() {
+            let heading = match (&set.encore, set.title()) {
+                (Some(_), Some(title)) => title,
+                (None, Some(name)) => name,
+                (_, None) => {
+                    set_number += 1;
+                    format!("Set {}", set_number)
+                }
+            };
+            let heading = match set.qualifiers() {
+                Some(qualifiers) => format!("{} ({})", heading, qualifiers),
+                None => heading,
+            };
+            body.push_str(&format!("<h2>{}</h2>\n<ol>\n", escape(&heading)));
+            for song in set.songs.iter().flatten() {
+                body.push_str("<li>");
+                if song.is_medley() {
+                    let parts: Vec<String> = song.medley.iter().map(|part| self.song_link(part)).collect();
+                    body.push_str(&format!("{} ({})", escape(&song.name), parts.join(" / ")));
+                } else {
+                    body.push_str(&self.song_link(song));
+                }
+                if let Some(artist) = &song.original_artist {
+                    body.push_str(&format!(
+                        " <span class=\"cover\">({} cover)</span>",
+                        escape(&artist.name)
+                    ));
+                }
+                if song.segue.unwrap_or(false) {
+                    body.push_str(" &gt;");
+                }
+                if let Some(notes) = &song.notes {
+                    body.push_str(&format!(" <span class=\"notes\">{}</span>", escape(notes)));
+                }
+                body.push_str("</li>\n");
+            }
+            body.push_str("</ol>\n");
+        }
+
+        let mut title = format!("{} {}", setlist.artist.name, setlist.event_date);
+        if let Some(disambiguation) = &setlist.disambiguation {
+            title.push_str(&format!(" ({})", disambiguation));
+        }
+        page(&title, "../", &body)
+    }
+
+    /// Returns a link to the page of a song, or just its name if the song is unknown
+    fn song_link(&self, song: &Song) -> String {
+        if song.is_unknown() {
+            format!("<em>{}</em>", escape(&song.name))
+        } else {
+            let canonical = self.aliases.canonical_name(&song.name);
+            link(&format!("../songs/{}.html", canonical.slug()), &song.name)
+        }
+    }
+
+    fn song_page(&self, group: &Group) -> String {
+        let mut body = format!("<p class=\"meta\">Played {} time(s)</p>\n<ul>\n", group.concerts.len());
+        for setlist in group.concerts.iter() {
+            let played_as: Vec<String> = setlist
+                .played_songs()
+                .filter(|s| self.aliases.canonical_name(&s.name) == group.name && s.name != group.name)
+                .map(|s| s.name.clone())
+                .collect();
+            body.push_str(&format!(
+                "<li>{} {}",
+                link(
+                    &format!("../concerts/{}.html", setlist.identifier()),
+                    &setlist.event_date
+                ),
+                escape(&format!("{}, {}", setlist.venue.name, setlist.venue.city.name))
+            ));
+            if !played_as.is_empty() {
+                body.push_str(&format!(
+                    " <span class=\"meta\">(as {})</span>",
+                    escape(&played_as.join(", "))
+                ));
+            }
+            body.push_str("</li>\n");
+        }
+        body.push_str("</ul>\n");
+        page(&group.name, "../", &body)
+    }
+
+    fn group_page(&self, group: &Group) -> String {
+        page(&group.name, "../", &self.concert_list(&group.concerts))
+    }
+
+    fn index_page(title: &str, groups: &BTreeMap<String, Group>) -> String {
+        let mut body = String::from("<ul>\n");
+        for (slug, group) in groups {
+            body.push_str(&format!(
+                "<li>{} ({})</li>\n",
+                link(&format!("{}.html", slug), &group.name),
+                group.concerts.len()
+            ));
+        }
+        body.push_str("</ul>\n");
+        page(title, "../", &body)
+    }
+
+    /// Writes a page to disk, creating its directory if needed
+    fn write(out: &Path, path: &str, content: &str) -> std::io::Result<()> {
+        let path = out.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        debug!("Writing {}", path.display());
+        std::fs::write(path, content)
+    }
+
+    /// Generates the site into a directory
+    ///
+    /// # Arguments
+    /// * `out` - The output directory; it is created if it doesn't exist, and existing pages are overwritten
+    ///
+    /// # Returns
+    /// * `std::io::Result<usize>` - The number of pages written on success, or an I/O error
+    pub fn build(&self, out: &Path) -> std::io::Result<usize> {
+        let mut pages = 0;
+
+        let concerts = self.concerts();
+        info!("Writing {} concert pages", concerts.len());
+        for setlist in concerts.iter() {
+            Self::write(
+                out,
+                &format!("concerts/{}.html", setlist.identifier()),
+                &self.concert_page(setlist),
+            )?;
+            pages += 1;
+        }
+        Self::write(
+            out,
+            "concerts/index.html",
+            &page("Concerts", "../", &self.concert_list(&concerts)),
+        )?;
+        pages += 1;
+
+        let songs = self.songs();
+        info!("Writing {} song pages", songs.len());
+        for (slug, group) in songs.iter() {
+            Self::write(out, &format!("songs/{}.html", slug), &self.song_page(group))?;
+            pages += 1;
+        }
+        Self::write(out, "songs/index.html", &Self::index_page("Songs", &songs))?;
+        pages += 1;
+
+        for (section, title, groups) in [
+            ("venues", "Venues", self.venues()),
+            ("tours", "Tours", self.tours()),
+            ("years", "Years", self.years()),
+        ] {
+            info!("Writing {} {} pages", groups.len(), section);
+            for (slug, group) in groups.iter() {
+                Self::write(out, &format!("{}/{}.html", section, slug), &self.group_page(group))?;
+                pages += 1;
+            }
+            Self::write(
+                out,
+                &format!("{}/index.html", section),
+                &Self::index_page(title, &groups),
+            )?;
+            pages += 1;
+        }
+
+        let body = format!(
+            "<ul>\n<li>{} ({})</li>\n<li>{} ({})</li>\n<li>{}</li>\n<li>{}</li>\n<li>{}</li>\n</ul>\n",
+            link("concerts/index.html", "Concerts"),
+            concerts.len(),
+            link("songs/index.html", "Songs"),
+            songs.len(),
+            link("venues/index.html", "Venues"),
+            link("tours/index.html", "Tours"),
+            link("years/index.html", "Years"),
+        );
+        Self::write(out, "index.html", &page("Setlist archive", "", &body))?;
+        pages += 1;
+
+        Ok(pages)
+    }
+}
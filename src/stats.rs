@@ -0,0 +1,438 @@
+//! Statistics over the master data.
+//!
+//! Songs are counted by their default title in the song aliases, so a song played under several titles is counted
+//! once. Performances can be filtered by their guests, teases and version tags. Placeholders for unknown songs are
+//! never counted, and setlists known to be incomplete do not count towards the gaps between performances.
+//! Recordings are counted from the sources of the concerts, with legacy source strings parsed.
+
+use std::collections::HashSet;
+
+use crate::setlists::{render_summary, Discography, Setlist, Setlists, Song, SongAliases};
+use crate::slug::Slug;
+
+/// How concerts are grouped in statistics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    /// By the tour of the concert
+    Tour,
+    /// By the year of the concert
+    Year,
+}
+
+impl GroupBy {
+    /// Returns the name of the group a concert belongs to
+    fn group(&self, setlist: &Setlist) -> String {
+        match self {
+            GroupBy::Tour => setlist
+                .tour
+                .as_ref()
+                .map(|t| t.name.clone())
+                .unwrap_or_else(|| "(no tour)".to_string()),
+            GroupBy::Year => setlist
+                .date()
+                .map(|d| d.format("%Y").to_string())
+                .unwrap_or_else(|| "(unknown year)".to_string()),
+        }
+    }
+}
+
+/// Which performances statistics count
+///
+/// # Fields
+///
+/// * `guest`: Only performances with a guest whose name contains this text
+/// * `tag`: Only performances with this version tag
+/// * `tease`: Only performances that tease a song whose name contains this text
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PerformanceFilter {
+    pub guest: Option<String>,
+    pub tag: Option<String>,
+    pub tease: Option<String>,
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+impl PerformanceFilter {
+    /// Returns true if a performance passes the filter
+    pub fn matches(&self, song: &Song) -> bool {
+        self.guest
+            .as_ref()
+            .is_none_or(|guest| song.guests.iter().any(|g| contains_ignore_case(&g.name, guest)))
+            && self.tag.as_ref().is_none_or(|tag| song.has_tag(tag))
+            && self
+                .tease
+                .as_ref()
+                .is_none_or(|tease| song.teases.iter().any(|t| contains_ignore_case(&t.name, tease)))
+    }
+}
+
+/// Returns the known songs played in a setlist that pass a filter, with medleys split into their parts
+///
+/// A part of a medley passes if either the part or the whole medley does, e.g. a guest listed on the medley.
+fn played<'a>(setlist: &'a Setlist, filter: &'a PerformanceFilter) -> impl Iterator<Item = &'a Song> {
+    setlist
+        .songs()
+        .flat_map(move |slot| {
+            let whole = filter.matches(slot);
+            slot.components()
+                .iter()
+                .filter(move |song| whole || filter.matches(song))
+        })
+        .filter(|song| !song.is_unknown())
+}
+
+/// Returns the setlists in date order; setlists with an unparseable date come last, in master data order
+fn by_date(master: &Setlists) -> Vec<&Setlist> {
+    let mut setlists: Vec<&Setlist> = master.data.iter().collect();
+    setlists.sort_by_key(|s| (s.date().is_none(), s.date()));
+    setlists
+}
+
+/// How many songs of an album were played in one group of concerts
+///
+/// # Fields
+///
+/// * `name`: The name of the group, e.g. a tour name or a year
+/// * `played`: The songs of the album played in the group, in tracklist order
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverageGroup {
+    pub name: String,
+    pub played: Vec<String>,
+}
+
+/// How many songs of an album were played, per group of concerts
+///
+/// # Fields
+///
+/// * `album`: The title of the album
+/// * `year`: The year the album was released
+/// * `tracks`: The songs on the album, by their default title
+/// * `groups`: The groups in which songs of the album were played, in the order of their first concert
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlbumCoverage {
+    pub album: String,
+    pub year: Option<i32>,
+    pub tracks: Vec<String>,
+    pub groups: Vec<CoverageGroup>,
+}
+
+/// Counts for every album how many of its songs were played, per tour or per year
+///
+/// # Arguments
+/// * `master` - The master data
+/// * `aliases` - The song aliases, to match played titles and tracks
+/// * `discography` - The albums and their tracklists
+/// * `by` - How to group the concerts
+/// * `filter` - Which performances count
+///
+/// # Returns
+/// * `Vec<AlbumCoverage>` - The coverage of every album, in discography order
+pub fn album_coverage(
+    master: &Setlists,
+    aliases: &SongAliases,
+    discography: &Discography,
+    by: GroupBy,
+    filter: &PerformanceFilter,
+) -> Vec<AlbumCoverage> {
+    // The songs played in each group, in the order of the groups' first concerts
+    let mut groups: Vec<(String, HashSet<String>)> = vec![];
+    for setlist in by_date(master) {
+        let name = by.group(setlist);
+        let index = match groups.iter().position(|(n, _)| *n == name) {
+            Some(index) => index,
+            None => {
+                groups.push((name, HashSet::new()));
+                groups.len() - 1
+            }
+        };
+        groups[index]
+            .1
+            .extend(played(setlist, filter).map(|song| aliases.canonical_name(&song.name).slug()));
+    }
+
+    discography
+        .albums
+        .iter()
+        .map(|album| {
+            let tracks: Vec<String> = album.tracks.iter().map(|t| aliases.canonical_name(&t.name)).collect();
+            let groups = groups
+                .iter()
+                .map(|(name, songs)| CoverageGroup {
+                    name: name.clone(),
+                    played: tracks.iter().filter(|t| songs.contains(&t.slug())).cloned().collect(),
+                })
+                .filter(|g| !g.played.is_empty())
+                .collect();
+            AlbumCoverage {
+                album: album.title.clone(),
+                year: album.year,
+                tracks,
+                groups,
+            }
+        })
+        .collect()
+}
+
+/// Renders album coverage as text: a line per album, followed by a line per group with the number and share of the
+/// album's songs played
+pub fn render_album_coverage(coverage: &[AlbumCoverage]) -> String {
+    let mut out = String::new();
+    for album in coverage {
+        match album.year {
+            Some(year) => out.push_str(&format!("{} ({}): {} songs\n", album.album, year, album.tracks.len())),
+            None => out.push_str(&format!("{}: {} songs\n", album.album, album.tracks.len())),
+        }
+        if album.groups.is_empty() {
+            out.push_str("  never played\n");
+        }
+        let width = album
+            .groups
+            .iter()
+            .map(|g| g.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        for group in album.groups.iter() {
+            out.push_str(&format!(
+                "  {:<width$}  {:>3}/{:<3} {:>3}%\n",
+                group.name,
+                group.played.len(),
+                album.tracks.len(),
+                group.played.len() * 100 / album.tracks.len().max(1)
+            ));
+        }
+    }
+    out
+}
+
+/// How often a song was played
+///
+/// # Fields
+///
+/// * `name`: The default title of the song
+/// * `performances`: The number of performances
+/// * `concerts`: The number of concerts it was played at
+/// * `average_duration`: The average duration in seconds of the performances with a duration
+#[derive(Clone, Debug, PartialEq)]
+pub struct SongCount {
+    pub name: String,
+    pub performances: usize,
+    pub concerts: usize,
+    pub average_duration: Option<u32>,
+}
+
+/// Counts the performances of every song
+///
+/// # Arguments
+/// * `master` - The master data
+/// * `aliases` - The song aliases, to count every song by its default title
+/// * `filter` - Which performances count
+///
+/// # Returns
+/// * `Vec<SongCount>` - The songs, most played first and then by title
+pub fn song_counts(master: &Setlists, aliases: &SongAliases, filter: &PerformanceFilter) -> Vec<SongCount> {
+    // Per song: title, performances, concerts, and the durations
+    let mut songs: Vec<(String, usize, HashSet<String>, Vec<u32>)> = vec![];
+    for setlist in master.data.iter() {
+        for song in played(setlist, filter) {
+            let name = aliases.canonical_name(&song.name);
+            let index = match songs.iter().position(|(n, ..)| n.slug() == name.slug()) {
+                Some(index) => index,
+                None => {
+                    songs.push((name, 0, HashSet::new(), vec![]));
+                    songs.len() - 1
+                }
+            };
+            let entry = &mut songs[index];
+            entry.1 += 1;
+            entry.2.insert(setlist.identifier());
+            entry.3.extend(song.duration_seconds());
+        }
+    }
+
+    let mut counts: Vec<SongCount> = songs
+        .into_iter()
+        .map(|(name, performances, concerts, durations)| SongCount {
+            name,
+            performances,
+            concerts: concerts.len(),
+            average_duration: (!durations.is_empty()).then(|| durations.iter().sum::<u32>() / durations.len() as u32),
+        })
+        .collect();
+    counts.sort_by(|a, b| b.performances.cmp(&a.performances).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+/// Renders song counts as text: a line per song with the number of performances and concerts and the average
+/// duration, if known
+pub fn render_song_counts(counts: &[SongCount]) -> String {
+    let mut out = String::new();
+    for count in counts {
+        let duration = count
+            .average_duration
+            .map(|d| format!("{}:{:02}", d / 60, d % 60))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "{:>5} {:>5} {:>6}  {}\n",
+            count.performances, count.concerts, duration, count.name
+        ));
+    }
+    out
+}
+
+/// The known recordings of the concerts
+///
+/// # Fields
+///
+/// * `concerts`: The number of concerts
+/// * `recorded`: The number of concerts with at least one known recording
+/// * `kinds`: Per kind of recording, the number of concerts with a recording of that kind, most common first
+/// * `kind`: The kind of recording the missing concerts lack, or `None` for any recording
+/// * `missing`: Summaries of the concerts without a known recording of that kind, in date order
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceStats {
+    pub concerts: usize,
+    pub recorded: usize,
+    pub kinds: Vec<(String, usize)>,
+    pub kind: Option<String>,
+    pub missing: Vec<String>,
+}
+
+/// Counts the concerts with known recordings, and finds the concerts without any
+///
+/// # Arguments
+/// * `master` - The master data
+/// * `kind` - Only count recordings of this kind as known, e.g. `soundboard`
+///
+/// # Returns
+/// * `SourceStats` - The counts, and the concerts without a known recording
+pub fn source_stats(master: &Setlists, kind: Option<&str>) -> SourceStats {
+    let mut kinds: Vec<(String, usize)> = vec![];
+    let mut recorded = 0;
+    let mut missing = vec![];
+    for setlist in by_date(master) {
+        let recordings = setlist.recordings();
+        if !recordings.is_empty() {
+            recorded += 1;
+        }
+        let mut seen: HashSet<String> = HashSet::new();
+        for recording in recordings.iter() {
+            let name = recording
+                .kind
+                .as_ref()
+                .map(|k| k.to_lowercase())
+                .unwrap_or_else(|| "unknown".to_string());
+            if seen.insert(name.clone()) {
+                match kinds.iter_mut().find(|(k, _)| *k == name) {
+                    Some((_, count)) => *count += 1,
+                    None => kinds.push((name, 1)),
+                }
+            }
+        }
+        let known = match kind {
+            Some(kind) => seen.contains(&kind.to_lowercase()),
+            None => !recordings.is_empty(),
+        };
+        if !known {
+            missing.push(render_summary(setlist));
+        }
+    }
+    kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    SourceStats {
+        concerts: master.data.len(),
+        recorded,
+        kinds,
+        kind: kind.map(|k| k.to_lowercase()),
+        missing,
+    }
+}
+
+/// Renders source statistics as text: the number of recorded concerts, a line per kind of recording, and the
+/// concerts without a known recording
+pub fn render_source_stats(stats: &SourceStats) -> String {
+    let mut out = format!("{} of {} concerts recorded\n", stats.recorded, stats.concerts);
+    for (kind, count) in stats.kinds.iter() {
+        out.push_str(&format!("{:>5}  {}\n", count, kind));
+    }
+    if !stats.missing.is_empty() {
+        let recording = match &stats.kind {
+            Some(kind) => format!("{} recording", kind),
+            None => "known recording".to_string(),
+        };
+        out.push_str(&format!(
+            "\n{} concerts without any {}:\n",
+            stats.missing.len(),
+            recording
+        ));
+    }
+    for concert in stats.missing.iter() {
+        out.push_str(&format!("  {}\n", concert));
+    }
+    out
+}
+
+/// How long ago a song was last played
+///
+/// # Fields
+///
+/// * `name`: The default title of the song
+/// * `last_played`: The date of the last concert the song was played at
+/// * `gap`: The number of concerts since then, not counting setlists known to be incomplete
+#[derive(Clone, Debug, PartialEq)]
+pub struct SongGap {
+    pub name: String,
+    pub last_played: String,
+    pub gap: usize,
+}
+
+/// Finds for every song how many concerts ago it was last played
+///
+/// Concerts whose setlist is incomplete (a partial set or an unknown song) are not counted, since the song may
+/// have been played there.
+///
+/// # Arguments
+/// * `master` - The master data
+/// * `aliases` - The song aliases, to find every song by its default title
+///
+/// # Returns
+/// * `Vec<SongGap>` - The songs, longest gap first and then by title
+pub fn song_gaps(master: &Setlists, aliases: &SongAliases) -> Vec<SongGap> {
+    // Per song slug: title, date and number of complete concerts up to and including its last performance
+    let mut last: Vec<(String, String, usize)> = vec![];
+    let mut complete = 0;
+    for setlist in by_date(master) {
+        if !setlist.is_partial() {
+            complete += 1;
+        }
+        for song in setlist.known_songs() {
+            let name = aliases.canonical_name(&song.name);
+            let entry = (name.clone(), setlist.event_date.clone(), complete);
+            match last.iter_mut().find(|(n, ..)| n.slug() == name.slug()) {
+                Some(known) => *known = entry,
+                None => last.push(entry),
+            }
+        }
+    }
+
+    let mut gaps: Vec<SongGap> = last
+        .into_iter()
+        .map(|(name, last_played, seen)| SongGap {
+            name,
+            last_played,
+            gap: complete - seen,
+        })
+        .collect();
+    gaps.sort_by(|a, b| b.gap.cmp(&a.gap).then_with(|| a.name.cmp(&b.name)));
+    gaps
+}
+
+/// Renders song gaps as text: a line per song with the gap and the date it was last played
+pub fn render_song_gaps(gaps: &[SongGap]) -> String {
+    let mut out = String::new();
+    for gap in gaps {
+        out.push_str(&format!("{:>5}  {}  {}\n", gap.gap, gap.last_played, gap.name));
+    }
+    out
+}
@@ -0,0 +1,176 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+
+mod rest;
+mod sqlite;
+
+pub use rest::{Pagination, RestStorage};
+pub use sqlite::SqliteStorage;
+
+use crate::mpdb::DbId;
+
+/// The entities (tables) of the MPDB schema
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Entity {
+    Countries,
+    Cities,
+    Venues,
+    Artists,
+    Songs,
+    Songtitles,
+    Concerts,
+    Sets,
+    Performances,
+    Albums,
+    AlbumTracks,
+    Musicians,
+    Personnel,
+    Sources,
+}
+
+impl Entity {
+    /// All entities, in the order they are populated
+    pub const ALL: [Entity; 14] = [
+        Entity::Countries,
+        Entity::Cities,
+        Entity::Venues,
+        Entity::Artists,
+        Entity::Songs,
+        Entity::Songtitles,
+        Entity::Concerts,
+        Entity::Sets,
+        Entity::Performances,
+        Entity::Albums,
+        Entity::AlbumTracks,
+        Entity::Musicians,
+        Entity::Personnel,
+        Entity::Sources,
+    ];
+
+    /// Returns the entity with a name, as returned by `Entity::name`
+    pub fn from_name(name: &str) -> Option<Entity> {
+        Entity::ALL.into_iter().find(|e| e.name() == name)
+    }
+
+    /// Returns the name of the entity, used both as API endpoint (`/api/<name>`) and as table name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Entity::Countries => "countries",
+            Entity::Cities => "cities",
+            Entity::Venues => "venues",
+            Entity::Artists => "artists",
+            Entity::Songs => "songs",
+            Entity::Songtitles => "songtitles",
+            Entity::Concerts => "concerts",
+            Entity::Sets => "sets",
+            Entity::Performances => "performances",
+            Entity::Albums => "albums",
+            Entity::AlbumTracks => "albumtracks",
+            Entity::Musicians => "musicians",
+            Entity::Personnel => "personnel",
+            Entity::Sources => "sources",
+        }
+    }
+}
+
+/// Errors that can occur when talking to a storage backend.
+///
+/// Rows that the backend refuses (e.g. an HTTP error status or a constraint violation) are not errors; see
+/// `Storage::create`.
+#[derive(Debug)]
+pub enum StorageError {
+    Http(reqwest::Error),
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Http(e) => write!(f, "HTTP error: {}", e),
+            StorageError::Sqlite(e) => write!(f, "SQLite error: {}", e),
+            StorageError::Json(e) => write!(f, "JSON error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<reqwest::Error> for StorageError {
+    fn from(e: reqwest::Error) -> Self {
+        StorageError::Http(e)
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Json(e)
+    }
+}
+
+/// A place to store the MPDB entities: the list, create, update and delete operations used when populating the
+/// database.
+///
+/// Rows are exchanged as JSON, in the same shape as the MPDB REST API uses.
+pub trait Storage {
+    /// Lists all rows of an entity
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to list
+    ///
+    /// # Returns
+    /// * `Result<Vec<T>, StorageError>` - All rows on success, or a storage error
+    fn list<T: DeserializeOwned + Send>(
+        &self,
+        entity: Entity,
+    ) -> impl Future<Output = Result<Vec<T>, StorageError>> + Send;
+
+    /// Creates a row
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to create a row for
+    /// * `data` - The row data; an `id` field, if any, is ignored
+    ///
+    /// # Returns
+    /// * `Result<Option<serde_json::Value>, StorageError>` - The created row, or `None` if the backend refused it,
+    ///   or a storage error
+    fn create<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        data: &T,
+    ) -> impl Future<Output = Result<Option<serde_json::Value>, StorageError>> + Send;
+
+    /// Updates a row
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to update a row of
+    /// * `id` - The ID of the row
+    /// * `data` - The new row data; an `id` field, if any, is ignored
+    ///
+    /// # Returns
+    /// * `Result<Option<serde_json::Value>, StorageError>` - The updated row, or `None` if the backend refused the
+    ///   update, or a storage error
+    fn update<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        id: DbId,
+        data: &T,
+    ) -> impl Future<Output = Result<Option<serde_json::Value>, StorageError>> + Send;
+
+    /// Deletes a row
+    ///
+    /// # Arguments
+    /// * `entity` - The entity to delete a row of
+    /// * `id` - The ID of the row
+    ///
+    /// # Returns
+    /// * `Result<bool, StorageError>` - Whether the row was deleted, `false` if the backend refused, or a storage error
+    fn delete(&self, entity: Entity, id: DbId) -> impl Future<Output = Result<bool, StorageError>> + Send;
+}
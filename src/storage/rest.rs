@@ -0,0 +1,266 @@
+use log::debug;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{Entity, Storage, StorageError};
+use crate::mpdb::DbId;
+
+/// How an endpoint returns the rows of a table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pagination {
+    /// The whole table as one JSON array
+    None,
+    /// Pages of at most `limit` rows, requested with `?page=<n>&limit=<limit>` (starting at page 1), until a page has
+    /// fewer than `limit` rows or the same rows as the page before
+    Page { limit: usize },
+    /// Pages of at most `limit` rows, requested with `?limit=<limit>&cursor=<cursor>`, where each page is an object
+    /// `{ "data": [...], "next_cursor": ... }` and the last page has no next cursor, or the cursor it was requested
+    /// with
+    Cursor { limit: usize },
+}
+
+impl Pagination {
+    /// Default number of rows per page
+    pub const DEFAULT_LIMIT: usize = 500;
+
+    /// Parses a pagination mode as written in the config file: `none`, `page` or `cursor`
+    ///
+    /// # Arguments
+    /// * `mode` - The mode
+    /// * `limit` - The number of rows per page, for `page` and `cursor`
+    ///
+    /// # Returns
+    /// * `Result<Pagination, String>` - The pagination, or an error message for unknown modes and a limit of 0
+    pub fn from_mode(mode: &str, limit: usize) -> Result<Pagination, String> {
+        if limit == 0 && mode != "none" {
+            return Err("The pagination limit must be at least 1".to_string());
+        }
+        match mode {
+            "none" => Ok(Pagination::None),
+            "page" => Ok(Pagination::Page { limit }),
+            "cursor" => Ok(Pagination::Cursor { limit }),
+            _ => Err(format!(
+                "Unknown pagination mode '{}' (expected none, page or cursor)",
+                mode
+            )),
+        }
+    }
+}
+
+/// The body of a list response: a plain array, or a page object with the rows under `data`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListBody<T> {
+    Rows(Vec<T>),
+    Page {
+        data: Vec<T>,
+        #[serde(default)]
+        next_cursor: Option<serde_json::Value>,
+    },
+}
+
+/// Credentials sent with every request
+#[derive(Clone, Debug)]
+enum Auth {
+    Basic { username: String, password: Option<String> },
+    Bearer(String),
+}
+
+/// Storage backed by the MPDB REST API at `<base_url>/api/<entity>`
+#[derive(Debug)]
+pub struct RestStorage {
+    base_url: String,
+    client: reqwest::Client,
+    auth: Option<Auth>,
+    pagination: Pagination,
+    endpoint_pagination: HashMap<Entity, Pagination>,
+}
+
+impl RestStorage {
+    /// Creates a storage for the API at `base_url`, fetching every table in one request
+    pub fn new(base_url: String) -> RestStorage {
+        RestStorage {
+            base_url,
+            client: reqwest::Client::new(),
+            auth: None,
+            pagination: Pagination::None,
+            endpoint_pagination: HashMap::new(),
+        }
+    }
+
+    /// Authenticates all requests with HTTP basic authentication
+    pub fn with_basic_auth(mut self, username: String, password: Option<String>) -> RestStorage {
+        self.auth = Some(Auth::Basic { username, password });
+        self
+    }
+
+    /// Authenticates all requests with a bearer token
+    pub fn with_token(mut self, token: String) -> RestStorage {
+        self.auth = Some(Auth::Bearer(token));
+        self
+    }
+
+    /// Sets the pagination used for all endpoints without their own setting
+    pub fn with_pagination(mut self, pagination: Pagination) -> RestStorage {
+        self.pagination = pagination;
+        self
+    }
+
+    /// Sets the pagination used for one endpoint
+    pub fn with_endpoint_pagination(mut self, entity: Entity, pagination: Pagination) -> RestStorage {
+        self.endpoint_pagination.insert(entity, pagination);
+        self
+    }
+
+    fn pagination(&self, entity: Entity) -> Pagination {
+        self.endpoint_pagination
+            .get(&entity)
+            .copied()
+            .unwrap_or(self.pagination)
+    }
+
+    /// Fetches one page of rows
+    ///
+    /// The body of the page is read whole before it is deserialized, so the memory used per request is bounded by the
+    /// pagination limit rather than by the size of the table.
+    async fn page<T: DeserializeOwned>(
+        &self,
+        entity: Entity,
+        query: &[(&str, String)],
+    ) -> Result<ListBody<T>, StorageError> {
+        debug!("Fetching {} {:?}", entity.name(), query);
+        let res = self
+            .request(reqwest::Method::GET, self.url(entity))
+            .query(query)
+            .send()
+            .await?;
+        let bytes = res.error_for_status()?.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Starts a request, with the credentials if any
+    fn request(&self, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.auth {
+            Some(Auth::Basic { username, password }) => request.basic_auth(username, password.as_ref()),
+            Some(Auth::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn url(&self, entity: Entity) -> String {
+        format!("{}/api/{}", self.base_url, entity.name())
+    }
+
+    /// Reads the body of a response, returning `None` for error statuses and `Null` for empty or non-JSON bodies
+    async fn body(res: reqwest::Response) -> Result<Option<serde_json::Value>, StorageError> {
+        let status = res.status();
+        let text = res.text().await?;
+        if !status.is_success() {
+            debug!("Request failed with status {}: {}", status, text);
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&text).unwrap_or_default()))
+    }
+}
+
+impl Storage for RestStorage {
+    async fn list<T: DeserializeOwned + Send>(&self, entity: Entity) -> Result<Vec<T>, StorageError> {
+        // Rows are kept as JSON until all pages are fetched, so a page can be compared with the previous one
+        let mut rows: Vec<serde_json::Value> = vec![];
+        match self.pagination(entity) {
+            Pagination::None => match self.page(entity, &[]).await? {
+                ListBody::Rows(data) | ListBody::Page { data, .. } => rows = data,
+            },
+            Pagination::Page { limit } => {
+                let mut previous: Option<Vec<serde_json::Value>> = None;
+                for page in 1.. {
+                    let query = [("page", page.to_string()), ("limit", limit.to_string())];
+                    let data = match self.page(entity, &query).await? {
+                        ListBody::Rows(data) | ListBody::Page { data, .. } => data,
+                    };
+                    // An empty page is past the end, and the same rows again mean the endpoint ignores the page
+                    if data.is_empty() || previous.as_ref() == Some(&data) {
+                        break;
+                    }
+                    // A page with more rows than asked for also means the endpoint ignores pagination
+                    let last = data.len() != limit;
+                    rows.extend(data.iter().cloned());
+                    if last {
+                        break;
+                    }
+                    previous = Some(data);
+                }
+            }
+            Pagination::Cursor { limit } => {
+                let mut cursor: Option<String> = None;
+                let mut previous: Option<Vec<serde_json::Value>> = None;
+                loop {
+                    let mut query = vec![("limit", limit.to_string())];
+                    if let Some(cursor) = cursor.clone() {
+                        query.push(("cursor", cursor));
+                    }
+                    match self.page(entity, &query).await? {
+                        ListBody::Rows(data) => {
+                            rows.extend(data);
+                            break;
+                        }
+                        ListBody::Page { data, next_cursor } => {
+                            if previous.as_ref() == Some(&data) {
+                                break;
+                            }
+                            rows.extend(data.iter().cloned());
+                            previous = Some(data);
+                            let next = match next_cursor {
+                                Some(serde_json::Value::String(c)) => Some(c),
+                                Some(serde_json::Value::Null) | None => None,
+                                Some(c) => Some(c.to_string()),
+                            };
+                            // Without a next cursor this was the last page, and the same cursor again would never end
+                            if next.is_none() || next == cursor {
+                                break;
+                            }
+                            cursor = next;
+                        }
+                    }
+                }
+            }
+        }
+        debug!("Fetched {} {}", rows.len(), entity.name());
+        Ok(rows
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<T>, _>>()?)
+    }
+
+    async fn create<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        data: &T,
+    ) -> Result<Option<serde_json::Value>, StorageError> {
+        let res = self
+            .request(reqwest::Method::POST, self.url(entity))
+            .json(data)
+            .send()
+            .await?;
+        Self::body(res).await
+    }
+
+    async fn update<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        id: DbId,
+        data: &T,
+    ) -> Result<Option<serde_json::Value>, StorageError> {
+        let url = format!("{}/{}", self.url(entity), id.0);
+        let res = self.request(reqwest::Method::PUT, url).json(data).send().await?;
+        Self::body(res).await
+    }
+
+    async fn delete(&self, entity: Entity, id: DbId) -> Result<bool, StorageError> {
+        let url = format!("{}/{}", self.url(entity), id.0);
+        let res = self.request(reqwest::Method::DELETE, url).send().await?;
+        Ok(Self::body(res).await?.is_some())
+    }
+}
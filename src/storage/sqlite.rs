@@ -0,0 +1,319 @@
+use log::debug;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, ErrorCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{Entity, Storage, StorageError};
+use crate::mpdb::DbId;
+
+/// The database schema, mirroring the tables behind the MPDB REST API
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS countries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    slug TEXT NOT NULL UNIQUE,
+    code TEXT
+);
+CREATE TABLE IF NOT EXISTS cities (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    country_id INTEGER NOT NULL REFERENCES countries(id)
+);
+CREATE TABLE IF NOT EXISTS venues (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    unique_name TEXT,
+    city_id INTEGER NOT NULL REFERENCES cities(id),
+    mbid TEXT
+);
+CREATE TABLE IF NOT EXISTS artists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    slug TEXT NOT NULL UNIQUE,
+    mbid TEXT,
+    sort_name TEXT,
+    country TEXT
+);
+CREATE TABLE IF NOT EXISTS songs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    work_mbid TEXT,
+    album TEXT,
+    composers TEXT,
+    release_year INTEGER,
+    instrumental BOOLEAN,
+    duration INTEGER
+);
+CREATE TABLE IF NOT EXISTS songtitles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    is_default BOOLEAN NOT NULL DEFAULT 0,
+    song_id INTEGER NOT NULL REFERENCES songs(id),
+    alias_for INTEGER REFERENCES songtitles(id)
+);
+CREATE TABLE IF NOT EXISTS concerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    date TEXT NOT NULL,
+    disambiguation TEXT,
+    sort_order INTEGER,
+    source TEXT,
+    slug TEXT NOT NULL UNIQUE,
+    venue_id INTEGER NOT NULL REFERENCES venues(id),
+    mbid TEXT
+);
+CREATE TABLE IF NOT EXISTS sets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    concert_id INTEGER NOT NULL REFERENCES concerts(id),
+    name TEXT,
+    unique_name TEXT NOT NULL UNIQUE,
+    sort_order INTEGER NOT NULL,
+    uncertain_order BOOLEAN NOT NULL DEFAULT 0,
+    partial BOOLEAN NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS performances (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    set_id INTEGER NOT NULL REFERENCES sets(id),
+    concert_id INTEGER NOT NULL REFERENCES concerts(id),
+    song_id INTEGER NOT NULL REFERENCES songs(id),
+    songtitle_id INTEGER NOT NULL REFERENCES songtitles(id),
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    segue BOOLEAN NOT NULL DEFAULT 0,
+    sort_order INTEGER NOT NULL,
+    recording_mbid TEXT,
+    duration INTEGER,
+    guests TEXT,
+    teases TEXT,
+    tags TEXT,
+    medley TEXT,
+    medley_position INTEGER,
+    medley_id INTEGER REFERENCES performances(id)
+);
+CREATE TABLE IF NOT EXISTS albums (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    artist_id INTEGER NOT NULL REFERENCES artists(id),
+    title TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    year INTEGER,
+    mbid TEXT
+);
+CREATE TABLE IF NOT EXISTS albumtracks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    album_id INTEGER NOT NULL REFERENCES albums(id),
+    song_id INTEGER NOT NULL REFERENCES songs(id),
+    position INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS musicians (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS personnel (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    concert_id INTEGER NOT NULL REFERENCES concerts(id),
+    musician_id INTEGER NOT NULL REFERENCES musicians(id),
+    instrument TEXT,
+    guest BOOLEAN NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    concert_id INTEGER NOT NULL REFERENCES concerts(id),
+    kind TEXT,
+    lineage TEXT,
+    taper TEXT,
+    archive_id TEXT,
+    complete BOOLEAN,
+    notes TEXT,
+    sort_order INTEGER NOT NULL
+);
+";
+
+/// Storage in a local SQLite database, with the same schema as the MPDB backend.
+///
+/// Rows that violate a constraint (e.g. a duplicate slug or an unknown foreign key) are refused, like the REST API
+/// refuses them.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+/// Converts a JSON value to an SQLite value
+fn to_sql(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+/// Converts an SQLite value to a JSON value, using the declared column type to recognize booleans
+fn from_sql(value: ValueRef, declared_type: &str) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) if declared_type == "BOOLEAN" => serde_json::Value::Bool(i != 0),
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(t) | ValueRef::Blob(t) => String::from_utf8_lossy(t).into(),
+    }
+}
+
+/// Returns `Ok(None)` for constraint violations, which the backend reports as refused rows
+fn refused_on_constraint<T>(result: rusqlite::Result<T>) -> Result<Option<T>, StorageError> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(rusqlite::Error::SqliteFailure(e, msg)) if e.code == ErrorCode::ConstraintViolation => {
+            debug!("Constraint violation: {}", msg.unwrap_or_default());
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) a database file and makes sure all tables exist
+    pub fn open(path: &Path) -> Result<SqliteStorage, StorageError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Creates a database in memory, e.g. for tests
+    pub fn in_memory() -> Result<SqliteStorage, StorageError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<SqliteStorage, StorageError> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(SqliteStorage { conn: Mutex::new(conn) })
+    }
+
+    /// Returns the names and declared types of the columns of a table
+    fn columns(conn: &Connection, entity: Entity) -> rusqlite::Result<Vec<(String, String)>> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", entity.name()))?;
+        let columns = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .collect();
+        columns
+    }
+
+    /// Selects rows of a table as JSON objects
+    fn select(conn: &Connection, entity: Entity, id: Option<i64>) -> Result<Vec<serde_json::Value>, StorageError> {
+        let columns = Self::columns(conn, entity)?;
+        let sql = match id {
+            Some(_) => format!("SELECT * FROM {} WHERE id = ?1", entity.name()),
+            None => format!("SELECT * FROM {} ORDER BY id", entity.name()),
+        };
+        let mut stmt = conn.prepare(&sql)?;
+        let params: Vec<i64> = id.into_iter().collect();
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                let mut object = serde_json::Map::new();
+                for (i, (name, declared_type)) in columns.iter().enumerate() {
+                    object.insert(name.clone(), from_sql(row.get_ref(i)?, declared_type));
+                }
+                Ok(serde_json::Value::Object(object))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Extracts the values for all known columns except `id` from a JSON object
+    fn values<T: Serialize>(conn: &Connection, entity: Entity, data: &T) -> Result<Vec<(String, Value)>, StorageError> {
+        let data = serde_json::to_value(data)?;
+        let columns = Self::columns(conn, entity)?;
+        Ok(columns
+            .into_iter()
+            .filter(|(name, _)| name != "id")
+            .filter_map(|(name, _)| data.get(&name).map(|v| (name, to_sql(v))))
+            .collect())
+    }
+}
+
+impl Storage for SqliteStorage {
+    async fn list<T: DeserializeOwned + Send>(&self, entity: Entity) -> Result<Vec<T>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let rows = Self::select(&conn, entity, None)?;
+        Ok(rows
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<T>, _>>()?)
+    }
+
+    async fn create<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        data: &T,
+    ) -> Result<Option<serde_json::Value>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let values = Self::values(&conn, entity, data)?;
+        let sql = if values.is_empty() {
+            format!("INSERT INTO {} DEFAULT VALUES", entity.name())
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                entity.name(),
+                values.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(", "),
+                (1..=values.len())
+                    .map(|i| format!("?{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        let params = rusqlite::params_from_iter(values.into_iter().map(|(_, v)| v));
+        match refused_on_constraint(conn.execute(&sql, params))? {
+            Some(_) => Ok(Self::select(&conn, entity, Some(conn.last_insert_rowid()))?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    async fn update<T: Serialize + Sync>(
+        &self,
+        entity: Entity,
+        id: DbId,
+        data: &T,
+    ) -> Result<Option<serde_json::Value>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let values = Self::values(&conn, entity, data)?;
+        // Nothing to set, e.g. when no field of the payload is a column: refused like an invalid row
+        if values.is_empty() {
+            return Ok(None);
+        }
+        let sql = format!(
+            "UPDATE {} SET {} WHERE id = ?{}",
+            entity.name(),
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, (n, _))| format!("{} = ?{}", n, i + 1))
+                .collect::<Vec<_>>()
+                .join(", "),
+            values.len() + 1
+        );
+        let params = rusqlite::params_from_iter(
+            values
+                .into_iter()
+                .map(|(_, v)| v)
+                .chain(std::iter::once(Value::Integer(id.0 as i64))),
+        );
+        match refused_on_constraint(conn.execute(&sql, params))? {
+            Some(0) | None => Ok(None),
+            Some(_) => Ok(Self::select(&conn, entity, Some(id.0 as i64))?.pop()),
+        }
+    }
+
+    async fn delete(&self, entity: Entity, id: DbId) -> Result<bool, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!("DELETE FROM {} WHERE id = ?1", entity.name());
+        Ok(matches!(refused_on_constraint(conn.execute(&sql, [id.0 as i64]))?, Some(n) if n > 0))
+    }
+}
@@ -0,0 +1,131 @@
+//! The sync state cache: what earlier populates learned about the database.
+//!
+//! The cache stores the remote ID of every country, city, venue, artist, song title and concert by its key (name or
+//! slug) and by MusicBrainz ID where known, and a content hash of every concert that was fully synced. Populating
+//! looks IDs up here instead of fetching whole tables, and skips concerts whose setlist hash did not change.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::mpdb::DbId;
+use crate::setlists::Setlist;
+
+/// The IDs of a song title and of the song it belongs to
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SongtitleIds {
+    pub id: DbId,
+    pub song_id: DbId,
+}
+
+/// Remote IDs and concert hashes from earlier populates
+///
+/// # Fields
+///
+/// * `target`: The database the state belongs to (the API base URL or the SQLite file)
+/// * `countries`: Country IDs by name
+/// * `cities`: City IDs by slug (`city-country`)
+/// * `venues`: Venue IDs by slug (`venue-city-country`)
+/// * `artists`: Artist IDs by name
+/// * `songtitles`: Song title and song IDs by the slug of the title
+/// * `concerts`: Concert IDs by slug
+/// * `concert_hashes`: Setlist content hashes of fully synced concerts, by concert slug
+/// * `artist_mbids`, `venue_mbids`, `concert_mbids`: Artist, venue and concert IDs by MusicBrainz ID
+/// * `song_mbids`: Song IDs by the MusicBrainz ID of their work
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct SyncState {
+    pub target: String,
+    pub countries: HashMap<String, DbId>,
+    pub cities: HashMap<String, DbId>,
+    pub venues: HashMap<String, DbId>,
+    pub artists: HashMap<String, DbId>,
+    pub songtitles: HashMap<String, SongtitleIds>,
+    pub concerts: HashMap<String, DbId>,
+    pub concert_hashes: HashMap<String, String>,
+    #[serde(default)]
+    pub artist_mbids: HashMap<String, DbId>,
+    #[serde(default)]
+    pub venue_mbids: HashMap<String, DbId>,
+    #[serde(default)]
+    pub song_mbids: HashMap<String, DbId>,
+    #[serde(default)]
+    pub concert_mbids: HashMap<String, DbId>,
+}
+
+impl SyncState {
+    /// Creates an empty state for a database
+    pub fn new(target: &str) -> Self {
+        SyncState {
+            target: target.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Loads the state from a cache file
+    ///
+    /// A missing file, or a file written for another database, gives an empty state.
+    ///
+    /// # Arguments
+    /// * `path` - The cache file
+    /// * `target` - The database that is about to be populated
+    ///
+    /// # Returns
+    /// * `Result<Self, Box<dyn std::error::Error>>` - The state on success, or an I/O or parse error
+    pub fn load(path: &Path, target: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Self::new(target));
+        }
+        let state: SyncState = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if state.target != target {
+            warn!(
+                "Sync state in {} belongs to {}, not {} - starting from scratch",
+                path.display(),
+                state.target,
+                target
+            );
+            return Ok(Self::new(target));
+        }
+        Ok(state)
+    }
+
+    /// Writes the state to a cache file
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns true if nothing is known about the database yet
+    pub fn is_empty(&self) -> bool {
+        self.countries.is_empty()
+            && self.cities.is_empty()
+            && self.venues.is_empty()
+            && self.artists.is_empty()
+            && self.songtitles.is_empty()
+            && self.concerts.is_empty()
+    }
+
+    /// Forgets a concert, e.g. after deleting it
+    pub fn forget_concert(&mut self, slug: &str) {
+        if let Some(id) = self.concerts.remove(slug) {
+            self.concert_mbids.retain(|_, concert_id| *concert_id != id);
+        }
+        self.concert_hashes.remove(slug);
+    }
+}
+
+/// Returns a hash of the content of a setlist, as a hex string
+///
+/// # Arguments
+/// * `setlist` - The setlist to hash
+///
+/// # Returns
+/// * `String` - The SHA-256 hash of the setlist serialized as JSON
+pub fn setlist_hash(setlist: &Setlist) -> String {
+    let json = serde_json::to_string(setlist).unwrap_or_default();
+    Sha256::digest(json.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{parse_duration, SongCatalogue};
+
+    #[test]
+    fn test_song_catalogue_can_be_parsed_from_yml() {
+        let yml = r#"
+song:
+- name: Hell, part 1-3
+  album: The Tower
+  composer:
+  - name: Bent Sæther
+  - name: Hans Magnus Ryan
+  releaseYear: 2017
+  duration: "11:30"
+- name: Watersign
+  originalArtist: Hawkwind
+  instrumental: false
+"#;
+        let catalogue = SongCatalogue::from_yml(yml).unwrap();
+        assert_eq!(catalogue.songs.len(), 2);
+        let hell = &catalogue.songs[0];
+        assert_eq!(hell.album.as_deref(), Some("The Tower"));
+        assert_eq!(hell.composer_names().as_deref(), Some("Bent Sæther, Hans Magnus Ryan"));
+        assert_eq!(hell.release_year, Some(2017));
+        assert_eq!(hell.duration_seconds(), Some(690));
+        let watersign = catalogue.get("watersign").unwrap();
+        assert_eq!(watersign.original_artist.as_deref(), Some("Hawkwind"));
+        assert_eq!(watersign.composer_names(), None);
+    }
+
+    #[test]
+    fn test_song_catalogue_can_be_parsed_from_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <songs>
+                    <song>
+                        <name>The Tower</name>
+                        <album>The Tower</album>
+                        <composer><name>Bent Sæther</name></composer>
+                        <releaseYear>2017</releaseYear>
+                        <instrumental>false</instrumental>
+                    </song>
+                    <song>
+                        <name>Watersign</name>
+                        <originalArtist>Hawkwind</originalArtist>
+                    </song>
+                </songs>"#;
+        let catalogue = SongCatalogue::from_xml(xml).unwrap();
+        assert_eq!(catalogue.songs.len(), 2);
+        assert_eq!(catalogue.songs[0].composers.len(), 1);
+        assert_eq!(catalogue.songs[0].instrumental, Some(false));
+        assert!(catalogue.songs[1].composers.is_empty());
+        assert_eq!(catalogue.songs[1].original_artist.as_deref(), Some("Hawkwind"));
+    }
+
+    #[test]
+    fn test_durations_are_parsed() {
+        assert_eq!(parse_duration("7:45"), Some(465));
+        assert_eq!(parse_duration("1:02:03"), Some(3723));
+        assert_eq!(parse_duration("7:60"), None);
+        assert_eq!(parse_duration("7m45"), None);
+        assert_eq!(parse_duration("45"), None);
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{export_csv, Setlists, Song, SongAliases};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  disambiguation: late show
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  tour:
+    name: Kingdom of Oblivion Tour
+  sets:
+    set:
+    - song:
+      - name: NOX
+        segue: true
+      - name: Watersign
+        cover:
+          name: Hawkwind
+        notes: Extended, with "jam"
+    - song:
+      - name: Ship of Fools
+    - encore: "1"
+      song:
+      - name: Vortex Surfer
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    #[test]
+    fn test_csv_has_one_row_per_performance_with_canonical_title() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        let csv = export_csv(&[&master.data[0]], &aliases).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].contains(",1,,,1,N.O.X.,NOX,true,,"));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        let csv = export_csv(&[&master.data[0]], &aliases).unwrap();
+
+        let imported = Setlists::from_csv(&csv).unwrap();
+        assert_eq!(imported.data.len(), 1);
+        let setlist = &imported.data[0];
+        assert_eq!(setlist.disambiguation, Some("late show".to_string()));
+        assert_eq!(setlist.tour.as_ref().unwrap().name, "Kingdom of Oblivion Tour");
+        assert_eq!(setlist.sets.set.len(), 3);
+        assert_eq!(setlist.sets.set[2].encore, Some("1".to_string()));
+
+        let songs = setlist.sets.set[0].songs.as_ref().unwrap();
+        assert_eq!(songs[0].name, "NOX");
+        assert_eq!(songs[0].segue, Some(true));
+        assert_eq!(songs[1].original_artist.as_ref().unwrap().name, "Hawkwind");
+        assert_eq!(songs[1].notes, Some("Extended, with \"jam\"".to_string()));
+
+        // Exporting the imported setlists gives the same sheet
+        assert_eq!(export_csv(&[setlist], &aliases).unwrap(), csv);
+    }
+
+    #[test]
+    fn test_csv_round_trip_keeps_empty_sets_and_concerts() {
+        let mut master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        master.data[0].sets.set[1].songs = None;
+        let mut empty = master.data[0].clone();
+        empty.disambiguation = None;
+        empty.sets.set.clear();
+        master.data.push(empty);
+
+        let setlists: Vec<_> = master.data.iter().collect();
+        let csv = export_csv(&setlists, &aliases).unwrap();
+        assert_eq!(csv.lines().count(), 6);
+        let imported = Setlists::from_csv(&csv).unwrap();
+        assert_eq!(imported.data.len(), 2);
+        assert_eq!(imported.data[0].sets.set.len(), 3);
+        assert_eq!(imported.data[0].sets.set[1].songs, None);
+        assert!(imported.data[1].sets.set.is_empty());
+        let setlists: Vec<_> = imported.data.iter().collect();
+        assert_eq!(export_csv(&setlists, &aliases).unwrap(), csv);
+    }
+
+    #[test]
+    fn test_csv_round_trip_with_a_medley() {
+        let mut master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        let songs = master.data[0].sets.set[1].songs.as_mut().unwrap();
+        songs.insert(
+            0,
+            Song {
+                name: "Kingdom Medley".to_string(),
+                segue: Some(true),
+                medley: vec![
+                    Song {
+                        name: "NOX".to_string(),
+                        segue: Some(true),
+                        ..Default::default()
+                    },
+                    Song {
+                        name: "Watersign".to_string(),
+                        segue: None,
+                        notes: Some("Short".to_string()),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        // One row per part, at the position of the medley
+        let csv = export_csv(&[&master.data[0]], &aliases).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines[3].ends_with(",1,N.O.X.,NOX,true,,,Kingdom Medley,1"));
+        assert!(lines[4].ends_with(",1,Watersign,Watersign,true,,Short,Kingdom Medley,2"));
+        assert!(lines[5].ends_with(",2,Ship of Fools,Ship of Fools,false,,,,"));
+
+        let imported = Setlists::from_csv(&csv).unwrap();
+        assert_eq!(imported.data[0].sets.set[1], master.data[0].sets.set[1]);
+        assert_eq!(export_csv(&[&imported.data[0]], &aliases).unwrap(), csv);
+    }
+}
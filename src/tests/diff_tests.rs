@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::git;
+    use crate::setlists::{FileFormat, SetlistChanges, Setlists};
+    use std::process::Command;
+
+    const OLD_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+- status: confirmed
+  eventDate: 01-03-1995
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: Nerve Tattoo
+"#;
+
+    const NEW_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+      - name: Ship of Fools
+- status: confirmed
+  eventDate: 02-03-1995
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: Nerve Tattoo
+"#;
+
+    #[test]
+    fn test_changes_between_versions() {
+        let old = Setlists::from_yml(OLD_YML).unwrap();
+        let new = Setlists::from_yml(NEW_YML).unwrap();
+        let changes = SetlistChanges::between(&old, &new);
+
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.added[0].identifier(), "motorpsycho-1995-03-02");
+        assert_eq!(changes.modified.len(), 1);
+        assert_eq!(changes.modified[0].identifier(), "motorpsycho-2021-05-16");
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.removed[0].identifier(), "motorpsycho-1995-03-01");
+        assert_eq!(changes.changed().data.len(), 2);
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let old = Setlists::from_yml(OLD_YML).unwrap();
+        assert!(SetlistChanges::between(&old, &old.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_read_master_at_revision() {
+        let dir = std::env::temp_dir().join(format!("mpdbtool-git-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sorted")).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        git(&["init", "-q"]);
+        std::fs::write(dir.join("master.yml"), OLD_YML).unwrap();
+        std::fs::write(dir.join("sorted").join("1995.yml"), OLD_YML).unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "old"]);
+        std::fs::write(dir.join("master.yml"), NEW_YML).unwrap();
+        std::fs::write(dir.join("sorted").join("1995.yml"), NEW_YML).unwrap();
+
+        let old = git::read_master_at(&dir.join("master.yml"), "HEAD", FileFormat::Yml).unwrap();
+        assert_eq!(old, Setlists::from_yml(OLD_YML).unwrap());
+        let old = git::read_dir_at(&dir.join("sorted"), "HEAD", FileFormat::Yml).unwrap();
+        assert_eq!(old.data.len(), 2);
+        assert_eq!(old.data[1].event_date, "01-03-1995");
+        assert!(git::read_master_at(&dir.join("missing.yml"), "HEAD", FileFormat::Yml).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::Discography;
+
+    #[test]
+    fn test_discography_can_be_parsed_from_yml() {
+        let yml = r#"
+album:
+- title: The Tower
+  year: 2017
+  track:
+  - name: The Tower
+  - name: Bartok of the Universe
+- title: Demon Box
+"#;
+        let discography = Discography::from_yml(yml).unwrap();
+        assert_eq!(discography.albums.len(), 2);
+        assert_eq!(discography.albums[0].tracks.len(), 2);
+        assert_eq!(discography.albums[0].tracks[1].name, "Bartok of the Universe");
+        assert_eq!(discography.albums[0].slug(), "the-tower-2017");
+        assert!(discography.albums[1].tracks.is_empty());
+        assert_eq!(discography.albums[1].slug(), "demon-box");
+    }
+
+    #[test]
+    fn test_discography_can_be_parsed_from_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <discography>
+                    <album>
+                        <title>Demon Box</title>
+                        <year>1993</year>
+                        <mbid>00000000-0000-4000-8000-000000000001</mbid>
+                        <track><name>Nothing to Say</name></track>
+                        <track><name>Feedtime</name></track>
+                    </album>
+                </discography>"#;
+        let discography = Discography::from_xml(xml).unwrap();
+        assert_eq!(discography.albums.len(), 1);
+        assert_eq!(discography.albums[0].year, Some(1993));
+        assert_eq!(discography.albums[0].tracks[0].name, "Nothing to Say");
+    }
+}
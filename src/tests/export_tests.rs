@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{export_setlist, export_setlists, Setlists, TextFormat};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: Ship of Fools
+        segue: true
+      - name: Watersign
+        cover:
+          name: Hawkwind
+        notes: With extended intro
+    - encore: "1"
+      song:
+      - name: Vortex Surfer
+        notes: Acoustic
+"#;
+
+    #[test]
+    fn test_export_text() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let output = export_setlist(&master.data[0], TextFormat::Text);
+        assert_eq!(
+            output,
+            "Motorpsycho, 16-05-2021\n\
+             Verkstedhallen, Trondheim, Norway\n\
+             \n\
+             Set 1: Ship of Fools > Watersign (Hawkwind) [1]\n\
+             Encore 1: Vortex Surfer [2]\n\
+             \n\
+             [1] With extended intro\n\
+             [2] Acoustic\n"
+        );
+    }
+
+    #[test]
+    fn test_export_markdown_numbers_footnotes_across_setlists() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let setlists = vec![&master.data[0], &master.data[0]];
+        let output = export_setlists(&setlists, TextFormat::Markdown);
+        assert!(output.contains("**Set 1:** Ship of Fools > Watersign (Hawkwind)[^1]  \n"));
+        assert!(output.contains("[^2]: Acoustic\n"));
+        assert!(output.contains("[^3]: With extended intro\n"));
+        assert!(output.contains("[^4]: Acoustic\n"));
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{Lineups, Player, Setlists};
+
+    const LINEUPS_YML: &str = r#"
+lineup:
+- artist: Motorpsycho
+  from: 01-01-1991
+  to: 31-12-2004
+  member:
+  - name: Bent Sæther
+    instrument: bass
+  - name: Hans Magnus Ryan
+    instrument: guitar
+  - name: Håkon Gebhardt
+    instrument: drums
+- artist: Motorpsycho
+  from: 2017-01-01
+  member:
+  - name: Bent Sæther
+    instrument: bass
+  - name: Hans Magnus Ryan
+    instrument: guitar
+  - name: Tomas Järmyr
+    instrument: drums
+"#;
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 01-03-2017
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  guest:
+  - name: Ståle Storløkken
+    instrument: keyboards
+  sets:
+    set:
+    - song:
+      - name: Bartok of the Universe
+        guest:
+        - name: Ståle Storløkken
+          instrument: keyboards
+        - name: Lars Horntveth
+          instrument: saxophone
+- status: confirmed
+  eventDate: 01-03-2010
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  member:
+  - name: Bent Sæther
+  - name: Hans Magnus Ryan
+  - name: Kenneth Kapstad
+  sets:
+    set:
+    - song:
+      - name: Hell, Part 1-3
+- status: confirmed
+  eventDate: 01-03-1995
+  artist:
+    name: The Tussler Society
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: Pills, Powders & Passion Plays
+"#;
+
+    fn player(name: &str, instrument: Option<&str>, guest: bool) -> Player {
+        Player {
+            name: name.to_string(),
+            instrument: instrument.map(|i| i.to_string()),
+            guest,
+        }
+    }
+
+    #[test]
+    fn test_lineups_can_be_parsed_from_yml() {
+        let lineups = Lineups::from_yml(LINEUPS_YML).unwrap();
+        assert_eq!(lineups.lineups.len(), 2);
+        assert_eq!(lineups.lineups[0].members.len(), 3);
+        assert_eq!(
+            lineups.lineups[0].to_date(),
+            chrono::NaiveDate::from_ymd_opt(2004, 12, 31)
+        );
+        assert_eq!(
+            lineups.lineups[1].from_date(),
+            chrono::NaiveDate::from_ymd_opt(2017, 1, 1)
+        );
+        assert_eq!(lineups.lineups[1].to, None);
+    }
+
+    #[test]
+    fn test_lineups_can_be_parsed_from_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <lineups>
+                    <lineup>
+                        <artist>Motorpsycho</artist>
+                        <from>01-01-1991</from>
+                        <member><name>Bent Sæther</name><instrument>bass</instrument></member>
+                        <member><name>Hans Magnus Ryan</name></member>
+                    </lineup>
+                </lineups>"#;
+        let lineups = Lineups::from_xml(xml).unwrap();
+        assert_eq!(lineups.lineups[0].members[1].name, "Hans Magnus Ryan");
+        assert_eq!(lineups.lineups[0].members[1].instrument, None);
+    }
+
+    #[test]
+    fn test_personnel_combines_lineup_and_guests() {
+        let lineups = Lineups::from_yml(LINEUPS_YML).unwrap();
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+
+        // A guest of both the concert and a song is listed once
+        assert_eq!(
+            lineups.personnel(&master.data[0]),
+            vec![
+                player("Bent Sæther", Some("bass"), false),
+                player("Hans Magnus Ryan", Some("guitar"), false),
+                player("Tomas Järmyr", Some("drums"), false),
+                player("Ståle Storløkken", Some("keyboards"), true),
+                player("Lars Horntveth", Some("saxophone"), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_personnel_uses_the_setlist_members_and_the_artist() {
+        let lineups = Lineups::from_yml(LINEUPS_YML).unwrap();
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+
+        // 2010 is not covered by a lineup, but the concert lists its own members
+        assert!(lineups.lineup_for(&master.data[1]).is_none());
+        let names: Vec<String> = lineups.personnel(&master.data[1]).into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Bent Sæther", "Hans Magnus Ryan", "Kenneth Kapstad"]);
+
+        // Lineups only apply to concerts of their artist
+        assert!(lineups.personnel(&master.data[2]).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! An in-memory mock of the MPDB REST API, for exercising the populate flow end-to-end in tests.
+//!
+//! The server keeps every table as a list of JSON rows, assigns IDs on create, refuses rows with a duplicate `slug`
+//! (or `unique_name`), deletes rows by ID, pages listings on request, and can be told to fail requests for specific
+//! rows or to ignore the pagination parameters.
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The endpoints served under `/api/`
+const ENTITIES: &[&str] = &[
+    "countries",
+    "cities",
+    "venues",
+    "artists",
+    "songs",
+    "songtitles",
+    "concerts",
+    "sets",
+    "performances",
+    "albums",
+    "albumtracks",
+    "musicians",
+    "personnel",
+    "sources",
+];
+
+/// A request failure to inject: requests to `entity` whose data has `field` equal to `value` get a 500 response
+struct Failure {
+    entity: String,
+    field: String,
+    value: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    tables: HashMap<String, Vec<Value>>,
+    failures: Vec<Failure>,
+    requests: usize,
+    authorization: Option<String>,
+    ignore_pagination: bool,
+}
+
+type SharedState = Arc<Mutex<MockState>>;
+
+pub struct MockServer {
+    pub base_url: String,
+    state: SharedState,
+}
+
+impl MockState {
+    fn should_fail(&self, entity: &str, data: &Value) -> bool {
+        self.failures
+            .iter()
+            .any(|f| f.entity == entity && data.get(&f.field) == Some(&f.value))
+    }
+
+    fn is_duplicate(&self, entity: &str, data: &Value, except_id: Option<&Value>) -> bool {
+        let rows = self.tables.get(entity).map(|t| t.as_slice()).unwrap_or_default();
+        ["slug", "unique_name"].iter().any(|key| {
+            data.get(key).is_some_and(|v| {
+                rows.iter()
+                    .any(|row| row.get(key) == Some(v) && row.get("id") != except_id)
+            })
+        })
+    }
+}
+
+/// Lists rows: the whole table without parameters, a plain array page with `page` and `limit`, or a cursor page
+/// object (`{ "data": [...], "next_cursor": ... }`) with only `limit` and an optional `cursor`
+async fn list(
+    State(state): State<SharedState>,
+    Path(entity): Path<String>,
+    Query(params): Query<HashMap<String, usize>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests += 1;
+    state.authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    if !ENTITIES.contains(&entity.as_str()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let rows = state.tables.get(&entity).cloned().unwrap_or_default();
+    let Some(&limit) = params.get("limit") else {
+        return Ok(Json(Value::Array(rows)));
+    };
+
+    // A broken endpoint: the first page for every page number, and the cursor that was sent as the next cursor
+    if state.ignore_pagination {
+        if params.contains_key("page") {
+            return Ok(Json(Value::Array(rows.into_iter().take(limit).collect())));
+        }
+        let cursor = params.get("cursor").copied();
+        let page: Vec<Value> = rows
+            .iter()
+            .skip(cursor.unwrap_or_default())
+            .take(limit)
+            .cloned()
+            .collect();
+        let next_cursor = cursor.unwrap_or(limit).to_string();
+        return Ok(Json(serde_json::json!({ "data": page, "next_cursor": next_cursor })));
+    }
+
+    let start = match params.get("page") {
+        Some(page) => page.saturating_sub(1) * limit,
+        None => params.get("cursor").copied().unwrap_or_default(),
+    };
+    let page: Vec<Value> = rows.iter().skip(start).take(limit).cloned().collect();
+    if params.contains_key("page") {
+        return Ok(Json(Value::Array(page)));
+    }
+    let next_cursor = (start + limit < rows.len()).then(|| (start + limit).to_string());
+    Ok(Json(serde_json::json!({ "data": page, "next_cursor": next_cursor })))
+}
+
+async fn create(
+    State(state): State<SharedState>,
+    Path(entity): Path<String>,
+    Json(mut data): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests += 1;
+    if !ENTITIES.contains(&entity.as_str()) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if state.should_fail(&entity, &data) {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if state.is_duplicate(&entity, &data, None) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let table = state.tables.entry(entity).or_default();
+    let id = table
+        .iter()
+        .filter_map(|row| row["id"].as_i64())
+        .max()
+        .unwrap_or_default()
+        + 1;
+    data["id"] = id.into();
+    table.push(data.clone());
+    Ok(Json(data))
+}
+
+async fn update(
+    State(state): State<SharedState>,
+    Path((entity, id)): Path<(String, i64)>,
+    Json(mut data): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests += 1;
+    if state.should_fail(&entity, &data) {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    let id = Value::from(id);
+    if state.is_duplicate(&entity, &data, Some(&id)) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let table = state.tables.entry(entity).or_default();
+    let row = table
+        .iter_mut()
+        .find(|row| row.get("id") == Some(&id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    data["id"] = id;
+    *row = data.clone();
+    Ok(Json(data))
+}
+
+async fn delete(
+    State(state): State<SharedState>,
+    Path((entity, id)): Path<(String, i64)>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut state = state.lock().unwrap();
+    state.requests += 1;
+    let id = Value::from(id);
+    let table = state.tables.entry(entity).or_default();
+    let index = table
+        .iter()
+        .position(|row| row.get("id") == Some(&id))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(table.remove(index)))
+}
+
+impl MockServer {
+    /// Starts a mock server on a random localhost port
+    pub async fn start() -> MockServer {
+        let state = SharedState::default();
+        let app = Router::new()
+            .route("/api/{entity}", get(list).post(create))
+            .route("/api/{entity}/{id}", put(update).delete(delete))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        MockServer { base_url, state }
+    }
+
+    /// Makes all create and update requests to `entity` fail if their `field` equals `value`
+    pub fn fail_when(&self, entity: &str, field: &str, value: impl Into<Value>) {
+        self.state.lock().unwrap().failures.push(Failure {
+            entity: entity.to_string(),
+            field: field.to_string(),
+            value: value.into(),
+        });
+    }
+
+    /// Makes paged listings always return their first page, and cursor listings return the cursor they were sent as
+    /// the next cursor
+    pub fn ignore_pagination(&self) {
+        self.state.lock().unwrap().ignore_pagination = true;
+    }
+
+    /// Returns all rows of an entity
+    pub fn rows(&self, entity: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .tables
+            .get(entity)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the `Authorization` header of the last list request
+    pub fn authorization(&self) -> Option<String> {
+        self.state.lock().unwrap().authorization.clone()
+    }
+
+    /// Returns the number of requests handled so far
+    pub fn requests(&self) -> usize {
+        self.state.lock().unwrap().requests
+    }
+}
@@ -1 +1,22 @@
+mod catalogue_tests;
+mod csv_tests;
+mod diff_tests;
+mod discography_tests;
+mod export_tests;
+mod lineups_tests;
+#[cfg(test)]
+mod mock_server;
+mod musicbrainz_tests;
+mod populate_tests;
+mod query_tests;
+mod render_tests;
+mod report_tests;
+mod rest_tests;
+mod settings_tests;
+mod site_tests;
+mod sources_tests;
+mod sqlite_tests;
+mod stats_tests;
+mod sync_state_tests;
+mod validate_tests;
 mod xml_tests;
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+    use crate::musicbrainz::{enrich, read_artists, read_works, MbArtist, MbWork};
+    use crate::{Setlists, SongAliases};
+
+    const MASTER: &str = r#"
+setlist:
+- status: complete
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: The Tower
+      - name: Watersign
+        cover:
+          name: Hawkwind
+      - name: Hey Joe
+        cover:
+          name: Billy Roberts
+      - name: Wild Thing
+        cover:
+          name: The Troggs
+"#;
+
+    const ARTISTS: &str = r#"{"id":"a0000000-0000-0000-0000-000000000001","name":"Motorpsycho","sort-name":"Motorpsycho","country":"NO","aliases":[]}
+{"id":"a0000000-0000-0000-0000-000000000002","name":"Hawkwind","sort-name":"Hawkwind","country":"GB","aliases":[{"name":"Hawkwind Zoo"}]}
+
+{"id":"a0000000-0000-0000-0000-000000000003","name":"The Troggs","sort-name":"Troggs, The","country":"GB","disambiguation":"UK band"}
+{"id":"a0000000-0000-0000-0000-000000000004","name":"Troggs","sort-name":"Troggs","aliases":[{"name":"The Troggs"}],"disambiguation":"tribute"}
+{"id":"a0000000-0000-0000-0000-000000000005","name":"Somebody Else","sort-name":"Else, Somebody"}
+"#;
+
+    const WORKS: &str = r#"{"id":"w0000000-0000-0000-0000-000000000001","title":"Master of the Universe","aliases":[{"name":"Watersign"}],"relations":[{"type":"composer","artist":{"id":"a0000000-0000-0000-0000-000000000002","name":"Hawkwind"}}]}
+{"id":"w0000000-0000-0000-0000-000000000002","title":"Watersign","relations":[{"type":"composer","artist":{"id":"a0000000-0000-0000-0000-000000000005","name":"Somebody Else"}}]}
+{"id":"w0000000-0000-0000-0000-000000000003","title":"Unplayed","relations":[]}
+"#;
+
+    fn dump(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("mpdbtool-mb-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn load() -> (Setlists, Vec<MbArtist>, Vec<MbWork>) {
+        let master = Setlists::from_yml(MASTER).unwrap();
+        let artists_path = dump("artists", ARTISTS);
+        let works_path = dump("works", WORKS);
+        let artists = read_artists(&artists_path, &master).unwrap();
+        let works = read_works(&works_path, &master).unwrap();
+        std::fs::remove_file(artists_path).unwrap();
+        std::fs::remove_file(works_path).unwrap();
+        (master, artists, works)
+    }
+
+    #[test]
+    fn test_only_matching_entities_are_read() {
+        let (_, artists, works) = load();
+        let names: Vec<&str> = artists.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Motorpsycho", "Hawkwind", "The Troggs", "Troggs"]);
+        assert_eq!(artists[1].aliases[0].name, "Hawkwind Zoo");
+        assert_eq!(works.len(), 2);
+    }
+
+    #[test]
+    fn test_unparseable_dump_line_is_reported() {
+        let master = Setlists::from_yml(MASTER).unwrap();
+        let path = dump("broken", "{\"id\":\"x\",\"name\":\"Hawkwind\"}\nnot json\n");
+        let result = read_artists(&path, &master);
+        std::fs::remove_file(path).unwrap();
+        assert!(result.unwrap_err().to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_artists_and_covers_are_enriched() {
+        let (mut master, artists, works) = load();
+        let mut aliases = SongAliases::default();
+        let enrichment = enrich(&mut master, &mut aliases, &artists, &works);
+
+        let setlist = &master.data[0];
+        assert_eq!(
+            setlist.artist.mbid.as_deref(),
+            Some("a0000000-0000-0000-0000-000000000001")
+        );
+        assert_eq!(setlist.artist.country.as_deref(), Some("NO"));
+
+        let songs: Vec<_> = setlist.songs().collect();
+        let hawkwind = songs[1].original_artist.as_ref().unwrap();
+        assert_eq!(hawkwind.sort_name.as_deref(), Some("Hawkwind"));
+        assert_eq!(hawkwind.country.as_deref(), Some("GB"));
+        assert_eq!(
+            songs[1].work_mbid.as_deref(),
+            Some("w0000000-0000-0000-0000-000000000001")
+        );
+
+        let song = aliases
+            .songs
+            .iter()
+            .find(|s| s.name == "Master of the Universe")
+            .unwrap();
+        assert_eq!(song.artist.as_deref(), Some("Hawkwind"));
+        assert_eq!(song.work_mbid.as_deref(), Some("w0000000-0000-0000-0000-000000000001"));
+        assert_eq!(song.aliases[0].name, "Watersign");
+
+        assert_eq!(enrichment.artists, 2);
+        assert_eq!(enrichment.covers, 1);
+        assert_eq!(enrichment.unmatched, vec!["artist Billy Roberts".to_string()]);
+    }
+
+    #[test]
+    fn test_ambiguous_artists_are_reported_and_left_alone() {
+        let (mut master, artists, works) = load();
+        let mut aliases = SongAliases::default();
+        let enrichment = enrich(&mut master, &mut aliases, &artists, &works);
+
+        assert_eq!(enrichment.ambiguous.len(), 1);
+        let ambiguity = &enrichment.ambiguous[0];
+        assert_eq!(ambiguity.kind, "artist");
+        assert_eq!(ambiguity.name, "The Troggs");
+        assert_eq!(ambiguity.candidates.len(), 2);
+        assert!(ambiguity
+            .to_string()
+            .contains("Troggs (a0000000-0000-0000-0000-000000000004, tribute)"));
+
+        let troggs = master.data[0].songs().nth(3).unwrap().original_artist.as_ref().unwrap();
+        assert_eq!(troggs.mbid, None);
+        assert_eq!(troggs.sort_name, None);
+    }
+
+    #[test]
+    fn test_existing_mbid_picks_the_artist() {
+        let (mut master, artists, works) = load();
+        let set = &mut master.data[0].sets.set[0];
+        set.songs.as_mut().unwrap()[3].original_artist.as_mut().unwrap().mbid =
+            Some("a0000000-0000-0000-0000-000000000003".to_string());
+        let mut aliases = SongAliases::default();
+        let enrichment = enrich(&mut master, &mut aliases, &artists, &works);
+
+        assert!(enrichment.ambiguous.is_empty());
+        let troggs = master.data[0].songs().nth(3).unwrap().original_artist.as_ref().unwrap();
+        assert_eq!(troggs.sort_name.as_deref(), Some("Troggs, The"));
+    }
+
+    #[test]
+    fn test_existing_alias_entry_gets_the_work() {
+        let (mut master, artists, works) = load();
+        let mut aliases = SongAliases::from_yml("song:\n- name: Watersign\n  alias:\n  - name: Water Sign\n").unwrap();
+        enrich(&mut master, &mut aliases, &artists, &works);
+
+        assert_eq!(aliases.songs.len(), 1);
+        assert_eq!(aliases.songs[0].name, "Watersign");
+        assert_eq!(aliases.songs[0].artist.as_deref(), Some("Hawkwind"));
+        assert_eq!(
+            aliases.songs[0].work_mbid.as_deref(),
+            Some("w0000000-0000-0000-0000-000000000001")
+        );
+        // The canonical title is kept, and the work title becomes an alias
+        let names: Vec<&str> = aliases.songs[0].aliases.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Water Sign", "Master of the Universe"]);
+    }
+}
@@ -0,0 +1,687 @@
+#[cfg(test)]
+mod tests {
+    use crate::mpdb::Mpdb;
+    use crate::setlists::SetlistChanges;
+    use crate::setlists::{Discography, Guest, Lineups, Setlists, Song, SongAliases, SongCatalogue};
+    use crate::storage::{Entity, Pagination, RestStorage, Storage};
+    use crate::tests::mock_server::MockServer;
+    use crate::{populate_changes, populate_db};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+        code: NO
+  sets:
+    set:
+    - song:
+      - name: NOX
+        segue: true
+      - name: Watersign
+        cover:
+          name: Hawkwind
+    - encore: "1"
+      song:
+      - name: Ship of Fools
+- status: confirmed
+  eventDate: 01-03-1995
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+        code: NO
+  sets:
+    set:
+    - song:
+      - name: Nerve Tattoo
+      - name: Ship of Fools
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    fn mpdb(server: &MockServer) -> Mpdb<RestStorage> {
+        let mut mpdb = Mpdb::new(RestStorage::new(server.base_url.clone()));
+        mpdb.master = Setlists::from_yml(MASTER_YML).unwrap();
+        mpdb.aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        mpdb
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_against_mock_server() {
+        let server = MockServer::start().await;
+        populate_db(&mut mpdb(&server)).await.unwrap();
+
+        assert_eq!(server.rows("countries").len(), 1);
+        assert_eq!(server.rows("countries")[0]["code"], "NO");
+        assert_eq!(server.rows("cities").len(), 2);
+        assert_eq!(server.rows("venues").len(), 2);
+        assert_eq!(server.rows("artists").len(), 2);
+        // N.O.X. (with alias NOX), Watersign, Ship of Fools and Nerve Tattoo
+        assert_eq!(server.rows("songs").len(), 4);
+        assert_eq!(server.rows("songtitles").len(), 5);
+
+        let concerts = server.rows("concerts");
+        assert_eq!(concerts.len(), 2);
+        assert_eq!(concerts[0]["slug"], "motorpsycho-2021-05-16");
+        assert_eq!(concerts[1]["date"], "1995-03-01");
+
+        let sets = server.rows("sets");
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[1]["unique_name"], "motorpsycho-2021-05-16-encore-1");
+
+        let performances = server.rows("performances");
+        assert_eq!(performances.len(), 5);
+        assert_eq!(performances[0]["segue"], true);
+        let ship_of_fools: Vec<_> = performances
+            .iter()
+            .filter(|p| p["song_id"] == performances[2]["song_id"])
+            .collect();
+        assert_eq!(ship_of_fools.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_twice_updates_concerts_without_duplicates() {
+        let server = MockServer::start().await;
+        populate_db(&mut mpdb(&server)).await.unwrap();
+        let report = populate_db(&mut mpdb(&server)).await.unwrap();
+
+        assert!(report.is_ok());
+        assert_eq!(report.step("countries").unwrap().skipped, 1);
+        assert_eq!(report.step("countries").unwrap().created, 0);
+        let concerts = report.step("concerts").unwrap();
+        assert_eq!((concerts.created, concerts.updated), (0, 2));
+        assert_eq!(report.step("performances").unwrap().created, 5);
+
+        assert_eq!(server.rows("countries").len(), 1);
+        assert_eq!(server.rows("venues").len(), 2);
+        assert_eq!(server.rows("concerts").len(), 2);
+        assert_eq!(server.rows("songtitles").len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_continues_after_injected_failures() {
+        let server = MockServer::start().await;
+        server.fail_when("venues", "name", "Rockefeller");
+        server.fail_when("performances", "sort_order", 1);
+        let report = populate_db(&mut mpdb(&server)).await.unwrap();
+
+        assert!(!report.is_ok());
+        let venues = report.step("venues").unwrap();
+        assert_eq!((venues.created, venues.failed), (1, 1));
+        assert_eq!(venues.failures, vec!["Rockefeller, Oslo, Norway"]);
+        let performances = report.step("performances").unwrap();
+        assert_eq!((performances.created, performances.failed), (3, 2));
+        assert_eq!(report.totals().failed, 3);
+
+        assert_eq!(server.rows("venues").len(), 1);
+        assert_eq!(server.rows("concerts").len(), 2);
+        // The concert at the missing venue has no venue
+        assert_eq!(server.rows("concerts")[1]["venue_id"], 0);
+        // Only the first song of each set is added
+        assert_eq!(server.rows("performances").len(), 3);
+        assert!(server.requests() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_with_rows_listed_without_slugs() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        let artist = serde_json::json!({ "name": "Motorpsycho" });
+        let artist_id = mpdb.storage.create(Entity::Artists, &artist).await.unwrap().unwrap()["id"].clone();
+        let city = serde_json::json!({ "name": "Trondheim", "country_id": 0 });
+        mpdb.storage.create(Entity::Cities, &city).await.unwrap();
+        let concert = serde_json::json!({ "artist_id": artist_id, "date": "2021-05-16", "venue_id": 0 });
+        mpdb.storage.create(Entity::Concerts, &concert).await.unwrap();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        assert!(report.is_ok());
+        // The city slug cannot be rebuilt from its name alone, so it is created again
+        assert_eq!(report.step("cities").unwrap().created, 2);
+        // The concert slug is rebuilt from its artist and date, so it is updated
+        let concerts = report.step("concerts").unwrap();
+        assert_eq!((concerts.created, concerts.updated), (1, 1));
+        assert_eq!(server.rows("concerts").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_fails_concerts_with_an_invalid_date() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        mpdb.master.data[1].event_date = "31-02-1995".to_string();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        let concerts = report.step("concerts").unwrap();
+        assert_eq!((concerts.created, concerts.failed), (1, 1));
+        assert!(concerts.failures[0].contains("invalid date '31-02-1995'"));
+        // The other concert and its songs are still synced
+        assert_eq!(server.rows("concerts").len(), 1);
+        assert_eq!(server.rows("performances").len(), 3);
+        assert!(report.step("performances").unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_populate_changes_resyncs_modified_and_deletes_removed() {
+        let server = MockServer::start().await;
+        populate_db(&mut mpdb(&server)).await.unwrap();
+
+        let old = Setlists::from_yml(MASTER_YML).unwrap();
+        let mut new = old.clone();
+        // Drop the encore of the first concert, and remove the second one
+        new.data[0].sets.set.pop();
+        new.data.pop();
+        let changes = SetlistChanges::between(&old, &new);
+
+        let mut mpdb = mpdb(&server);
+        mpdb.master = new;
+        let requests = server.requests();
+        populate_changes(&mut mpdb, &changes).await.unwrap();
+        assert!(server.requests() > requests);
+
+        let concerts = server.rows("concerts");
+        assert_eq!(concerts.len(), 1);
+        assert_eq!(concerts[0]["slug"], "motorpsycho-2021-05-16");
+        assert_eq!(server.rows("sets").len(), 1);
+        assert_eq!(server.rows("performances").len(), 2);
+        assert!(server
+            .rows("performances")
+            .iter()
+            .all(|p| p["concert_id"] == concerts[0]["id"]));
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_with_state_skips_unchanged_concerts() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(mpdb.state.concert_hashes.len(), 2);
+
+        // Nothing changed: no rows are fetched or created
+        let requests = server.requests();
+        populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(server.requests(), requests);
+
+        // One song removed from the first concert: only that concert is synced again
+        mpdb.master.data[0].sets.set[0].songs.as_mut().unwrap().pop();
+        populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(server.rows("concerts").len(), 2);
+        assert_eq!(server.rows("sets").len(), 3);
+        assert_eq!(server.rows("performances").len(), 4);
+        assert_eq!(server.rows("songs").len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_with_paginated_listing() {
+        let server = MockServer::start().await;
+        populate_db(&mut mpdb(&server)).await.unwrap();
+
+        // A fresh state is filled from the paginated listings, so nothing is created twice
+        let storage = RestStorage::new(server.base_url.clone())
+            .with_pagination(Pagination::Page { limit: 2 })
+            .with_endpoint_pagination(Entity::Songtitles, Pagination::Cursor { limit: 2 });
+        let mut mpdb = Mpdb::new(storage);
+        mpdb.master = Setlists::from_yml(MASTER_YML).unwrap();
+        mpdb.aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(mpdb.state.songtitles.len(), 5);
+        assert_eq!(server.rows("songs").len(), 4);
+        assert_eq!(server.rows("songtitles").len(), 5);
+        assert_eq!(server.rows("sets").len(), 3);
+        assert_eq!(server.rows("performances").len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_with_several_artists() {
+        let server = MockServer::start().await;
+        let mut mpdb = Mpdb::new(RestStorage::new(server.base_url.clone()));
+        mpdb.home_artist = "Spidergawd".to_string();
+        mpdb.master = Setlists::from_yml(
+            r#"
+setlist:
+- status: confirmed
+  eventDate: 02-02-2020
+  artist:
+    name: The Tussler Society
+  venue:
+    name: Blæst
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: Hell
+      - name: Tussler Theme
+      - name: Watersign
+        cover:
+          name: Hawkwind
+"#,
+        )
+        .unwrap();
+        mpdb.aliases = SongAliases::from_yml(
+            r#"
+song:
+- name: Hell, Part 1-3
+  artist: Motorpsycho
+  alias:
+  - name: Hell
+- name: Spiderwork
+  alias: []
+"#,
+        )
+        .unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+
+        let artists = server.rows("artists");
+        let artist_id = |name: &str| artists.iter().find(|a| a["name"] == name).unwrap()["id"].clone();
+        assert_eq!(artists.len(), 4);
+        assert_eq!(artists[0]["name"], "Spidergawd");
+
+        // Songs belong to the artist in the aliases, the home artist, the cover's artist or the setlist's artist
+        let songs = server.rows("songs");
+        let titles = server.rows("songtitles");
+        let owner = |title: &str| {
+            let song_id = &titles.iter().find(|t| t["title"] == title).unwrap()["song_id"];
+            songs.iter().find(|s| &s["id"] == song_id).unwrap()["artist_id"].clone()
+        };
+        assert_eq!(owner("Hell, Part 1-3"), artist_id("Motorpsycho"));
+        assert_eq!(owner("Spiderwork"), artist_id("Spidergawd"));
+        assert_eq!(owner("Watersign"), artist_id("Hawkwind"));
+        assert_eq!(owner("Tussler Theme"), artist_id("The Tussler Society"));
+        assert_eq!(songs.len(), 4);
+    }
+
+    const MBID_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  mbid: 00000000-0000-4000-8000-00000000000e
+  artist:
+    name: Motorpsycho
+    mbid: 00000000-0000-4000-8000-00000000000a
+    sortName: Motorpsycho
+    country: "NO"
+  venue:
+    name: Verkstedhallen
+    mbid: 00000000-0000-4000-8000-00000000000f
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+        workMbid: 00000000-0000-4000-8000-000000000001
+        recordingMbid: 00000000-0000-4000-8000-000000000002
+        duration: "7:05"
+        guest:
+        - name: Jenny Hval
+          instrument: vocals
+        tease:
+        - name: The Wheel
+        tag:
+        - name: extended
+        - name: acoustic
+"#;
+
+    #[tokio::test]
+    async fn test_populate_db_sends_and_matches_mbids() {
+        let server = MockServer::start().await;
+        let mut mpdb = Mpdb::new(RestStorage::new(server.base_url.clone()));
+        mpdb.master = Setlists::from_yml(MBID_YML).unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(
+            server.rows("artists")[0]["mbid"],
+            "00000000-0000-4000-8000-00000000000a"
+        );
+        assert_eq!(server.rows("artists")[0]["sort_name"], "Motorpsycho");
+        assert_eq!(server.rows("artists")[0]["country"], "NO");
+        assert_eq!(server.rows("venues")[0]["mbid"], "00000000-0000-4000-8000-00000000000f");
+        assert_eq!(
+            server.rows("concerts")[0]["mbid"],
+            "00000000-0000-4000-8000-00000000000e"
+        );
+        assert_eq!(
+            server.rows("songs")[0]["work_mbid"],
+            "00000000-0000-4000-8000-000000000001"
+        );
+        assert_eq!(
+            server.rows("performances")[0]["recording_mbid"],
+            "00000000-0000-4000-8000-000000000002"
+        );
+        let performance = &server.rows("performances")[0];
+        assert_eq!(performance["duration"], 425);
+        assert_eq!(performance["guests"], "Jenny Hval (vocals)");
+        assert_eq!(performance["teases"], "The Wheel");
+        assert_eq!(performance["tags"], "extended, acoustic");
+
+        // Renamed artist, venue and song and a corrected date are matched by their MBIDs, starting from a fresh state
+        let mut mpdb = Mpdb::new(RestStorage::new(server.base_url.clone()));
+        mpdb.master = Setlists::from_yml(
+            &MBID_YML
+                .replace("16-05-2021", "17-05-2021")
+                .replace("name: Verkstedhallen", "name: Verkstedhallen Trondheim")
+                .replace("name: NOX", "name: N.O.X.")
+                .replace("    name: Motorpsycho\n", "    name: Motorpsycho (NO)\n"),
+        )
+        .unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(server.rows("artists").len(), 1);
+        assert_eq!(server.rows("venues").len(), 1);
+        let concerts = server.rows("concerts");
+        assert_eq!(concerts.len(), 1);
+        assert_eq!(concerts[0]["slug"], "motorpsycho-no-2021-05-17");
+        assert!(mpdb.state.concerts.contains_key("motorpsycho-no-2021-05-17"));
+        assert!(!mpdb.state.concerts.contains_key("motorpsycho-2021-05-16"));
+        assert_eq!(server.rows("songs").len(), 1);
+        let titles = server.rows("songtitles");
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[1]["title"], "N.O.X.");
+        assert_eq!(titles[1]["is_default"], false);
+        assert_eq!(titles[1]["song_id"], titles[0]["song_id"]);
+        let performances = server.rows("performances");
+        assert_eq!(performances.len(), 1);
+        assert_eq!(performances[0]["song_id"], titles[0]["song_id"]);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_sends_song_metadata() {
+        let server = MockServer::start().await;
+        let catalogue = SongCatalogue::from_yml(
+            r#"
+song:
+- name: NOX
+  album: Kingdom of Oblivion
+  composer:
+  - name: Bent Sæther
+  releaseYear: 2021
+  instrumental: true
+  duration: "5:30"
+- name: Watersign
+  originalArtist: Hawkwind
+- name: Never Played
+"#,
+        )
+        .unwrap();
+        let mut mpdb = mpdb(&server);
+        mpdb.catalogue = catalogue.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        let step = report.step("song metadata").unwrap();
+        assert_eq!((step.updated, step.skipped, step.failed), (1, 2, 0));
+        let songtitles = server.rows("songtitles");
+        let nox = songtitles.iter().find(|t| t["title"] == "N.O.X.").unwrap();
+        let songs = server.rows("songs");
+        let song = songs.iter().find(|s| s["id"] == nox["song_id"]).unwrap();
+        assert_eq!(song["album"], "Kingdom of Oblivion");
+        assert_eq!(song["composers"], "Bent Sæther");
+        assert_eq!(song["release_year"], 2021);
+        assert_eq!(song["instrumental"], true);
+        assert_eq!(song["duration"], 330);
+        assert!(!song["artist_id"].is_null());
+
+        // Unchanged metadata is not sent again
+        let mut mpdb = self::mpdb(&server);
+        mpdb.catalogue = catalogue;
+        let report = populate_db(&mut mpdb).await.unwrap();
+        let step = report.step("song metadata").unwrap();
+        assert_eq!((step.updated, step.skipped), (0, 3));
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_links_albums_to_songs() {
+        let server = MockServer::start().await;
+        let discography = Discography::from_yml(
+            r#"
+album:
+- title: Kingdom of Oblivion
+  year: 2021
+  track:
+  - name: NOX
+  - name: Unreleased Jam
+  - name: Ship of Fools
+"#,
+        )
+        .unwrap();
+        let mut mpdb = mpdb(&server);
+        mpdb.discography = discography.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        let step = report.step("albums").unwrap();
+        assert_eq!((step.created, step.failed), (1, 1));
+        assert_eq!(
+            step.failures,
+            vec!["Kingdom of Oblivion: Unreleased Jam (not a known song)"]
+        );
+        let albums = server.rows("albums");
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0]["slug"], "kingdom-of-oblivion-2021");
+        let tracks = server.rows("albumtracks");
+        let positions: Vec<i64> = tracks.iter().map(|t| t["position"].as_i64().unwrap()).collect();
+        assert_eq!(positions, vec![1, 3]);
+        assert!(tracks.iter().all(|t| t["album_id"] == albums[0]["id"]));
+
+        // An unchanged album is skipped; a changed tracklist is relinked
+        let mut mpdb = self::mpdb(&server);
+        mpdb.discography = discography.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(report.step("albums").unwrap().skipped, 1);
+
+        let mut mpdb = self::mpdb(&server);
+        mpdb.discography = discography;
+        mpdb.discography.albums[0].tracks.remove(1);
+        let report = populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(report.step("albums").unwrap().updated, 1);
+        let positions: Vec<i64> = server
+            .rows("albumtracks")
+            .iter()
+            .map(|t| t["position"].as_i64().unwrap())
+            .collect();
+        assert_eq!(positions, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_links_personnel_to_concerts() {
+        let server = MockServer::start().await;
+        let lineups = Lineups::from_yml(
+            r#"
+lineup:
+- artist: Motorpsycho
+  from: 01-01-1991
+  member:
+  - name: Bent Sæther
+    instrument: bass
+  - name: Hans Magnus Ryan
+    instrument: guitar
+"#,
+        )
+        .unwrap();
+        let mut mpdb = mpdb(&server);
+        mpdb.lineups = lineups.clone();
+        mpdb.master.data[0].guests.push(Guest {
+            name: "Ståle Storløkken".to_string(),
+            instrument: Some("keyboards".to_string()),
+        });
+        let master = mpdb.master.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(report.step("personnel").unwrap().created, 2);
+        assert_eq!(server.rows("musicians").len(), 3);
+        let personnel = server.rows("personnel");
+        assert_eq!(personnel.len(), 5);
+        let guests: Vec<_> = personnel.iter().filter(|p| p["guest"] == true).collect();
+        assert_eq!(guests.len(), 1);
+        assert_eq!(guests[0]["instrument"], "keyboards");
+
+        // Unchanged personnel is skipped, and a removed concert loses its personnel
+        let mut mpdb = self::mpdb(&server);
+        (mpdb.master, mpdb.lineups) = (master.clone(), lineups);
+        let report = populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(report.step("personnel").unwrap().skipped, 2);
+
+        // Without the lineups, as when watching the master data
+        let mut new = master.clone();
+        new.data.pop();
+        let changes = SetlistChanges::between(&master, &new);
+        let mut mpdb = self::mpdb(&server);
+        mpdb.master = new;
+        populate_changes(&mut mpdb, &changes).await.unwrap();
+        assert_eq!(server.rows("personnel").len(), 3);
+        assert_eq!(server.rows("concerts").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_adds_sources() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        mpdb.master.data[0].source = Some("SBD > DAT, taped by Ola Nordmann".to_string());
+        let master = mpdb.master.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        assert_eq!(report.step("sources").unwrap().created, 1);
+        let sources = server.rows("sources");
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0]["kind"], "soundboard");
+        assert_eq!(sources[0]["taper"], "Ola Nordmann");
+        assert_eq!(sources[0]["lineage"], "SBD > DAT");
+        // The legacy string is still stored on the concert
+        let concerts = server.rows("concerts");
+        assert!(concerts
+            .iter()
+            .any(|c| c["source"] == "SBD > DAT, taped by Ola Nordmann"));
+
+        // Unchanged sources are skipped; changed ones are recreated
+        let mut mpdb = self::mpdb(&server);
+        mpdb.master = master.clone();
+        let report = populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(report.step("sources").unwrap().skipped, 1);
+
+        let mut mpdb = self::mpdb(&server);
+        mpdb.master = master;
+        mpdb.master.data[0].source = Some("AUD".to_string());
+        let report = populate_db(&mut mpdb).await.unwrap();
+        assert_eq!(report.step("sources").unwrap().updated, 1);
+        let sources = server.rows("sources");
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0]["kind"], "audience");
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_skips_unknown_songs() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        let set = &mut mpdb.master.data[1].sets.set[0];
+        set.uncertain_order = Some(true);
+        set.partial = Some(true);
+        set.songs.as_mut().unwrap().insert(
+            1,
+            Song {
+                name: "Unknown".to_string(),
+                ..Default::default()
+            },
+        );
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        // No song is created for the placeholder, and its performance is left out
+        assert_eq!(server.rows("songs").len(), 4);
+        assert!(!server.rows("songtitles").iter().any(|t| t["slug"] == "unknown"));
+        assert_eq!(report.step("performances").unwrap().skipped, 1);
+        let performances = server.rows("performances");
+        assert_eq!(performances.len(), 5);
+        // The songs after the placeholder keep their position
+        assert!(performances.iter().any(|p| p["sort_order"] == 2));
+
+        let sets = server.rows("sets");
+        let set = sets
+            .iter()
+            .find(|s| s["unique_name"] == "motorpsycho-1995-03-01-main-set")
+            .unwrap();
+        assert_eq!(
+            (set["uncertain_order"].clone(), set["partial"].clone()),
+            (true.into(), true.into())
+        );
+        assert!(report.step("performances").unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_populate_db_links_medley_parts() {
+        let server = MockServer::start().await;
+        let mut mpdb = mpdb(&server);
+        let songs = mpdb.master.data[1].sets.set[0].songs.as_mut().unwrap();
+        songs[0] = Song {
+            name: "Tussler Medley".to_string(),
+            guests: vec![Guest {
+                name: "Ståle Storløkken".to_string(),
+                instrument: None,
+            }],
+            medley: ["Nerve Tattoo", "Watersign", "Unknown"]
+                .iter()
+                .map(|name| Song {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        songs[0].medley[1].guests.push(Guest {
+            name: "Jenny Hval".to_string(),
+            instrument: None,
+        });
+        let report = populate_db(&mut mpdb).await.unwrap();
+
+        // No song is created for the medley itself, only for its parts
+        assert!(!server.rows("songtitles").iter().any(|t| t["slug"] == "tussler-medley"));
+        assert_eq!(report.step("performances").unwrap().skipped, 1);
+        let concert_id = server
+            .rows("concerts")
+            .iter()
+            .find(|c| c["date"] == "1995-03-01")
+            .unwrap()["id"]
+            .clone();
+        let performances: Vec<serde_json::Value> = server
+            .rows("performances")
+            .into_iter()
+            .filter(|p| p["concert_id"] == concert_id)
+            .collect();
+        assert_eq!(performances.len(), 3);
+
+        // The parts share the first slot and link to the performance of the first part
+        let (first, second) = (&performances[0], &performances[1]);
+        assert_eq!(
+            (first["sort_order"].clone(), second["sort_order"].clone()),
+            (0.into(), 0.into())
+        );
+        assert_eq!(first["medley"], "Tussler Medley");
+        assert_eq!(second["medley_position"], 1);
+        assert!(first["medley_id"].is_null());
+        assert_eq!(second["medley_id"], first["id"]);
+        assert!(performances[2]["medley"].is_null());
+
+        // A guest of the whole medley plays on every part
+        assert_eq!(first["guests"], "Ståle Storløkken");
+        assert_eq!(second["guests"], "Jenny Hval, Ståle Storløkken");
+        assert_eq!(performances[2]["sort_order"], 1);
+    }
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{Lineups, SetlistQuery, Setlists, SongAliases};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  tour:
+    name: Kingdom of Oblivion Tour
+  sets:
+    set:
+    - song:
+      - name: The Transmutation of Cosmoctopus Lurker
+      - name: NOX
+- status: unconfirmed
+  eventDate: 01-03-1995
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  notes: Recorded for radio
+  guest:
+  - name: Helge Sten
+  sets:
+    set:
+    - song:
+      - name: Nerve Tattoo
+      - name: Watersign
+        cover:
+          name: Hawkwind
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    fn fixture() -> (Setlists, SongAliases) {
+        (
+            Setlists::from_yml(MASTER_YML).unwrap(),
+            SongAliases::from_yml(ALIASES_YML).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_song_filter_resolves_aliases() {
+        let (master, aliases) = fixture();
+        let query = SetlistQuery {
+            song: Some("n.o.x.".to_string()),
+            ..Default::default()
+        };
+        let result = master.query(&query, &aliases);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].venue.name, "Verkstedhallen");
+    }
+
+    #[test]
+    fn test_date_range_and_location_filters() {
+        let (master, aliases) = fixture();
+        let query = SetlistQuery {
+            country: Some("norway".to_string()),
+            to: chrono::NaiveDate::from_ymd_opt(2000, 1, 1),
+            ..Default::default()
+        };
+        let result = master.query(&query, &aliases);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].venue.city.name, "Oslo");
+    }
+
+    #[test]
+    fn test_cover_notes_and_status_filters() {
+        let (master, aliases) = fixture();
+        let query = SetlistQuery {
+            cover_of: Some("hawkwind".to_string()),
+            has_notes: true,
+            status: Some("Unconfirmed".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(master.query(&query, &aliases).len(), 1);
+
+        let query = SetlistQuery {
+            cover_of: Some("Hawkwind".to_string()),
+            tour: Some("Kingdom".to_string()),
+            ..Default::default()
+        };
+        assert!(master.query(&query, &aliases).is_empty());
+    }
+
+    #[test]
+    fn test_musician_filter_uses_lineups_and_guests() {
+        let (master, aliases) = fixture();
+        let lineups = Lineups::from_yml(
+            r#"
+lineup:
+- artist: Motorpsycho
+  from: 01-01-2017
+  member:
+  - name: Tomas Järmyr
+"#,
+        )
+        .unwrap();
+        let query = SetlistQuery {
+            musician: Some("tomas järmyr".to_string()),
+            lineups: lineups.clone(),
+            ..Default::default()
+        };
+        let result = master.query(&query, &aliases);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].venue.name, "Verkstedhallen");
+
+        let query = SetlistQuery {
+            musician: Some("Helge Sten".to_string()),
+            lineups,
+            ..Default::default()
+        };
+        let result = master.query(&query, &aliases);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].venue.name, "Rockefeller");
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{render_setlist, Setlists};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  disambiguation: late show
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - name: Main
+      song:
+      - name: Ship of Fools
+        segue: true
+      - name: Watersign
+        cover:
+          name: Hawkwind
+        notes: With extended intro
+    - encore: "1"
+      partial: true
+      uncertainOrder: true
+      song:
+      - name: Vortex Surfer
+        duration: "12:04"
+        tag:
+        - name: acoustic
+        guest:
+        - name: Jenny Hval
+        - name: Ståle Storløkken
+          instrument: keyboards
+        tease:
+        - name: The Wheel
+      - name: Space Medley
+        part:
+        - name: The Wheel
+        - name: Un Chien d'Espace
+"#;
+
+    #[test]
+    fn test_find_by_identifier_and_date() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        assert_eq!(master.data[0].identifier(), "motorpsycho-2021-05-16-late-show");
+        assert_eq!(master.find("motorpsycho-2021-05-16-late-show").len(), 1);
+        assert_eq!(master.find("16-05-2021").len(), 1);
+        assert_eq!(master.find("2021-05-16").len(), 1);
+        assert!(master.find("2021-05-17").is_empty());
+    }
+
+    #[test]
+    fn test_render_without_colour() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let output = render_setlist(&master.data[0], false);
+        assert!(output.starts_with("Motorpsycho - Sunday 16 May 2021 (late show)\n"));
+        assert!(output.contains("Set 1: Main\n"));
+        assert!(output.contains("1. Ship of Fools ->\n"));
+        assert!(output.contains("2. Watersign (Hawkwind cover)\n"));
+        assert!(output.contains("With extended intro"));
+        assert!(output.contains("Encore 1 (incomplete, order uncertain)\n"));
+        assert!(output.contains("1. Vortex Surfer [acoustic] (12:04)\n"));
+        assert!(output.contains("        with Jenny Hval, Ståle Storløkken (keyboards)\n"));
+        assert!(output.contains("        teases The Wheel\n"));
+        assert!(output.contains("2. Space Medley (The Wheel / Un Chien d'Espace)\n"));
+        assert!(!output.contains('\x1b'));
+    }
+}
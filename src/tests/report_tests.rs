@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::report::{RunReport, StepReport};
+
+    fn report() -> RunReport {
+        let mut countries = StepReport::new("countries");
+        countries.created = 2;
+        countries.skipped = 1;
+        let mut venues = StepReport::new("venues");
+        venues.created = 1;
+        venues.fail("Rockefeller, Oslo, Norway".to_string());
+
+        let mut report = RunReport::default();
+        report.add::<String>("countries", Ok(countries));
+        report.add::<String>("venues", Ok(venues));
+        report.add("concerts", Err("connection refused"));
+        report
+    }
+
+    #[test]
+    fn test_totals_sum_all_steps() {
+        let totals = report().totals();
+        assert_eq!(totals.created, 3);
+        assert_eq!(totals.skipped, 1);
+        assert_eq!(totals.failed, 1);
+        assert_eq!(totals.failures, vec!["Rockefeller, Oslo, Norway"]);
+    }
+
+    #[test]
+    fn test_step_errors_are_recorded() {
+        let report = report();
+        let concerts = report.step("concerts").unwrap();
+        assert_eq!(concerts.error.as_deref(), Some("connection refused"));
+        assert!(!concerts.is_ok());
+        assert!(report.step("countries").unwrap().is_ok());
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_report_is_printed_as_table() {
+        let table = report().to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "step       created  updated  skipped  deleted   failed");
+        assert_eq!(lines[1], "countries        2        0        1        0        0");
+        assert_eq!(lines[3], "concerts                                         error");
+        assert_eq!(lines[4], "total            3        0        1        0        1");
+        assert_eq!(lines[5], "venues: failed: Rockefeller, Oslo, Norway");
+        assert_eq!(lines[6], "concerts: error: connection refused");
+    }
+
+    #[test]
+    fn test_report_round_trips_through_json() {
+        let report = report();
+        let json: RunReport = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json, report);
+    }
+}
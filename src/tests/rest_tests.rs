@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use crate::storage::{Entity, Pagination, RestStorage, Storage};
+    use crate::tests::mock_server::MockServer;
+    use serde_json::{json, Value};
+
+    async fn server_with_countries(count: usize) -> MockServer {
+        let server = MockServer::start().await;
+        let storage = RestStorage::new(server.base_url.clone());
+        for i in 0..count {
+            let country = json!({ "name": format!("Country {}", i), "slug": format!("country-{}", i) });
+            storage.create(Entity::Countries, &country).await.unwrap().unwrap();
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn test_list_without_pagination() {
+        let server = server_with_countries(5).await;
+        let storage = RestStorage::new(server.base_url.clone());
+        let requests = server.requests();
+
+        let rows: Vec<Value> = storage.list(Entity::Countries).await.unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(server.requests(), requests + 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_with_page_pagination() {
+        let server = server_with_countries(5).await;
+        let storage = RestStorage::new(server.base_url.clone()).with_pagination(Pagination::Page { limit: 2 });
+        let requests = server.requests();
+
+        let rows: Vec<Value> = storage.list(Entity::Countries).await.unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[4]["name"], "Country 4");
+        // Pages of 2, 2 and 1 rows
+        assert_eq!(server.requests(), requests + 3);
+
+        // A full last page needs one more, empty, page
+        let rows: Vec<Value> = storage.list(Entity::Cities).await.unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_with_cursor_pagination_per_endpoint() {
+        let server = server_with_countries(4).await;
+        let storage = RestStorage::new(server.base_url.clone())
+            .with_pagination(Pagination::Page { limit: 100 })
+            .with_endpoint_pagination(Entity::Countries, Pagination::Cursor { limit: 2 });
+        let requests = server.requests();
+
+        let rows: Vec<Value> = storage.list(Entity::Countries).await.unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0]["name"], "Country 0");
+        assert_eq!(server.requests(), requests + 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_stops_when_the_endpoint_ignores_pagination() {
+        let server = server_with_countries(4).await;
+        server.ignore_pagination();
+
+        // Full pages with the same rows every time: the repeated page ends the listing
+        let storage = RestStorage::new(server.base_url.clone()).with_pagination(Pagination::Page { limit: 2 });
+        let requests = server.requests();
+        let rows: Vec<Value> = storage.list(Entity::Countries).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(server.requests(), requests + 2);
+
+        // A next cursor equal to the one sent ends the listing after that page
+        let storage = RestStorage::new(server.base_url.clone()).with_pagination(Pagination::Cursor { limit: 2 });
+        let requests = server.requests();
+        let rows: Vec<Value> = storage.list(Entity::Countries).await.unwrap();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(server.requests(), requests + 2);
+    }
+
+    #[test]
+    fn test_pagination_from_mode() {
+        assert_eq!(Pagination::from_mode("none", 10), Ok(Pagination::None));
+        assert_eq!(Pagination::from_mode("page", 10), Ok(Pagination::Page { limit: 10 }));
+        assert_eq!(
+            Pagination::from_mode("cursor", 10),
+            Ok(Pagination::Cursor { limit: 10 })
+        );
+        assert!(Pagination::from_mode("offset", 10).is_err());
+        assert!(Pagination::from_mode("page", 0).is_err());
+        assert!(Pagination::from_mode("cursor", 0).is_err());
+        assert_eq!(Pagination::from_mode("none", 0), Ok(Pagination::None));
+        assert_eq!(Entity::from_name("performances"), Some(Entity::Performances));
+    }
+}
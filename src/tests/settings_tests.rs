@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::settings::Settings;
+    use crate::storage::{Entity, Storage};
+    use crate::tests::mock_server::MockServer;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    const CONFIG_TOML: &str = r#"
+mpdb_base_url = "http://localhost:5150"
+master_path = "data"
+
+[pagination]
+mode = "page"
+limit = 100
+
+[profiles.prod]
+mpdb_base_url = "https://mpdb.example.com"
+token = "secret"
+
+[profiles.dev]
+username = "dev"
+password = "devpass"
+"#;
+
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mpdbtool-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Option<HashMap<String, String>> {
+        Some(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+    }
+
+    #[test]
+    fn test_defaults_without_values() {
+        let path = config_file("empty", "");
+        let settings = Settings::load_with_env(Some(&path), None, env(&[])).unwrap();
+        assert_eq!(settings, Settings::default());
+        assert_eq!(settings.master_file(), "mpdbmasterdata/master");
+        assert_eq!(settings.home_artist, "Motorpsycho");
+        assert!(settings.base_url().is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_profiles_and_environment() {
+        let path = config_file("profiles", CONFIG_TOML);
+
+        let settings = Settings::load_with_env(Some(&path), None, env(&[])).unwrap();
+        assert_eq!(settings.base_url(), Ok("http://localhost:5150"));
+        assert_eq!(settings.aliases_file(), "data/master_aliases");
+        assert_eq!(settings.pagination.limit, 100);
+        assert_eq!(settings.token, None);
+
+        let settings = Settings::load_with_env(Some(&path), Some("prod"), env(&[])).unwrap();
+        assert_eq!(settings.base_url(), Ok("https://mpdb.example.com"));
+        assert_eq!(settings.token.as_deref(), Some("secret"));
+        assert_eq!(settings.master_path, "data");
+
+        let vars = env(&[
+            ("MPDBTOOL_MPDB_BASE_URL", "http://staging:5150"),
+            ("MPDBTOOL_PAGINATION__LIMIT", "20"),
+            ("MPDBTOOL_HOME_ARTIST", "Spidergawd"),
+        ]);
+        let settings = Settings::load_with_env(Some(&path), Some("prod"), vars).unwrap();
+        assert_eq!(settings.base_url(), Ok("http://staging:5150"));
+        assert_eq!(settings.pagination.limit, 20);
+        assert_eq!(settings.pagination.mode, "page");
+        assert_eq!(settings.home_artist, "Spidergawd");
+
+        assert!(Settings::load_with_env(Some(&path), Some("test"), env(&[])).is_err());
+        let vars = env(&[("MPDBTOOL_PAGINATION__LIMIT", "0")]);
+        assert!(Settings::load_with_env(Some(&path), None, vars).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_config_file_is_an_error() {
+        let path = std::env::temp_dir().join("mpdbtool-does-not-exist.toml");
+        assert!(Settings::load_with_env(Some(&path), None, env(&[])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rest_storage_sends_credentials() {
+        let server = MockServer::start().await;
+        let path = config_file("credentials", CONFIG_TOML);
+        let vars = env(&[("MPDBTOOL_MPDB_BASE_URL", &server.base_url)]);
+
+        let settings = Settings::load_with_env(Some(&path), Some("prod"), vars.clone()).unwrap();
+        let rows: Vec<serde_json::Value> = settings.rest_storage().unwrap().list(Entity::Countries).await.unwrap();
+        assert!(rows.is_empty());
+        assert_eq!(server.authorization().as_deref(), Some("Bearer secret"));
+
+        let settings = Settings::load_with_env(Some(&path), Some("dev"), vars).unwrap();
+        let _: Vec<serde_json::Value> = settings.rest_storage().unwrap().list(Entity::Countries).await.unwrap();
+        // dev:devpass
+        assert_eq!(server.authorization().as_deref(), Some("Basic ZGV2OmRldnBhc3M="));
+        std::fs::remove_file(path).unwrap();
+    }
+}
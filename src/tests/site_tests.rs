@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{Setlists, SongAliases};
+    use crate::site::Site;
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  tour:
+    name: Kingdom of Oblivion Tour
+  sets:
+    set:
+    - song:
+      - name: NOX
+      - name: Ship of Fools
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    #[test]
+    fn test_site_build_writes_cross_linked_pages() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        let out = std::env::temp_dir().join(format!("mpdbtool-site-test-{}", std::process::id()));
+
+        let pages = Site::new(&master, &aliases).build(&out).unwrap();
+        // 1 concert, 2 songs, 1 venue, 1 tour, 1 year, 5 section indexes and the front page
+        assert_eq!(pages, 12);
+
+        let concert = std::fs::read_to_string(out.join("concerts/motorpsycho-2021-05-16.html")).unwrap();
+        assert!(concert.contains("href=\"../songs/n-o-x.html\">NOX</a>"));
+        assert!(concert.contains("href=\"../venues/verkstedhallen-trondheim-norway.html\""));
+        assert!(concert.contains("href=\"../tours/kingdom-of-oblivion-tour.html\""));
+        assert!(concert.contains("href=\"../years/2021.html\""));
+
+        let song = std::fs::read_to_string(out.join("songs/n-o-x.html")).unwrap();
+        assert!(song.contains("href=\"../concerts/motorpsycho-2021-05-16.html\""));
+
+        std::fs::remove_dir_all(out).unwrap();
+    }
+}
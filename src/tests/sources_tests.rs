@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{Setlists, Source};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  source: AUD
+  sources:
+    source:
+    - kind: soundboard
+      lineage: SBD > DAT > CD-R > EAC > FLAC
+      archiveId: mp2021-05-16.sbd.flac16
+      complete: false
+    - kind: video
+      taper: Ola Nordmann
+  sets:
+    set:
+    - song:
+      - name: NOX
+- status: confirmed
+  eventDate: 01-03-1995
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  source: AUD > DAT, taped by Kari Nordmann
+  sets:
+    set:
+    - song:
+      - name: Nerve Tattoo
+"#;
+
+    #[test]
+    fn test_sources_can_be_parsed_from_yml() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let sources = &master.data[0].sources.source;
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].archive_id.as_deref(), Some("mp2021-05-16.sbd.flac16"));
+        assert_eq!(sources[0].complete, Some(false));
+        assert_eq!(sources[1].taper.as_deref(), Some("Ola Nordmann"));
+        assert!(master.data[1].sources.is_empty());
+
+        // Sources are written back, and left out when there are none
+        let yml = master.to_yml().unwrap();
+        assert_eq!(yml.matches("sources:").count(), 1);
+        assert_eq!(Setlists::from_yml(&yml).unwrap(), master);
+    }
+
+    #[test]
+    fn test_sources_can_be_parsed_from_xml() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <setlists>
+                    <setlist>
+                        <status>confirmed</status>
+                        <eventDate>01-03-1995</eventDate>
+                        <artist><name>Motorpsycho</name></artist>
+                        <venue>
+                            <name>Rockefeller</name>
+                            <city><name>Oslo</name><country><name>Norway</name></country></city>
+                        </venue>
+                        <sources>
+                            <source><kind>fm</kind><notes>NRK P3 broadcast</notes></source>
+                        </sources>
+                        <sets><set><song><name>Nerve Tattoo</name></song></set></sets>
+                    </setlist>
+                </setlists>"#;
+        let master = Setlists::from_xml(xml).unwrap();
+        assert_eq!(master.data[0].source, None);
+        assert_eq!(master.data[0].sources.source[0].kind.as_deref(), Some("fm"));
+    }
+
+    #[test]
+    fn test_recordings_fall_back_to_the_legacy_source() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        // Structured sources replace the legacy string
+        assert_eq!(master.data[0].recordings().len(), 2);
+
+        let recordings = master.data[1].recordings();
+        assert_eq!(
+            recordings,
+            vec![Source {
+                kind: Some("audience".to_string()),
+                lineage: Some("AUD > DAT".to_string()),
+                taper: Some("Kari Nordmann".to_string()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_legacy_sources_are_parsed() {
+        let source = Source::parse_legacy("SBD");
+        assert_eq!(source.kind.as_deref(), Some("soundboard"));
+        assert_eq!(source.notes, None);
+
+        let source = Source::parse_legacy("FM broadcast; recorded by NRK, first song missing");
+        assert_eq!(source.kind.as_deref(), Some("fm"));
+        assert_eq!(source.taper.as_deref(), Some("NRK"));
+        assert_eq!(source.notes.as_deref(), Some("first song missing"));
+
+        let source = Source::parse_legacy("Unknown generation tape");
+        assert_eq!(source.kind, None);
+        assert_eq!(source.notes.as_deref(), Some("Unknown generation tape"));
+    }
+
+    #[test]
+    fn test_source_display() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let sources = &master.data[0].sources.source;
+        assert_eq!(
+            sources[0].to_string(),
+            "soundboard, SBD > DAT > CD-R > EAC > FLAC, archive mp2021-05-16.sbd.flac16 (incomplete)"
+        );
+        assert_eq!(sources[1].to_string(), "video, taped by Ola Nordmann");
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use crate::mpdb::{Concert, DbId, Mpdb, Performance, Songtitle, Source};
+    use crate::setlists::{SetlistChanges, Setlists, SongAliases};
+    use crate::storage::{Entity, SqliteStorage, Storage};
+    use crate::{populate_changes, populate_db};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+        segue: true
+      - name: Watersign
+        cover:
+          name: Hawkwind
+    - encore: "1"
+      song:
+      - name: Ship of Fools
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    #[tokio::test]
+    async fn test_populate_sqlite() {
+        let mut mpdb = Mpdb::new(SqliteStorage::in_memory().unwrap());
+        mpdb.master = Setlists::from_yml(MASTER_YML).unwrap();
+        mpdb.aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+
+        let storage = &mpdb.storage;
+        let concerts: Vec<Concert> = storage.list(Entity::Concerts).await.unwrap();
+        assert_eq!(concerts.len(), 1);
+        assert_eq!(concerts[0].slug, "motorpsycho-2021-05-16");
+
+        let artists: Vec<serde_json::Value> = storage.list(Entity::Artists).await.unwrap();
+        assert_eq!(artists.len(), 2);
+
+        // N.O.X. and its alias, Watersign and Ship of Fools
+        let songtitles: Vec<Songtitle> = storage.list(Entity::Songtitles).await.unwrap();
+        assert_eq!(songtitles.len(), 4);
+
+        let sets: Vec<serde_json::Value> = storage.list(Entity::Sets).await.unwrap();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[1]["name"], "Encore 1");
+
+        let performances: Vec<Performance> = storage.list(Entity::Performances).await.unwrap();
+        assert_eq!(performances.len(), 3);
+        assert!(performances[0].segue);
+        // NOX is performed as the alias, but counted as the song N.O.X.
+        let nox = songtitles.iter().find(|s| s.title == "N.O.X.").unwrap();
+        assert_eq!(performances[0].song_id, nox.song_id);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_removes_the_only_concert_with_sources() {
+        let mut master = Setlists::from_yml(MASTER_YML).unwrap();
+        let mut recorded = master.data[0].clone();
+        recorded.event_date = "17-05-2021".to_string();
+        recorded.source = Some("AUD".to_string());
+        master.data.push(recorded);
+
+        let mut mpdb = Mpdb::new(SqliteStorage::in_memory().unwrap());
+        mpdb.master = master.clone();
+        mpdb.aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        populate_db(&mut mpdb).await.unwrap();
+        let sources: Vec<Source> = mpdb.storage.list(Entity::Sources).await.unwrap();
+        assert_eq!(sources.len(), 1);
+
+        // None of the remaining concerts has a source, but the removed one's rows are deleted with it
+        let mut new = master.clone();
+        new.data.pop();
+        let changes = SetlistChanges::between(&master, &new);
+        mpdb.master = new;
+        let report = populate_changes(&mut mpdb, &changes).await.unwrap();
+        assert!(report.step("removed concerts").unwrap().is_ok());
+        let sources: Vec<Source> = mpdb.storage.list(Entity::Sources).await.unwrap();
+        assert!(sources.is_empty());
+        let concerts: Vec<Concert> = mpdb.storage.list(Entity::Concerts).await.unwrap();
+        assert_eq!(concerts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_refuses_duplicates_and_updates() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let country = serde_json::json!({ "name": "Norway", "slug": "norway", "code": "NO" });
+        let created = storage.create(Entity::Countries, &country).await.unwrap().unwrap();
+        assert_eq!(created["id"], 1);
+        assert!(storage.create(Entity::Countries, &country).await.unwrap().is_none());
+
+        let country = serde_json::json!({ "name": "Norge", "slug": "norge" });
+        let updated = storage
+            .update(Entity::Countries, DbId(1), &country)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated["name"], "Norge");
+        assert_eq!(updated["code"], "NO");
+        assert!(storage
+            .update(Entity::Countries, DbId(2), &country)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A payload without any known column updates nothing
+        let unknown = serde_json::json!({ "id": 1, "population": 5_500_000 });
+        assert!(storage
+            .update(Entity::Countries, DbId(1), &unknown)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_delete_refuses_referenced_rows() {
+        let storage = SqliteStorage::in_memory().unwrap();
+        let country = serde_json::json!({ "name": "Norway", "slug": "norway" });
+        storage.create(Entity::Countries, &country).await.unwrap().unwrap();
+        let city = serde_json::json!({ "name": "Oslo", "slug": "oslo-norway", "country_id": 1 });
+        storage.create(Entity::Cities, &city).await.unwrap().unwrap();
+
+        assert!(!storage.delete(Entity::Countries, DbId(1)).await.unwrap());
+        assert!(storage.delete(Entity::Cities, DbId(1)).await.unwrap());
+        assert!(!storage.delete(Entity::Cities, DbId(1)).await.unwrap());
+        assert!(storage.delete(Entity::Countries, DbId(1)).await.unwrap());
+    }
+}
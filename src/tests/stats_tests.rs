@@ -0,0 +1,289 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{Discography, Guest, Setlists, Song, SongAliases};
+    use crate::stats::{
+        album_coverage, render_album_coverage, render_song_counts, render_song_gaps, render_source_stats, song_counts,
+        song_gaps, source_stats, GroupBy, PerformanceFilter,
+    };
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  tour:
+    name: Kingdom Tour
+  sets:
+    set:
+    - song:
+      - name: NOX
+      - name: The Tower
+        duration: "9:00"
+- status: confirmed
+  eventDate: 01-03-2017
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  tour:
+    name: The Tower Tour
+  source: SBD
+  sets:
+    set:
+    - song:
+      - name: The Tower
+        duration: "10:00"
+        tag:
+        - name: extended
+      - name: Bartok
+        guest:
+        - name: Ståle Storløkken
+          instrument: keyboards
+        tease:
+        - name: Nerve Tattoo
+- status: confirmed
+  eventDate: 02-03-2017
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Rockefeller
+    city:
+      name: Oslo
+      country:
+        name: Norway
+  sources:
+    source:
+    - kind: audience
+    - kind: Soundboard
+      complete: false
+  sets:
+    set:
+    - song:
+      - name: Intrepid Explorer
+      - name: Unknown
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: Bartok of the Universe
+  alias:
+  - name: Bartok
+"#;
+
+    const DISCOGRAPHY_YML: &str = r#"
+album:
+- title: The Tower
+  year: 2017
+  track:
+  - name: The Tower
+  - name: Bartok of the Universe
+  - name: Intrepid Explorer
+  - name: A Pacific Sonata
+- title: Demon Box
+  track:
+  - name: Feedtime
+"#;
+
+    fn coverage(by: GroupBy) -> Vec<crate::stats::AlbumCoverage> {
+        album_coverage(
+            &Setlists::from_yml(MASTER_YML).unwrap(),
+            &SongAliases::from_yml(ALIASES_YML).unwrap(),
+            &Discography::from_yml(DISCOGRAPHY_YML).unwrap(),
+            by,
+            &PerformanceFilter::default(),
+        )
+    }
+
+    #[test]
+    fn test_album_coverage_per_tour() {
+        let coverage = coverage(GroupBy::Tour);
+        assert_eq!(coverage.len(), 2);
+        let tower = &coverage[0];
+        let groups: Vec<(&str, usize)> = tower.groups.iter().map(|g| (g.name.as_str(), g.played.len())).collect();
+        // Groups are in the order of their first concert
+        assert_eq!(
+            groups,
+            vec![("The Tower Tour", 2), ("(no tour)", 1), ("Kingdom Tour", 1)]
+        );
+        assert_eq!(tower.groups[0].played, vec!["The Tower", "Bartok of the Universe"]);
+        assert!(coverage[1].groups.is_empty());
+    }
+
+    #[test]
+    fn test_album_coverage_per_year() {
+        let coverage = coverage(GroupBy::Year);
+        let groups: Vec<(&str, usize)> = coverage[0]
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), g.played.len()))
+            .collect();
+        assert_eq!(groups, vec![("2017", 3), ("2021", 1)]);
+    }
+
+    #[test]
+    fn test_album_coverage_rendering() {
+        let rendered = render_album_coverage(&coverage(GroupBy::Year));
+        assert_eq!(
+            rendered,
+            "The Tower (2017): 4 songs\n  2017    3/4    75%\n  2021    1/4    25%\nDemon Box: 1 songs\n  never played\n"
+        );
+    }
+
+    #[test]
+    fn test_album_coverage_with_a_filter() {
+        let filter = PerformanceFilter {
+            tag: Some("Extended".to_string()),
+            ..Default::default()
+        };
+        let coverage = album_coverage(
+            &Setlists::from_yml(MASTER_YML).unwrap(),
+            &SongAliases::from_yml(ALIASES_YML).unwrap(),
+            &Discography::from_yml(DISCOGRAPHY_YML).unwrap(),
+            GroupBy::Tour,
+            &filter,
+        );
+        assert_eq!(coverage[0].groups.len(), 1);
+        assert_eq!(coverage[0].groups[0].played, vec!["The Tower"]);
+    }
+
+    #[test]
+    fn test_song_counts() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        let counts = song_counts(&master, &aliases, &PerformanceFilter::default());
+        let names: Vec<(&str, usize)> = counts.iter().map(|c| (c.name.as_str(), c.performances)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("The Tower", 2),
+                ("Bartok of the Universe", 1),
+                ("Intrepid Explorer", 1),
+                ("NOX", 1)
+            ]
+        );
+        assert_eq!(counts[0].concerts, 2);
+        assert_eq!(counts[0].average_duration, Some(570));
+        assert_eq!(render_song_counts(&counts[..1]), "    2     2   9:30  The Tower\n");
+
+        for filter in [
+            PerformanceFilter {
+                guest: Some("storløkken".to_string()),
+                ..Default::default()
+            },
+            PerformanceFilter {
+                tease: Some("Nerve".to_string()),
+                ..Default::default()
+            },
+        ] {
+            let counts = song_counts(&master, &aliases, &filter);
+            assert_eq!(counts.len(), 1);
+            assert_eq!(counts[0].name, "Bartok of the Universe");
+        }
+    }
+
+    #[test]
+    fn test_song_counts_per_medley_part() {
+        let mut master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        master.data[0].sets.set[0].songs.as_mut().unwrap()[0] = Song {
+            name: "Kingdom Medley".to_string(),
+            guests: vec![Guest {
+                name: "Jenny Hval".to_string(),
+                instrument: None,
+            }],
+            medley: ["NOX", "Bartok"]
+                .iter()
+                .map(|name| Song {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        // Every part counts as a performance of its song, the medley itself does not
+        let counts = song_counts(&master, &aliases, &PerformanceFilter::default());
+        let names: Vec<(&str, usize)> = counts.iter().map(|c| (c.name.as_str(), c.performances)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Bartok of the Universe", 2),
+                ("The Tower", 2),
+                ("Intrepid Explorer", 1),
+                ("NOX", 1)
+            ]
+        );
+
+        // A filter on the whole medley applies to all its parts
+        let filter = PerformanceFilter {
+            guest: Some("hval".to_string()),
+            ..Default::default()
+        };
+        let names: Vec<String> = song_counts(&master, &aliases, &filter)
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["Bartok of the Universe", "NOX"]);
+    }
+
+    #[test]
+    fn test_source_stats() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let stats = source_stats(&master, None);
+        assert_eq!((stats.concerts, stats.recorded), (3, 2));
+        assert_eq!(
+            stats.kinds,
+            vec![("soundboard".to_string(), 2), ("audience".to_string(), 1)]
+        );
+        assert_eq!(
+            stats.missing,
+            vec!["16-05-2021  Verkstedhallen, Trondheim, Norway  [Kingdom Tour]"]
+        );
+
+        let stats = source_stats(&master, Some("audience"));
+        assert_eq!(stats.missing.len(), 2);
+        assert_eq!(
+            render_source_stats(&stats),
+            "2 of 3 concerts recorded\n    2  soundboard\n    1  audience\n\n2 concerts without any audience recording:\n  \
+             01-03-2017  Rockefeller, Oslo, Norway  [The Tower Tour]\n  \
+             16-05-2021  Verkstedhallen, Trondheim, Norway  [Kingdom Tour]\n"
+        );
+    }
+
+    #[test]
+    fn test_song_gaps() {
+        let mut master = Setlists::from_yml(MASTER_YML).unwrap();
+        let aliases = SongAliases::from_yml(ALIASES_YML).unwrap();
+        // The 02-03-2017 setlist has an unknown song, so it does not count towards the gaps
+        let gaps = song_gaps(&master, &aliases);
+        let names: Vec<(&str, usize)> = gaps.iter().map(|g| (g.name.as_str(), g.gap)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Bartok of the Universe", 1),
+                ("Intrepid Explorer", 1),
+                ("NOX", 0),
+                ("The Tower", 0)
+            ]
+        );
+        assert_eq!(
+            render_song_gaps(&gaps[..1]),
+            "    1  01-03-2017  Bartok of the Universe\n"
+        );
+
+        master.data[2].sets.set[0].songs.as_mut().unwrap().pop();
+        let gaps = song_gaps(&master, &aliases);
+        assert_eq!((gaps[0].name.as_str(), gaps[0].gap), ("Bartok of the Universe", 2));
+    }
+}
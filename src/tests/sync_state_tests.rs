@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use crate::mpdb::DbId;
+    use crate::setlists::Setlists;
+    use crate::sync_state::{setlist_hash, SyncState};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+"#;
+
+    #[test]
+    fn test_setlist_hash_changes_with_content() {
+        let master = Setlists::from_yml(MASTER_YML).unwrap();
+        let mut setlist = master.data[0].clone();
+        let hash = setlist_hash(&setlist);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, setlist_hash(&master.data[0]));
+
+        setlist.notes = Some("Soundcheck".to_string());
+        assert_ne!(hash, setlist_hash(&setlist));
+    }
+
+    #[test]
+    fn test_sync_state_save_and_load() {
+        let path = std::env::temp_dir().join(format!("mpdbtool-sync-test-{}.json", std::process::id()));
+        let mut state = SyncState::new("http://localhost:5150");
+        state.countries.insert("Norway".to_string(), DbId(1));
+        state
+            .concert_hashes
+            .insert("motorpsycho-2021-05-16".to_string(), "abc".to_string());
+        state.save(&path).unwrap();
+
+        assert_eq!(SyncState::load(&path, "http://localhost:5150").unwrap(), state);
+        // A state for another database is not used
+        assert!(SyncState::load(&path, "sqlite:mpdb.sqlite").unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(SyncState::load(&path, "http://localhost:5150").unwrap().is_empty());
+    }
+}
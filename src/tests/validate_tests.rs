@@ -0,0 +1,430 @@
+#[cfg(test)]
+mod tests {
+    use crate::setlists::{
+        Artist, Discography, Guest, Lineups, Member, Setlists, Song, SongAliases, SongCatalogue, Source, Tag, Tease,
+    };
+    use crate::validate::{has_errors, validate, validate_catalogue, validate_discography, validate_lineups, Severity};
+
+    const MASTER_YML: &str = r#"
+setlist:
+- status: confirmed
+  eventDate: 16-05-2021
+  artist:
+    name: Motorpsycho
+  venue:
+    name: Verkstedhallen
+    city:
+      name: Trondheim
+      country:
+        name: Norway
+  sets:
+    set:
+    - song:
+      - name: NOX
+        segue: true
+      - name: Watersign
+"#;
+
+    const ALIASES_YML: &str = r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+"#;
+
+    fn master() -> Setlists {
+        Setlists::from_yml(MASTER_YML).unwrap()
+    }
+
+    fn aliases() -> SongAliases {
+        SongAliases::from_yml(ALIASES_YML).unwrap()
+    }
+
+    #[test]
+    fn test_valid_data_has_no_issues() {
+        assert!(validate(&master(), &aliases()).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_date_and_empty_names() {
+        let mut master = master();
+        master.data[0].event_date = "2021-05-16".to_string();
+        master.data[0].venue.city.name = " ".to_string();
+        master.data[0].sets.set[0].songs.as_mut().unwrap()[1].name = String::new();
+
+        let issues = validate(&master, &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(issues.len(), 3);
+        assert!(messages[0].starts_with("invalid event date '2021-05-16'"));
+        assert_eq!(messages[1], "empty city name");
+        assert_eq!(messages[2], "main set: song 2 has no name");
+        assert!(has_errors(&issues));
+    }
+
+    #[test]
+    fn test_duplicate_concerts_and_dangling_segue() {
+        let mut master = master();
+        master.data[0].sets.set[0].songs.as_mut().unwrap().pop();
+        master.data.push(master.data[0].clone());
+
+        let issues = validate(&master, &aliases());
+        assert_eq!(issues.len(), 3);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].message, "main set: last song 'NOX' segues into nothing");
+        assert_eq!(issues[1].concert.as_deref(), Some("motorpsycho-2021-05-16"));
+        assert!(issues[1].message.starts_with("duplicate concert (setlists 1 and 2"));
+        assert_eq!(issues[1].to_string().split(": ").next(), Some("error"));
+        assert_eq!(issues[2].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_ambiguous_aliases() {
+        let aliases = SongAliases::from_yml(
+            r#"
+song:
+- name: N.O.X.
+  alias:
+  - name: NOX
+  - name: nox
+- name: Nox II
+  alias:
+  - name: NOX
+"#,
+        )
+        .unwrap();
+
+        let issues = validate(&master(), &aliases);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(
+            issues[1].message,
+            "'NOX' is a title or alias of both 'N.O.X.' and 'Nox II'"
+        );
+    }
+
+    #[test]
+    fn test_empty_alias_artist() {
+        let aliases = SongAliases::from_yml(
+            r#"
+song:
+- name: Hell, Part 1-3
+  artist: " "
+  alias: []
+"#,
+        )
+        .unwrap();
+
+        let issues = validate(&master(), &aliases);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "song 'Hell, Part 1-3' has an empty artist name");
+    }
+
+    #[test]
+    fn test_malformed_mbids() {
+        let mut master = master();
+        master.data[0].artist.mbid = Some("9f5e4a0e-2c7d-4b1a-9e3f-6c8d2b1a0f4e".to_string());
+        master.data[0].venue.mbid = Some("not-a-uuid".to_string());
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[0];
+        song.work_mbid = Some("9F5E4A0E-2C7D-4B1A-9E3F-6C8D2B1A0F4".to_string());
+        let aliases = SongAliases::from_yml(
+            r#"
+song:
+- name: Hell, Part 1-3
+  workMbid: 9f5e4a0e_2c7d_4b1a_9e3f_6c8d2b1a0f4e
+  alias: []
+"#,
+        )
+        .unwrap();
+
+        let issues = validate(&master, &aliases);
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(issues.len(), 3, "{:?}", messages);
+        assert_eq!(messages[0], "venue MBID 'not-a-uuid' is not a valid UUID");
+        assert!(messages[1].ends_with("work MBID '9F5E4A0E-2C7D-4B1A-9E3F-6C8D2B1A0F4' is not a valid UUID"));
+        assert_eq!(
+            messages[2],
+            "song 'Hell, Part 1-3' work MBID '9f5e4a0e_2c7d_4b1a_9e3f_6c8d2b1a0f4e' is not a valid UUID"
+        );
+    }
+
+    #[test]
+    fn test_song_catalogue_issues() {
+        let mut master = master();
+        master.data[0].sets.set[0].songs.as_mut().unwrap()[1].original_artist = Some(Artist {
+            name: "Hawkwind Zoo".to_string(),
+            sort_name: None,
+            mbid: None,
+            country: None,
+        });
+        let catalogue = SongCatalogue::from_yml(
+            r#"
+song:
+- name: NOX
+  duration: "9:99"
+- name: N.O.X.
+  composer:
+  - name: " "
+- name: Unknown Song
+- name: Watersign
+  originalArtist: Hawkwind
+  releaseYear: 1850
+"#,
+        )
+        .unwrap();
+
+        let issues = validate_catalogue(&catalogue, &master, &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "song catalogue entry 'NOX' is an alias of 'N.O.X.'",
+                "song 'NOX' has an invalid duration '9:99' (expected M:SS or H:MM:SS)",
+                "song 'N.O.X.' is in the song catalogue more than once",
+                "song 'N.O.X.' has a composer with an empty name",
+                "song catalogue entry 'Unknown Song' is not played in any concert",
+                "song 'Watersign' has an implausible release year 1850",
+                "'Watersign' is a cover of Hawkwind Zoo, but the song catalogue says it is by Hawkwind",
+            ]
+        );
+        assert_eq!(issues[6].concert.as_deref(), Some("motorpsycho-2021-05-16"));
+        assert_eq!(issues[4].severity, Severity::Warning);
+        assert!(has_errors(&issues));
+    }
+
+    #[test]
+    fn test_discography_issues() {
+        let discography = Discography::from_yml(
+            r#"
+album:
+- title: Kingdom of Oblivion
+  year: 2021
+  mbid: nope
+  track:
+  - name: NOX
+  - name: N.O.X.
+  - name: Watersign
+  - name: Dreamkiller
+- title: Kingdom of Oblivion
+  year: 2021
+- title: Future Album
+  year: 2999
+  track:
+  - name: N.O.X.
+"#,
+        )
+        .unwrap();
+
+        let issues = validate_discography(&discography, &master(), &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "album 'Kingdom of Oblivion' MBID 'nope' is not a valid UUID",
+                "album 'Kingdom of Oblivion': track 'NOX' is an alias of 'N.O.X.'",
+                "album 'Kingdom of Oblivion': 'N.O.X.' is on the tracklist more than once",
+                "album 'Kingdom of Oblivion': track 'Dreamkiller' is not a known song and is not played in any concert",
+                "album 'Kingdom of Oblivion' is in the discography more than once",
+                "album 'Kingdom of Oblivion' has no tracks",
+                "album 'Future Album' has an implausible year 2999",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_performance_metadata_issues() {
+        let mut master = master();
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[1];
+        song.duration = Some("7m".to_string());
+        song.guests.push(Guest::default());
+        song.teases.push(Tease { name: " ".to_string() });
+        song.tags.push(Tag {
+            name: "Acoustic".to_string(),
+        });
+        song.tags.push(Tag {
+            name: "acustic".to_string(),
+        });
+
+        let issues = validate(&master, &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "main set: 'Watersign': invalid duration '7m' (expected M:SS or H:MM:SS)",
+                "main set: 'Watersign': guest with an empty name",
+                "main set: 'Watersign': tease with an empty name",
+                "main set: 'Watersign': unknown version tag 'acustic'",
+            ]
+        );
+        assert_eq!(issues[3].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_lineup_issues() {
+        let lineups = Lineups::from_yml(
+            r#"
+lineup:
+- artist: Motorpsycho
+  from: 01-01-2017
+  to: 31-12-2020
+  member:
+  - name: Bent Sæther
+  - name: ""
+- artist: Motorpsycho
+  from: 01-01-2020
+  member:
+  - name: Bent Sæther
+- artist: Motorpsycho
+  from: 01-01-2010
+  to: 01-01-2009
+- artist: Spidergawd
+  from: 2013-13-01
+  member:
+  - name: Per Borten
+"#,
+        )
+        .unwrap();
+        let issues = validate_lineups(&lineups, &master());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "lineup of 'Motorpsycho' from 01-01-2017: member with an empty name",
+                "lineup of 'Motorpsycho' from 01-01-2020: overlaps the lineup from 01-01-2017",
+                "lineup of 'Motorpsycho' from 01-01-2010: ends before it starts, on 01-01-2009",
+                "lineup of 'Motorpsycho' from 01-01-2010: no members",
+                "lineup of 'Spidergawd' from 2013-13-01: the artist has no concerts",
+                "lineup of 'Spidergawd' from 2013-13-01: invalid start date (expected DD-MM-YYYY)",
+            ]
+        );
+        assert!(has_errors(&issues));
+
+        // A concert outside all lineups is only reported if it has no members of its own
+        let lineups =
+            Lineups::from_yml("lineup:\n- artist: Motorpsycho\n  from: 01-01-2022\n  member:\n  - name: Bent Sæther\n")
+                .unwrap();
+        let issues = validate_lineups(&lineups, &master());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].message, "no lineup for the date of the concert");
+
+        let mut master = master();
+        master.data[0].members.push(Member {
+            name: " ".to_string(),
+            instrument: Some("bass".to_string()),
+        });
+        assert!(validate_lineups(&lineups, &master).is_empty());
+        let messages: Vec<String> = validate(&master, &aliases()).into_iter().map(|i| i.message).collect();
+        assert_eq!(messages, vec!["member with an empty name"]);
+    }
+
+    #[test]
+    fn test_source_issues() {
+        let mut master = master();
+        master.data[0].source = Some("AUD".to_string());
+        master.data[0].sources.source = vec![
+            Source {
+                kind: Some("Soundboard".to_string()),
+                archive_id: Some("".to_string()),
+                ..Default::default()
+            },
+            Source {
+                kind: Some("cassette".to_string()),
+                ..Default::default()
+            },
+            Source::default(),
+        ];
+
+        let issues = validate(&master, &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "the legacy source is ignored, since sources are given",
+                "source 1: empty archive ID",
+                "source 2: unknown kind 'cassette'",
+                "source 3: no kind",
+            ]
+        );
+        assert_eq!(issues[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_unknown_song_and_partial_set_issues() {
+        let mut master = master();
+        let set = &mut master.data[0].sets.set[0];
+        set.songs.as_mut().unwrap()[1].name = "???".to_string();
+        assert!(validate(&master, &aliases()).is_empty());
+
+        // A placeholder cannot be a cover
+        let set = &mut master.data[0].sets.set[0];
+        set.songs.as_mut().unwrap()[1].original_artist = Some(Artist {
+            name: "Hawkwind".to_string(),
+            sort_name: None,
+            mbid: None,
+            country: None,
+        });
+        let messages: Vec<String> = validate(&master, &aliases()).into_iter().map(|i| i.message).collect();
+        assert_eq!(
+            messages,
+            vec!["main set: song 2 is unknown, but has a cover artist or work MBID"]
+        );
+
+        // A partial set may have no songs at all
+        let set = &mut master.data[0].sets.set[0];
+        set.songs = None;
+        assert_eq!(validate(&master, &aliases()).len(), 1);
+        master.data[0].sets.set[0].partial = Some(true);
+        assert!(validate(&master, &aliases()).is_empty());
+    }
+
+    #[test]
+    fn test_medley_issues() {
+        let mut master = master();
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[1];
+        song.medley = vec![
+            Song {
+                name: "Watersign".to_string(),
+                ..Default::default()
+            },
+            Song {
+                name: "Hell, Part 1-3".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert!(validate(&master, &aliases()).is_empty());
+
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[1];
+        song.medley[0].name = " ".to_string();
+        song.medley[1].medley = vec![Song {
+            name: "Un Chien d'Espace".to_string(),
+            ..Default::default()
+        }];
+        let issues = validate(&master, &aliases());
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "main set: part 1 of medley 'Watersign' has no name",
+                "main set: medley 'Hell, Part 1-3' is nested in medley 'Watersign'",
+            ]
+        );
+        assert!(has_errors(&issues));
+
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[1];
+        song.medley.truncate(1);
+        song.medley[0].name = "Watersign".to_string();
+        let issues = validate(&master, &aliases());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].message, "main set: medley 'Watersign' has only one part");
+        assert_eq!(issues[0].severity, Severity::Warning);
+
+        // The duration of a medley cannot be split over its parts
+        let song = &mut master.data[0].sets.set[0].songs.as_mut().unwrap()[1];
+        song.duration = Some("12:00".to_string());
+        let issues = validate(&master, &aliases());
+        assert_eq!(
+            issues[1].message,
+            "main set: duration of medley 'Watersign' is ignored, give the durations of its parts"
+        );
+    }
+}
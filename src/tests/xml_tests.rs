@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::Setlists;
+    use crate::{Setlists, SongAliases};
 
     #[test]
     fn test_full_xml_can_be_parsed() {
@@ -96,4 +96,152 @@ mod tests {
         let result = Setlists::from_xml(invalid_xml);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_song_aliases_with_artist_can_be_parsed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <songs>
+                    <song>
+                        <name>N.O.X.</name>
+                        <alias><name>NOX</name></alias>
+                    </song>
+                    <song>
+                        <name>Hell, Part 1-3</name>
+                        <artist>The Tussler Society</artist>
+                        <alias><name>Hell</name></alias>
+                    </song>
+                </songs>"#;
+
+        let aliases = SongAliases::from_xml(xml).unwrap();
+        assert_eq!(aliases.songs.len(), 2);
+        assert_eq!(aliases.songs[0].artist, None);
+        assert_eq!(aliases.songs[1].artist.as_deref(), Some("The Tussler Society"));
+        assert_eq!(aliases.songs[1].aliases[0].name, "Hell");
+    }
+
+    #[test]
+    fn test_song_with_performance_metadata_can_be_parsed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <setlists>
+                    <setlist status="complete" eventDate="16-05-2021">
+                        <artist><name>Motorpsycho</name></artist>
+                        <venue>
+                            <name>Verkstedhallen</name>
+                            <city><name>Trondheim</name><country><name>Norway</name></country></city>
+                        </venue>
+                        <sets>
+                            <set>
+                                <song>
+                                    <name>The Tower</name>
+                                    <duration>10:12</duration>
+                                    <guest><name>Jenny Hval</name><instrument>vocals</instrument></guest>
+                                    <guest><name>Ståle Storløkken</name></guest>
+                                    <tease><name>The Wheel</name></tease>
+                                    <tag><name>acoustic</name></tag>
+                                </song>
+                                <song><name>NOX</name></song>
+                            </set>
+                        </sets>
+                    </setlist>
+                </setlists>"#;
+
+        let setlists = Setlists::from_xml(xml).unwrap();
+        let songs: Vec<_> = setlists.data[0].songs().collect();
+        assert_eq!(songs[0].duration_seconds(), Some(612));
+        assert_eq!(songs[0].guests.len(), 2);
+        assert_eq!(songs[0].guests[0].instrument.as_deref(), Some("vocals"));
+        assert_eq!(
+            songs[0].guest_names().as_deref(),
+            Some("Jenny Hval (vocals), Ståle Storløkken")
+        );
+        assert_eq!(songs[0].teases[0].name, "The Wheel");
+        assert!(songs[0].has_tag("Acoustic"));
+        assert!(songs[1].guests.is_empty() && songs[1].tags.is_empty());
+
+        // Round trip through YAML, where empty lists are left out
+        let yml = setlists.to_yml().unwrap();
+        assert_eq!(yml.matches("guest:").count(), 1);
+        assert_eq!(Setlists::from_yml(&yml).unwrap(), setlists);
+    }
+
+    #[test]
+    fn test_partial_setlist_can_be_parsed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <setlists>
+                    <setlist status="unconfirmed" eventDate="01-03-1992">
+                        <artist><name>Motorpsycho</name></artist>
+                        <venue>
+                            <name>Rockefeller</name>
+                            <city><name>Oslo</name><country><name>Norway</name></country></city>
+                        </venue>
+                        <sets>
+                            <set>
+                                <uncertainOrder>true</uncertainOrder>
+                                <song><name>Nerve Tattoo</name></song>
+                                <song><name>Unknown</name></song>
+                                <song><name>???</name></song>
+                            </set>
+                            <set><encore>1</encore><song><name>Feedtime</name></song></set>
+                        </sets>
+                    </setlist>
+                </setlists>"#;
+
+        let setlists = Setlists::from_xml(xml).unwrap();
+        let setlist = &setlists.data[0];
+        assert_eq!(setlist.sets.set[0].uncertain_order, Some(true));
+        assert_eq!(setlist.sets.set[0].qualifiers().as_deref(), Some("order uncertain"));
+        assert_eq!(setlist.sets.set[1].qualifiers(), None);
+        let known: Vec<&str> = setlist.known_songs().map(|s| s.name.as_str()).collect();
+        assert_eq!(known, vec!["Nerve Tattoo", "Feedtime"]);
+        assert!(setlist.is_partial());
+
+        let mut complete = setlist.clone();
+        complete.sets.set[0].songs.as_mut().unwrap().truncate(1);
+        assert!(!complete.is_partial());
+        complete.sets.set[1].partial = Some(true);
+        assert!(complete.is_partial());
+    }
+
+    #[test]
+    fn test_medley_can_be_parsed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+                <setlists>
+                    <setlist status="confirmed" eventDate="01-03-1995">
+                        <artist><name>Motorpsycho</name></artist>
+                        <venue>
+                            <name>Rockefeller</name>
+                            <city><name>Oslo</name><country><name>Norway</name></country></city>
+                        </venue>
+                        <sets>
+                            <set>
+                                <song><name>Nerve Tattoo</name></song>
+                                <song>
+                                    <name>Demon Box Medley</name>
+                                    <segue>true</segue>
+                                    <part><name>Feedtime</name><duration>4:10</duration></part>
+                                    <part><name>Sungravy</name></part>
+                                </song>
+                                <song><name>Vortex Surfer</name></song>
+                            </set>
+                        </sets>
+                    </setlist>
+                </setlists>"#;
+
+        let setlists = Setlists::from_xml(xml).unwrap();
+        let setlist = &setlists.data[0];
+        let medley = &setlist.sets.set[0].songs.as_ref().unwrap()[1];
+        assert!(medley.is_medley());
+        assert_eq!(medley.medley_names().as_deref(), Some("Feedtime / Sungravy"));
+        assert_eq!(medley.medley[0].duration.as_deref(), Some("4:10"));
+
+        // The medley is one slot, but its parts are the songs played
+        assert_eq!(setlist.songs().count(), 3);
+        let played: Vec<&str> = setlist.played_songs().map(|s| s.name.as_str()).collect();
+        assert_eq!(played, vec!["Nerve Tattoo", "Feedtime", "Sungravy", "Vortex Surfer"]);
+
+        // Round trip through YAML, where songs that are not medleys have no parts
+        let yml = setlists.to_yml().unwrap();
+        assert_eq!(yml.matches("part:").count(), 1);
+        assert_eq!(Setlists::from_yml(&yml).unwrap(), setlists);
+    }
 }
@@ -0,0 +1,659 @@
+//! Validation of the master data and the song aliases.
+//!
+//! Validation finds mistakes that would otherwise only show up while populating the database (or not at all):
+//! unparseable dates, empty names, duplicate concerts, ambiguous song aliases and malformed MusicBrainz IDs. The song
+//! catalogue, the discography and the band lineups are validated against the master data separately.
+
+use chrono::Datelike;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::setlists::{
+    parse_date, parse_duration, Discography, Lineups, Setlists, SongAliases, SongCatalogue, SOURCE_KINDS, VERSION_TAGS,
+};
+use crate::slug::Slug;
+
+/// How serious a validation issue is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Suspicious, but the data can be used
+    Warning,
+    /// The data is wrong, and populating would fail or store wrong data
+    Error,
+}
+
+/// A problem found in the master data or the song aliases
+///
+/// # Fields
+///
+/// * `severity`: How serious the problem is
+/// * `concert`: The identifier of the concert the problem is in, if any
+/// * `message`: A description of the problem
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub concert: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match &self.concert {
+            Some(concert) => write!(f, "{}: {}: {}", severity, concert, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+/// Collects issues while validating
+#[derive(Default)]
+struct Issues(Vec<Issue>);
+
+impl Issues {
+    fn add(&mut self, severity: Severity, concert: Option<&str>, message: String) {
+        self.0.push(Issue {
+            severity,
+            concert: concert.map(|c| c.to_string()),
+            message,
+        });
+    }
+}
+
+/// Validates the master data and the song aliases
+///
+/// # Arguments
+/// * `master` - The master data
+/// * `aliases` - The song aliases
+///
+/// # Returns
+/// * `Vec<Issue>` - All issues found, in master data order, followed by the issues in the song aliases
+pub fn validate(master: &Setlists, aliases: &SongAliases) -> Vec<Issue> {
+    let mut issues = Issues::default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (i, setlist) in master.data.iter().enumerate() {
+        let id = setlist.identifier();
+        let concert = Some(id.as_str());
+
+        // Populating parses the date strictly in the master data format
+        if chrono::NaiveDate::parse_from_str(&setlist.event_date, "%d-%m-%Y").is_err() {
+            issues.add(
+                Severity::Error,
+                concert,
+                format!("invalid event date '{}' (expected DD-MM-YYYY)", setlist.event_date),
+            );
+        }
+        if let Some(first) = seen.insert(id.clone(), i) {
+            issues.add(
+                Severity::Error,
+                concert,
+                format!(
+                    "duplicate concert (setlists {} and {} have the same artist, date and disambiguation)",
+                    first + 1,
+                    i + 1
+                ),
+            );
+        }
+        for (what, name) in [
+            ("status", &setlist.status),
+            ("artist name", &setlist.artist.name),
+            ("venue name", &setlist.venue.name),
+            ("city name", &setlist.venue.city.name),
+            ("country name", &setlist.venue.city.country.name),
+        ] {
+            if name.trim().is_empty() {
+                issues.add(Severity::Error, concert, format!("empty {}", what));
+            }
+        }
+        if setlist.sets.set.is_empty() {
+            issues.add(Severity::Warning, concert, "no sets".to_string());
+        }
+        for (what, mbid) in [
+            ("setlist", &setlist.mbid),
+            ("artist", &setlist.artist.mbid),
+            ("venue", &setlist.venue.mbid),
+        ] {
+            check_mbid(&mut issues, concert, what, mbid);
+        }
+        if setlist.source.is_some() && !setlist.sources.is_empty() {
+            issues.add(
+                Severity::Warning,
+                concert,
+                "the legacy source is ignored, since sources are given".to_string(),
+            );
+        }
+        for (n, source) in setlist.sources.source.iter().enumerate() {
+            match source.kind.as_deref() {
+                Some(kind) if !SOURCE_KINDS.contains(&kind.to_lowercase().as_str()) => issues.add(
+                    Severity::Warning,
+                    concert,
+                    format!("source {}: unknown kind '{}'", n + 1, kind),
+                ),
+                None => issues.add(Severity::Warning, concert, format!("source {}: no kind", n + 1)),
+                _ => {}
+            }
+            for (what, value) in [
+                ("lineage", &source.lineage),
+                ("taper", &source.taper),
+                ("archive ID", &source.archive_id),
+            ] {
+                if value.as_ref().is_some_and(|v| v.trim().is_empty()) {
+                    issues.add(Severity::Error, concert, format!("source {}: empty {}", n + 1, what));
+                }
+            }
+        }
+        let musicians = setlist
+            .members
+            .iter()
+            .map(|m| ("member", &m.name))
+            .chain(setlist.guests.iter().map(|g| ("guest", &g.name)));
+        for (kind, name) in musicians {
+            if name.trim().is_empty() {
+                issues.add(Severity::Error, concert, format!("{} with an empty name", kind));
+            }
+        }
+
+        for set in setlist.sets.set.iter() {
+            let title = set.title().unwrap_or_else(|| "main set".to_string());
+            let songs = set.songs.as_deref().unwrap_or_default();
+            // A set known to be incomplete may have no known songs at all
+            if songs.is_empty() && set.partial != Some(true) {
+                issues.add(Severity::Warning, concert, format!("{}: no songs", title));
+            }
+            for (n, song) in songs.iter().enumerate() {
+                if song.name.trim().is_empty() {
+                    issues.add(
+                        Severity::Error,
+                        concert,
+                        format!("{}: song {} has no name", title, n + 1),
+                    );
+                }
+                if song.medley.len() == 1 {
+                    issues.add(
+                        Severity::Warning,
+                        concert,
+                        format!("{}: medley '{}' has only one part", title, song.name),
+                    );
+                }
+                if song.is_medley() && song.duration.is_some() {
+                    issues.add(
+                        Severity::Warning,
+                        concert,
+                        format!(
+                            "{}: duration of medley '{}' is ignored, give the durations of its parts",
+                            title, song.name
+                        ),
+                    );
+                }
+                for (m, part) in song.medley.iter().enumerate() {
+                    if part.name.trim().is_empty() {
+                        issues.add(
+                            Severity::Error,
+                            concert,
+                            format!("{}: part {} of medley '{}' has no name", title, m + 1, song.name),
+                        );
+                    }
+                    if part.is_medley() {
+                        issues.add(
+                            Severity::Error,
+                            concert,
+                            format!("{}: medley '{}' is nested in medley '{}'", title, part.name, song.name),
+                        );
+                    }
+                    let what = format!("{}: '{}' in medley '{}'", title, part.name, song.name);
+                    check_mbid(&mut issues, concert, &format!("{} work", what), &part.work_mbid);
+                    check_mbid(
+                        &mut issues,
+                        concert,
+                        &format!("{} recording", what),
+                        &part.recording_mbid,
+                    );
+                }
+                if song.is_unknown() && (song.original_artist.is_some() || song.work_mbid.is_some()) {
+                    issues.add(
+                        Severity::Warning,
+                        concert,
+                        format!(
+                            "{}: song {} is unknown, but has a cover artist or work MBID",
+                            title,
+                            n + 1
+                        ),
+                    );
+                }
+                if song.original_artist.as_ref().is_some_and(|a| a.name.trim().is_empty()) {
+                    issues.add(
+                        Severity::Error,
+                        concert,
+                        format!("{}: cover '{}' has an empty artist name", title, song.name),
+                    );
+                }
+                let what = format!("{}: '{}'", title, song.name);
+                check_mbid(&mut issues, concert, &format!("{} work", what), &song.work_mbid);
+                check_mbid(
+                    &mut issues,
+                    concert,
+                    &format!("{} recording", what),
+                    &song.recording_mbid,
+                );
+                if let Some(artist) = &song.original_artist {
+                    check_mbid(&mut issues, concert, &format!("{} cover artist", what), &artist.mbid);
+                }
+                if let Some(duration) = song.duration.as_ref().filter(|d| parse_duration(d).is_none()) {
+                    issues.add(
+                        Severity::Error,
+                        concert,
+                        format!("{}: invalid duration '{}' (expected M:SS or H:MM:SS)", what, duration),
+                    );
+                }
+                let names = song
+                    .guests
+                    .iter()
+                    .map(|g| ("guest", &g.name))
+                    .chain(song.teases.iter().map(|t| ("tease", &t.name)))
+                    .chain(song.tags.iter().map(|t| ("tag", &t.name)));
+                for (kind, name) in names {
+                    if name.trim().is_empty() {
+                        issues.add(
+                            Severity::Error,
+                            concert,
+                            format!("{}: {} with an empty name", what, kind),
+                        );
+                    }
+                }
+                for tag in song.tags.iter().filter(|t| !t.name.trim().is_empty()) {
+                    if !VERSION_TAGS.contains(&tag.name.to_lowercase().as_str()) {
+                        issues.add(
+                            Severity::Warning,
+                            concert,
+                            format!("{}: unknown version tag '{}'", what, tag.name),
+                        );
+                    }
+                }
+            }
+            if songs.last().is_some_and(|s| s.segue.unwrap_or(false)) {
+                issues.add(
+                    Severity::Warning,
+                    concert,
+                    format!(
+                        "{}: last song '{}' segues into nothing",
+                        title,
+                        songs.last().unwrap().name
+                    ),
+                );
+            }
+        }
+    }
+
+    // Every song title and alias must resolve to exactly one song
+    let mut titles: HashMap<String, &str> = HashMap::new();
+    for song in aliases.songs.iter() {
+        if song.name.trim().is_empty() {
+            issues.add(Severity::Error, None, "song alias entry with an empty name".to_string());
+        }
+        if song.artist.as_ref().is_some_and(|a| a.trim().is_empty()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("song '{}' has an empty artist name", song.name),
+            );
+        }
+        check_mbid(
+            &mut issues,
+            None,
+            &format!("song '{}' work", song.name),
+            &song.work_mbid,
+        );
+        for name in std::iter::once(&song.name).chain(song.aliases.iter().map(|a| &a.name)) {
+            if let Some(other) = titles.insert(name.slug(), &song.name) {
+                if other != song.name {
+                    issues.add(
+                        Severity::Error,
+                        None,
+                        format!("'{}' is a title or alias of both '{}' and '{}'", name, other, song.name),
+                    );
+                } else if name != &song.name {
+                    issues.add(
+                        Severity::Warning,
+                        None,
+                        format!("'{}' is listed more than once for '{}'", name, song.name),
+                    );
+                }
+            }
+        }
+    }
+
+    issues.0
+}
+
+/// Validates the song catalogue against the master data and the song aliases
+///
+/// # Arguments
+/// * `catalogue` - The song catalogue
+/// * `master` - The master data
+/// * `aliases` - The song aliases
+///
+/// # Returns
+/// * `Vec<Issue>` - All issues found, in catalogue order; mismatched cover artists are reported for the concert they
+///   are in
+pub fn validate_catalogue(catalogue: &SongCatalogue, master: &Setlists, aliases: &SongAliases) -> Vec<Issue> {
+    let mut issues = Issues::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let played: HashSet<String> = master
+        .data
+        .iter()
+        .flat_map(|s| s.played_songs())
+        .map(|song| aliases.canonical_name(&song.name).slug())
+        .collect();
+    let known: HashSet<String> = aliases.songs.iter().map(|s| s.name.slug()).collect();
+    let this_year = chrono::Local::now().year();
+
+    for song in catalogue.songs.iter() {
+        if song.name.trim().is_empty() {
+            issues.add(
+                Severity::Error,
+                None,
+                "song catalogue entry with an empty name".to_string(),
+            );
+            continue;
+        }
+        let canonical = aliases.canonical_name(&song.name);
+        if !seen.insert(canonical.slug()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("song '{}' is in the song catalogue more than once", canonical),
+            );
+        }
+        if canonical != song.name {
+            issues.add(
+                Severity::Warning,
+                None,
+                format!("song catalogue entry '{}' is an alias of '{}'", song.name, canonical),
+            );
+        }
+        if !played.contains(&canonical.slug()) && !known.contains(&canonical.slug()) {
+            issues.add(
+                Severity::Warning,
+                None,
+                format!("song catalogue entry '{}' is not played in any concert", song.name),
+            );
+        }
+        if song.composers.iter().any(|c| c.name.trim().is_empty()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("song '{}' has a composer with an empty name", song.name),
+            );
+        }
+        if song.original_artist.as_ref().is_some_and(|a| a.trim().is_empty()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("song '{}' has an empty original artist", song.name),
+            );
+        }
+        if let Some(year) = song.release_year.filter(|y| !(1900..=this_year).contains(y)) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("song '{}' has an implausible release year {}", song.name, year),
+            );
+        }
+        if let Some(duration) = song.duration.as_ref().filter(|d| parse_duration(d).is_none()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!(
+                    "song '{}' has an invalid duration '{}' (expected M:SS or H:MM:SS)",
+                    song.name, duration
+                ),
+            );
+        }
+    }
+
+    // Covers must agree with the original artist in the catalogue
+    for setlist in master.data.iter() {
+        let id = setlist.identifier();
+        for song in setlist.played_songs() {
+            let Some(info) = catalogue.get(&aliases.canonical_name(&song.name)) else {
+                continue;
+            };
+            match (&song.original_artist, &info.original_artist) {
+                (Some(cover), Some(original)) if cover.name.slug() != original.slug() => issues.add(
+                    Severity::Warning,
+                    Some(&id),
+                    format!(
+                        "'{}' is a cover of {}, but the song catalogue says it is by {}",
+                        song.name, cover.name, original
+                    ),
+                ),
+                (Some(cover), None) => issues.add(
+                    Severity::Warning,
+                    Some(&id),
+                    format!(
+                        "'{}' is a cover of {}, but the song catalogue has no original artist",
+                        song.name, cover.name
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    issues.0
+}
+
+/// Validates the discography against the master data and the song aliases
+///
+/// # Arguments
+/// * `discography` - The discography
+/// * `master` - The master data
+/// * `aliases` - The song aliases
+///
+/// # Returns
+/// * `Vec<Issue>` - All issues found, in album order
+pub fn validate_discography(discography: &Discography, master: &Setlists, aliases: &SongAliases) -> Vec<Issue> {
+    let mut issues = Issues::default();
+    let mut seen: HashSet<String> = HashSet::new();
+    let played: HashSet<String> = master
+        .data
+        .iter()
+        .flat_map(|s| s.played_songs())
+        .map(|song| aliases.canonical_name(&song.name).slug())
+        .collect();
+    let known: HashSet<String> = aliases.songs.iter().map(|s| s.name.slug()).collect();
+    let this_year = chrono::Local::now().year();
+
+    for album in discography.albums.iter() {
+        if album.title.trim().is_empty() {
+            issues.add(Severity::Error, None, "album with an empty title".to_string());
+            continue;
+        }
+        if !seen.insert(album.slug()) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("album '{}' is in the discography more than once", album.title),
+            );
+        }
+        if let Some(year) = album.year.filter(|y| !(1900..=this_year).contains(y)) {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("album '{}' has an implausible year {}", album.title, year),
+            );
+        }
+        check_mbid(&mut issues, None, &format!("album '{}'", album.title), &album.mbid);
+        if album.tracks.is_empty() {
+            issues.add(
+                Severity::Warning,
+                None,
+                format!("album '{}' has no tracks", album.title),
+            );
+        }
+
+        let mut tracks: HashSet<String> = HashSet::new();
+        for track in album.tracks.iter() {
+            let canonical = aliases.canonical_name(&track.name);
+            if track.name.trim().is_empty() {
+                issues.add(
+                    Severity::Error,
+                    None,
+                    format!("album '{}' has a track with an empty name", album.title),
+                );
+                continue;
+            }
+            if !tracks.insert(canonical.slug()) {
+                issues.add(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "album '{}': '{}' is on the tracklist more than once",
+                        album.title, canonical
+                    ),
+                );
+            }
+            if canonical != track.name {
+                issues.add(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "album '{}': track '{}' is an alias of '{}'",
+                        album.title, track.name, canonical
+                    ),
+                );
+            } else if !known.contains(&canonical.slug()) && !played.contains(&canonical.slug()) {
+                issues.add(
+                    Severity::Warning,
+                    None,
+                    format!(
+                        "album '{}': track '{}' is not a known song and is not played in any concert",
+                        album.title, track.name
+                    ),
+                );
+            }
+        }
+    }
+
+    issues.0
+}
+
+/// Validates the band lineups against the master data
+///
+/// # Arguments
+/// * `lineups` - The band lineups
+/// * `master` - The master data
+///
+/// # Returns
+/// * `Vec<Issue>` - All issues found in the lineups, in lineup order, followed by the concerts without a lineup
+pub fn validate_lineups(lineups: &Lineups, master: &Setlists) -> Vec<Issue> {
+    let mut issues = Issues::default();
+    let artists: HashSet<String> = master.data.iter().map(|s| s.artist.name.slug()).collect();
+
+    for (i, lineup) in lineups.lineups.iter().enumerate() {
+        let what = format!("lineup of '{}' from {}", lineup.artist, lineup.from);
+        if lineup.artist.trim().is_empty() {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("lineup {} has an empty artist name", i + 1),
+            );
+        } else if !artists.contains(&lineup.artist.slug()) {
+            issues.add(Severity::Warning, None, format!("{}: the artist has no concerts", what));
+        }
+        let from = parse_date(&lineup.from);
+        if from.is_none() {
+            issues.add(
+                Severity::Error,
+                None,
+                format!("{}: invalid start date (expected DD-MM-YYYY)", what),
+            );
+        }
+        match lineup.to.as_deref().map(|to| (to, parse_date(to))) {
+            Some((to, None)) => issues.add(
+                Severity::Error,
+                None,
+                format!("{}: invalid end date '{}' (expected DD-MM-YYYY)", what, to),
+            ),
+            Some((to, Some(end))) if from.is_some_and(|from| end < from) => issues.add(
+                Severity::Error,
+                None,
+                format!("{}: ends before it starts, on {}", what, to),
+            ),
+            _ => {}
+        }
+        if lineup.members.is_empty() {
+            issues.add(Severity::Warning, None, format!("{}: no members", what));
+        }
+        if lineup.members.iter().any(|m| m.name.trim().is_empty()) {
+            issues.add(Severity::Error, None, format!("{}: member with an empty name", what));
+        }
+
+        // Lineups of the same artist must not overlap, or a concert would have two lineups
+        for other in lineups.lineups[..i]
+            .iter()
+            .filter(|o| o.artist.slug() == lineup.artist.slug())
+        {
+            let overlap = match (from, other.from_date()) {
+                (Some(from), Some(other_from)) => {
+                    lineup.to_date().is_none_or(|to| other_from <= to) && other.to_date().is_none_or(|to| from <= to)
+                }
+                _ => false,
+            };
+            if overlap {
+                issues.add(
+                    Severity::Error,
+                    None,
+                    format!("{}: overlaps the lineup from {}", what, other.from),
+                );
+            }
+        }
+    }
+
+    // Concerts of an artist with lineups should be covered by one, or have their own members
+    let with_lineups: HashSet<String> = lineups.lineups.iter().map(|l| l.artist.slug()).collect();
+    for setlist in master.data.iter() {
+        if with_lineups.contains(&setlist.artist.name.slug())
+            && setlist.members.is_empty()
+            && lineups.lineup_for(setlist).is_none()
+        {
+            issues.add(
+                Severity::Warning,
+                Some(&setlist.identifier()),
+                "no lineup for the date of the concert".to_string(),
+            );
+        }
+    }
+
+    issues.0
+}
+
+/// Adds an error if a MusicBrainz ID is given but is not a UUID
+fn check_mbid(issues: &mut Issues, concert: Option<&str>, what: &str, mbid: &Option<String>) {
+    if let Some(mbid) = mbid.as_ref().filter(|mbid| !is_mbid(mbid)) {
+        issues.add(
+            Severity::Error,
+            concert,
+            format!("{} MBID '{}' is not a valid UUID", what, mbid),
+        );
+    }
+}
+
+/// Returns true if a string is a MusicBrainz ID: a UUID in its hyphenated form, e.g.
+/// `0a7e4ee5-4b4e-4b3a-8d29-7c1ab1b4e4d7`
+pub fn is_mbid(mbid: &str) -> bool {
+    mbid.len() == 36
+        && mbid.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Returns true if any of the issues is an error
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}